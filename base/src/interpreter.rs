@@ -0,0 +1,478 @@
+use module::{Config, Instruction, InstructionParameter, Method, ParameterType};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::time::Instant;
+
+/// Calls a method of a module by name, see `dispatch_source`.
+/// Fails with a message if the method is unknown or an argument has the wrong type.
+pub type Dispatch = fn(&str, Vec<Value>) -> Result<Value, String>;
+
+/// Runs instructions directly, without generating and compiling a crate.
+/// Methods are called through the `Dispatch` registered for their module, or return their
+/// `mock` if the module has none.
+#[derive(Debug, Default, Clone)]
+pub struct Interpreter {
+    modules: HashMap<String, Dispatch>,
+}
+
+impl Interpreter {
+    /// Register the `call` function of the generated `dispatch` module of a module
+    pub fn register(&mut self, module: &str, dispatch: Dispatch) {
+        self.modules.insert(module.to_string(), dispatch);
+    }
+
+    pub fn is_registered(&self, module: &str) -> bool {
+        self.modules.contains_key(module)
+    }
+
+    /// Start a run with the given variables, to be stepped through instruction by instruction
+    pub fn start(&self, inputs: HashMap<String, Value>) -> Run<'_> {
+        Run {
+            interpreter: self,
+            variables: inputs,
+            position: 0,
+            trace: None,
+        }
+    }
+
+    /// Start a run that records every call it makes, see `Run::trace`
+    pub fn start_traced(&self, inputs: HashMap<String, Value>) -> Run<'_> {
+        Run {
+            trace: Some(Vec::new()),
+            ..self.start(inputs)
+        }
+    }
+
+    /// Run the instructions until one of them returns or exits
+    pub fn run(
+        &self,
+        instructions: &[Instruction],
+        inputs: HashMap<String, Value>,
+    ) -> Result<Outcome, InterpretError> {
+        self.start(inputs).run(instructions)
+    }
+
+    /// Run the instructions like `run`, and return the calls they made,
+    /// including the call that failed if the run fails
+    pub fn run_traced(
+        &self,
+        instructions: &[Instruction],
+        inputs: HashMap<String, Value>,
+    ) -> (Result<Outcome, InterpretError>, Vec<TraceEntry>) {
+        let mut run = self.start_traced(inputs);
+        let outcome = run.run(instructions);
+        (outcome, run.into_trace())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum Outcome {
+    Return(Value),
+    Exit(i32),
+    /// Every instruction was run without returning
+    Finished,
+}
+
+/// A call made by an interpreter run
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TraceEntry {
+    /// The index of the instruction that made the call
+    pub instruction: usize,
+    pub module: String,
+    pub method: String,
+    pub inputs: HashMap<String, Value>,
+    /// `None` if the call failed
+    pub output: Option<Value>,
+    pub duration_us: u64,
+}
+
+/// The state of an interpreter run: the variables set by the instructions run so far
+pub struct Run<'a> {
+    interpreter: &'a Interpreter,
+    variables: HashMap<String, Value>,
+    /// The number of instructions run so far
+    position: usize,
+    trace: Option<Vec<TraceEntry>>,
+}
+
+impl<'a> Run<'a> {
+    pub fn variables(&self) -> &HashMap<String, Value> {
+        &self.variables
+    }
+
+    /// The calls made so far, or `None` if the run was not started with `start_traced`
+    pub fn trace(&self) -> Option<&[TraceEntry]> {
+        self.trace.as_ref().map(|t| &t[..])
+    }
+
+    pub fn into_trace(self) -> Vec<TraceEntry> {
+        self.trace.unwrap_or_default()
+    }
+
+    /// Run the remaining instructions until one of them returns or exits
+    pub fn run(&mut self, instructions: &[Instruction]) -> Result<Outcome, InterpretError> {
+        for instruction in instructions {
+            if let Some(outcome) = self.step(instruction)? {
+                return Ok(outcome);
+            }
+        }
+        Ok(Outcome::Finished)
+    }
+
+    /// Run a single instruction. Returns the outcome if the instruction returns or exits.
+    pub fn step(&mut self, instruction: &Instruction) -> Result<Option<Outcome>, InterpretError> {
+        self.position += 1;
+        match instruction {
+            Instruction::CallModule {
+                config,
+                method,
+                parameters,
+                out_variable_name,
+            } => {
+                let value = self.call(config, method, parameters)?;
+                self.variables.insert(out_variable_name.clone(), value);
+                Ok(None)
+            }
+            Instruction::Return(parameter) => Ok(Some(Outcome::Return(self.value(parameter)?))),
+            Instruction::Exit(parameter) => {
+                let value = self.value(parameter)?;
+                match value.as_i64() {
+                    // The code fits in an `i32` if converting it back gives the same value
+                    Some(code) if i64::from(code as i32) == code => {
+                        Ok(Some(Outcome::Exit(code as i32)))
+                    }
+                    _ => Err(InterpretError::InvalidExitCode(value)),
+                }
+            }
+        }
+    }
+
+    /// The value of a parameter, looking up variables
+    pub fn value(&self, parameter: &InstructionParameter) -> Result<Value, InterpretError> {
+        Ok(match parameter {
+            InstructionParameter::Variable(name) => self
+                .variables
+                .get(name)
+                .cloned()
+                .ok_or_else(|| InterpretError::UnknownVariable(name.clone()))?,
+            InstructionParameter::String(value) => Value::String(value.clone()),
+            InstructionParameter::Number(value) => Value::from(*value),
+            InstructionParameter::Float(value) => Value::from(f64::from(*value)),
+        })
+    }
+
+    fn call(
+        &mut self,
+        config: &Config,
+        method: &str,
+        parameters: &[(String, InstructionParameter)],
+    ) -> Result<Value, InterpretError> {
+        let method = config
+            .methods
+            .iter()
+            .find(|m| m.name == method)
+            .ok_or_else(|| InterpretError::UnknownMethod {
+                module: config.name.clone(),
+                method: method.to_string(),
+            })?;
+        let mut args = Vec::with_capacity(method.input.len());
+        for input in &method.input {
+            let parameter = parameters
+                .iter()
+                .find(|p| p.0 == input.name)
+                .ok_or_else(|| InterpretError::MissingArgument {
+                    method: method.name.clone(),
+                    argument: input.name.clone(),
+                })?;
+            let value = self.value(&parameter.1)?;
+            let valid = match input.value_type {
+                ParameterType::Numeric(_) => value.is_number(),
+                ParameterType::String(_) => value.is_string(),
+                _ => true,
+            };
+            if !valid {
+                return Err(InterpretError::InvalidArgument {
+                    method: method.name.clone(),
+                    argument: input.name.clone(),
+                    value,
+                });
+            }
+            args.push(value);
+        }
+
+        let started = Instant::now();
+        let result = self.dispatch(config, method, args.clone());
+        if let Some(trace) = &mut self.trace {
+            let elapsed = started.elapsed();
+            trace.push(TraceEntry {
+                instruction: self.position - 1,
+                module: config.name.clone(),
+                method: method.name.clone(),
+                inputs: method
+                    .input
+                    .iter()
+                    .map(|i| i.name.clone())
+                    .zip(args)
+                    .collect(),
+                output: result.as_ref().ok().cloned(),
+                duration_us: elapsed.as_secs() * 1_000_000 + u64::from(elapsed.subsec_micros()),
+            });
+        }
+        result
+    }
+
+    fn dispatch(
+        &self,
+        config: &Config,
+        method: &Method,
+        args: Vec<Value>,
+    ) -> Result<Value, InterpretError> {
+        let dispatch = match self.interpreter.modules.get(&config.name) {
+            Some(dispatch) => *dispatch,
+            None => {
+                return method
+                    .mock
+                    .clone()
+                    .ok_or_else(|| InterpretError::NotRegistered(config.name.clone()))
+            }
+        };
+        if method.is_async {
+            return Err(InterpretError::Async(method.name.clone()));
+        }
+        dispatch(&method.name, args).map_err(|message| InterpretError::Dispatch {
+            method: method.name.clone(),
+            message,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterpretError {
+    UnknownVariable(String),
+    UnknownMethod { module: String, method: String },
+    MissingArgument { method: String, argument: String },
+    /// The value does not match the type of the argument in the module.json
+    InvalidArgument {
+        method: String,
+        argument: String,
+        value: Value,
+    },
+    /// The module has no registered `Dispatch`, and the method has no mock
+    NotRegistered(String),
+    Async(String),
+    /// The `Dispatch` of the module failed
+    Dispatch { method: String, message: String },
+    InvalidExitCode(Value),
+}
+
+impl fmt::Display for InterpretError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InterpretError::UnknownVariable(name) => write!(fmt, "Unknown variable {:?}", name),
+            InterpretError::UnknownMethod { module, method } => {
+                write!(fmt, "Module {:?} has no method {:?}", module, method)
+            }
+            InterpretError::MissingArgument { method, argument } => {
+                write!(fmt, "Missing argument {:?} of {:?}", argument, method)
+            }
+            InterpretError::InvalidArgument {
+                method,
+                argument,
+                value,
+            } => write!(
+                fmt,
+                "Invalid value {} for argument {:?} of {:?}",
+                value, argument, method
+            ),
+            InterpretError::NotRegistered(module) => write!(
+                fmt,
+                "Module {:?} cannot be interpreted, it has no dispatch function and no mock",
+                module
+            ),
+            InterpretError::Async(method) => {
+                write!(fmt, "{:?} is async, and cannot be interpreted", method)
+            }
+            InterpretError::Dispatch { method, message } => {
+                write!(fmt, "Could not call {:?}: {}", method, message)
+            }
+            InterpretError::InvalidExitCode(value) => write!(fmt, "Invalid exit code {}", value),
+        }
+    }
+}
+
+impl Error for InterpretError {
+    fn description(&self) -> &str {
+        match self {
+            InterpretError::UnknownVariable(_) => "Unknown variable",
+            InterpretError::UnknownMethod { .. } => "Unknown method",
+            InterpretError::MissingArgument { .. } => "Missing argument",
+            InterpretError::InvalidArgument { .. } => "Invalid argument",
+            InterpretError::NotRegistered(_) => "Module cannot be interpreted",
+            InterpretError::Async(_) => "Async methods cannot be interpreted",
+            InterpretError::Dispatch { .. } => "Method could not be called",
+            InterpretError::InvalidExitCode(_) => "Invalid exit code",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use module::dispatch_source;
+    use std::rc::Rc;
+
+    const CALCULATOR: &str = r#"{
+        "name": "calculator",
+        "description": "Calculates things",
+        "methods": [
+            {
+                "name": "add",
+                "description": "Adds a and b",
+                "input": [
+                    { "name": "a", "description": "A", "type": { "type": "numeric" } },
+                    { "name": "b", "description": "B", "type": { "type": "numeric" } }
+                ],
+                "output": { "name": "sum", "description": "a + b", "type": { "type": "numeric" } },
+                "mock": 7
+            },
+            {
+                "name": "divide",
+                "description": "Divides a by b",
+                "input": [
+                    { "name": "a", "description": "A", "type": { "type": "numeric" } },
+                    { "name": "b", "description": "B", "type": { "type": "numeric" } }
+                ],
+                "output": []
+            }
+        ]
+    }"#;
+
+    fn calculator() -> Rc<Config> {
+        Rc::new(::serde_json::from_str(CALCULATOR).unwrap())
+    }
+
+    fn dispatch(method: &str, args: Vec<Value>) -> Result<Value, String> {
+        let (a, b) = (args[0].as_i64().unwrap(), args[1].as_i64().unwrap());
+        match method {
+            "add" => Ok(Value::from(a + b)),
+            "divide" if b == 0 => Err("Division by zero".to_string()),
+            "divide" => Ok(Value::from(a / b)),
+            _ => Err(format!("Unknown method {:?}", method)),
+        }
+    }
+
+    fn call(config: &Rc<Config>, method: &str, a: InstructionParameter) -> Instruction {
+        Instruction::CallModule {
+            config: config.clone(),
+            method: method.to_string(),
+            parameters: vec![
+                ("a".to_string(), a),
+                ("b".to_string(), InstructionParameter::Variable("b".to_string())),
+            ],
+            out_variable_name: "out".to_string(),
+        }
+    }
+
+    fn inputs(b: i32) -> HashMap<String, Value> {
+        vec![("b".to_string(), Value::from(b))].into_iter().collect()
+    }
+
+    fn result() -> Instruction {
+        Instruction::Return(InstructionParameter::Variable("out".to_string()))
+    }
+
+    #[test]
+    fn unregistered_modules_return_their_mock() {
+        let config = calculator();
+        let interpreter = Interpreter::default();
+        let add = call(&config, "add", InstructionParameter::Number(1));
+        let outcome = interpreter.run(&[add, result()], inputs(2));
+        assert_eq!(Ok(Outcome::Return(Value::from(7))), outcome);
+
+        let divide = call(&config, "divide", InstructionParameter::Number(1));
+        let outcome = interpreter.run(&[divide, result()], inputs(2));
+        assert_eq!(Err(InterpretError::NotRegistered("calculator".to_string())), outcome);
+    }
+
+    #[test]
+    fn registered_modules_are_called() {
+        let config = calculator();
+        let mut interpreter = Interpreter::default();
+        interpreter.register("calculator", dispatch);
+        let add = call(&config, "add", InstructionParameter::Number(1));
+        let (outcome, trace) = interpreter.run_traced(&[add, result()], inputs(2));
+        assert_eq!(Ok(Outcome::Return(Value::from(3))), outcome);
+        assert_eq!(1, trace.len());
+        assert_eq!("add", trace[0].method);
+        assert_eq!(Some(&Value::from(2)), trace[0].inputs.get("b"));
+        assert_eq!(Some(Value::from(3)), trace[0].output);
+    }
+
+    #[test]
+    fn failed_calls_are_errors() {
+        let config = calculator();
+        let mut interpreter = Interpreter::default();
+        interpreter.register("calculator", dispatch);
+        let divide = call(&config, "divide", InstructionParameter::Number(1));
+        let (outcome, trace) = interpreter.run_traced(&[divide, result()], inputs(0));
+        assert_eq!(
+            Err(InterpretError::Dispatch {
+                method: "divide".to_string(),
+                message: "Division by zero".to_string(),
+            }),
+            outcome
+        );
+        assert_eq!(1, trace.len());
+        assert_eq!(None, trace[0].output);
+    }
+
+    #[test]
+    fn arguments_need_the_type_of_their_input() {
+        let config = calculator();
+        let add = call(&config, "add", InstructionParameter::String("one".to_string()));
+        let (outcome, trace) = Interpreter::default().run_traced(&[add, result()], inputs(2));
+        assert_eq!(
+            Err(InterpretError::InvalidArgument {
+                method: "add".to_string(),
+                argument: "a".to_string(),
+                value: Value::from("one"),
+            }),
+            outcome
+        );
+        assert!(trace.is_empty());
+    }
+
+    #[test]
+    fn exit_codes_are_integers() {
+        let interpreter = Interpreter::default();
+        let exit = Instruction::Exit(InstructionParameter::Number(3));
+        assert_eq!(Ok(Outcome::Exit(3)), interpreter.run(&[exit], HashMap::new()));
+
+        let exit = Instruction::Exit(InstructionParameter::Float(1.5));
+        let outcome = interpreter.run(&[exit], HashMap::new());
+        assert_eq!(Err(InterpretError::InvalidExitCode(Value::from(1.5))), outcome);
+
+        let exit = Instruction::Exit(InstructionParameter::Variable("b".to_string()));
+        let outcome = interpreter.run(&[exit], inputs(-1));
+        assert_eq!(Ok(Outcome::Exit(-1)), outcome);
+
+        let exit = Instruction::Exit(InstructionParameter::Variable("code".to_string()));
+        let code = vec![("code".to_string(), Value::from(1i64 << 32))];
+        let outcome = interpreter.run(&[exit], code.into_iter().collect());
+        assert_eq!(Err(InterpretError::InvalidExitCode(Value::from(1i64 << 32))), outcome);
+    }
+
+    #[test]
+    fn dispatch_source_returns_errors() {
+        let source = dispatch_source(&calculator());
+        let signature = "pub fn call(method: &str, args: Vec<Value>) -> Result<Value, String>";
+        assert!(source.contains(signature));
+        assert!(source.contains("serde_json::to_value(super::add(a, b))"));
+        assert!(source.contains("super::divide(a, b);\n            Ok(Value::Null)"));
+        assert!(source.contains("_ => Err(format!(\"Unknown method {:?}\", method)),"));
+        assert!(!source.contains("panic!"));
+        assert!(!source.contains(".expect("));
+    }
+}
@@ -4,9 +4,11 @@ extern crate regex;
 extern crate serde;
 extern crate serde_json;
 
+mod interpreter;
 mod module;
 
+pub use interpreter::{Dispatch, InterpretError, Interpreter, Outcome, Run, TraceEntry};
 pub use module::{
-    Builder, Config, Input, Instruction, InstructionParameter, Method, NumericConstraint, Output,
-    ParameterType, StringConstraint,
+    dispatch_source, Builder, Config, Input, Instruction, InstructionParameter, Method,
+    NumericConstraint, Output, ParameterType, StringConstraint,
 };
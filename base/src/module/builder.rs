@@ -1,4 +1,4 @@
-use super::config::Config;
+use super::config::{Config, Method};
 use std::collections::HashMap;
 use std::rc::Rc;
 
@@ -76,25 +76,27 @@ pub enum Instruction {
 }
 
 impl Instruction {
-    pub fn build(&self) -> String {
+    /// Whether this instruction calls an async method, and can only be used in an `async fn`
+    pub fn is_async(&self) -> bool {
+        match self {
+            Instruction::CallModule { config, method, .. } => config
+                .methods
+                .iter()
+                .any(|m| &m.name == method && m.is_async),
+            _ => false,
+        }
+    }
+
+    /// The generated code of each argument of this instruction, by name, in the order they are generated
+    pub fn arguments(&self) -> Vec<(String, String)> {
         match self {
             Instruction::CallModule {
                 config,
                 method,
                 parameters,
-                out_variable_name,
+                ..
             } => {
-                let method = config
-                    .methods
-                    .iter()
-                    .find(|m| &m.name == method)
-                    .unwrap_or_else(|| {
-                        panic!(
-                            "Could not find method {:?}, available: {:?}",
-                            method,
-                            config.methods.iter().map(|m| &m.name).collect::<Vec<_>>()
-                        )
-                    });
+                let method = find_method(config, method);
                 let mut args = Vec::with_capacity(method.input.len());
                 for arg in &method.input {
                     let value = parameters
@@ -107,24 +109,54 @@ impl Instruction {
                                 parameters.iter().map(|p| &p.0).collect::<Vec<_>>()
                             )
                         });
-                    args.push(value.1.to_string(true));
+                    args.push((arg.name.clone(), value.1.to_string(true)));
                 }
+                args
+            }
+            Instruction::Exit(param) => vec![("code".to_string(), param.to_string(false))],
+            Instruction::Return(param) => vec![("value".to_string(), param.to_string(false))],
+        }
+    }
+
+    pub fn build(&self) -> String {
+        let mut args = self.arguments().into_iter().map(|a| a.1);
+        match self {
+            Instruction::CallModule {
+                config,
+                method,
+                out_variable_name,
+                ..
+            } => {
+                let method = find_method(config, method);
                 format!(
-                    "    let {} = {}::{}({});\n",
+                    "    let {} = {}::{}({}){};\n",
                     out_variable_name.to_string(),
                     config.name,
                     method.name,
-                    args.join(", ")
+                    args.collect::<Vec<_>>().join(", "),
+                    if method.is_async { ".await" } else { "" }
                 )
             }
-            Instruction::Exit(param) => {
-                format!("    std::process::exit({});\n", param.to_string(true))
-            }
-            Instruction::Return(param) => format!("    {}\n", param.to_string(false)),
+            Instruction::Exit(_) => format!("    std::process::exit({});\n", args.next().unwrap()),
+            Instruction::Return(_) => format!("    {}\n", args.next().unwrap()),
         }
     }
 }
 
+fn find_method<'a>(config: &'a Config, method: &str) -> &'a Method {
+    config
+        .methods
+        .iter()
+        .find(|m| m.name == method)
+        .unwrap_or_else(|| {
+            panic!(
+                "Could not find method {:?}, available: {:?}",
+                method,
+                config.methods.iter().map(|m| &m.name).collect::<Vec<_>>()
+            )
+        })
+}
+
 #[derive(Debug)]
 pub enum InstructionParameter {
     Variable(String),
@@ -143,7 +175,86 @@ impl InstructionParameter {
             },
             InstructionParameter::String(value) => format!("{:?}", value),
             InstructionParameter::Number(value) => value.to_string(),
-            InstructionParameter::Float(value) => value.to_string(),
+            // `Debug` keeps the `.0` of whole numbers, and the suffix keeps the literal an `f32`
+            InstructionParameter::Float(value) => format!("{:?}f32", value),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_calls_the_module() {
+        let config = Rc::new(Config::from_path("../modules/adder"));
+        let mut builder = Builder::default();
+        builder.add_module(config.clone());
+        builder.add_instruction(Instruction::CallModule {
+            config: config.clone(),
+            method: "add".to_string(),
+            parameters: vec![
+                ("a".to_string(), InstructionParameter::Number(5)),
+                ("b".to_string(), InstructionParameter::Number(10)),
+            ],
+            out_variable_name: "out".to_string(),
+        });
+        builder.add_instruction(Instruction::Exit(InstructionParameter::Variable(
+            "out".to_string(),
+        )));
+
+        let files = builder.build();
+        assert_eq!(
+            r#"extern crate adder;
+
+fn main() {
+    let out = adder::add(5, 10);
+    std::process::exit(out);
+}"#,
+            files["src/main.rs"]
+        );
+        assert_eq!(
+            format!(
+                r#"[package]
+name = "test"
+version = "0.1.0"
+authors = [""]
+
+[dependencies]
+adder = {{ path = "{}" }}
+"#,
+                config.url.to_str().unwrap().replace("\\", "/")
+            ),
+            files["Cargo.toml"]
+        );
+    }
+
+    #[test]
+    fn async_methods_are_awaited() {
+        let add = |config: &Rc<Config>| Instruction::CallModule {
+            config: config.clone(),
+            method: "add".to_string(),
+            parameters: vec![
+                ("a".to_string(), InstructionParameter::Number(5)),
+                ("b".to_string(), InstructionParameter::Number(10)),
+            ],
+            out_variable_name: "out".to_string(),
+        };
+        let sync = add(&Rc::new(Config::from_path("../modules/adder")));
+        assert!(!sync.is_async());
+        assert_eq!("    let out = adder::add(5, 10);\n", sync.build());
+
+        let mut config = Config::from_path("../modules/adder");
+        config.methods[0].is_async = true;
+        let instruction = add(&Rc::new(config));
+        assert!(instruction.is_async());
+        assert_eq!("    let out = adder::add(5, 10).await;\n", instruction.build());
+        assert!(!Instruction::Exit(InstructionParameter::Number(0)).is_async());
+    }
+
+    #[test]
+    fn floats_are_f32_literals() {
+        assert_eq!("5.0f32", InstructionParameter::Float(5.0).to_string(false));
+        assert_eq!("-0.25f32", InstructionParameter::Float(-0.25).to_string(true));
+    }
+}
@@ -6,4 +6,7 @@ pub struct Input {
     pub description: String,
     #[serde(default, rename = "type")]
     pub value_type: ParameterType,
+    /// Previous names of this input, so stored calls using an old name can be fixed automatically
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub renamed_from: Vec<String>,
 }
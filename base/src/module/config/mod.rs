@@ -14,6 +14,7 @@ use serde::de::{MapAccess, Visitor};
 use serde::{Deserialize, Deserializer};
 use std::fmt;
 use std::fs::File;
+use std::io;
 use std::marker::PhantomData;
 use std::path::PathBuf;
 
@@ -30,14 +31,19 @@ pub struct Config {
 
 impl Config {
     pub fn from_path(p: &str) -> Config {
-        let mut path = ::std::env::current_dir().unwrap();
+        Config::try_from_path(p).unwrap()
+    }
+
+    /// Load the `module.json` in the given directory, relative to the current directory
+    pub fn try_from_path(p: &str) -> io::Result<Config> {
+        let mut path = ::std::env::current_dir()?;
         path.push(p);
         let mut json_file = path.clone();
         json_file.push("module.json");
-        let mut file = File::open(&json_file).unwrap();
-        let mut config: Config = ::serde_json::from_reader(&mut file).unwrap();
+        let mut file = File::open(&json_file)?;
+        let mut config: Config = ::serde_json::from_reader(&mut file)?;
         config.url = path;
-        config
+        Ok(config)
     }
 }
 
@@ -51,6 +57,18 @@ pub struct Method {
 
     #[serde(deserialize_with = "array_or_single")]
     pub output: Vec<Output>,
+
+    /// Whether this method is an `async fn` that needs to be awaited
+    #[serde(default, rename = "async")]
+    pub is_async: bool,
+
+    /// Value returned by this method when the generated code is built with mocks enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mock: Option<::serde_json::Value>,
+
+    /// Previous names of this method, so stored calls using an old name can be fixed automatically
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub renamed_from: Vec<String>,
 }
 
 fn array_or_single<'de, T, D>(deserializer: D) -> Result<Vec<T>, D::Error>
@@ -0,0 +1,73 @@
+use super::config::{Config, Method, ParameterType};
+
+/// Generate the source of a `dispatch` module for the given module, to be saved as
+/// `src/dispatch.rs` of the module crate and declared with `pub mod dispatch;`.
+///
+/// It contains `pub fn call(method: &str, args: Vec<Value>) -> Result<Value, String>`, which
+/// calls a method by name for the `Interpreter`. The module crate needs to depend on
+/// `serde_json`, and the types of its object inputs and its outputs need to implement
+/// `Deserialize` and `Serialize`.
+pub fn dispatch_source(config: &Config) -> String {
+    let mut result = format!(
+        "//! Generated from the module.json of `{}` by `rust_functional dispatch`, do not edit
+
+use serde_json::{{self, Value}};
+
+/// Call a method of this module by name, with one JSON value for every input of the method
+pub fn call(method: &str, args: Vec<Value>) -> Result<Value, String> {{
+    let mut args = args.into_iter();
+    match method {{
+",
+        config.name
+    );
+    for method in &config.methods {
+        result += &format!("        {:?} => {{\n", method.name);
+        result += &call_method(method);
+        result += "        }\n";
+    }
+    result += "        _ => Err(format!(\"Unknown method {:?}\", method)),\n    }\n}\n";
+    result
+}
+
+fn call_method(method: &Method) -> String {
+    if method.is_async {
+        return unsupported(method, "is async");
+    }
+    let mut result = String::new();
+    let mut arguments = Vec::with_capacity(method.input.len());
+    for input in &method.input {
+        // Numbers are passed by value, with their type inferred from the method
+        let (declared_type, argument) = match &input.value_type {
+            ParameterType::Numeric(_) | ParameterType::Unknown => {
+                (String::new(), input.name.clone())
+            }
+            ParameterType::String(_) => (": String".to_string(), format!("&{}", input.name)),
+            ParameterType::Object(path) => (format!(": {}", path), format!("&{}", input.name)),
+            ParameterType::Trait(_) => return unsupported(method, "takes a trait object"),
+        };
+        result += &format!(
+            "            let {}{} = serde_json::from_value(args.next().unwrap_or(Value::Null))\n",
+            input.name, declared_type
+        );
+        result += &format!(
+            "                .map_err(|e| format!(\"Invalid value for {}: {{}}\", e))?;\n",
+            input.name
+        );
+        arguments.push(argument);
+    }
+    let call = format!("super::{}({})", method.name, arguments.join(", "));
+    if method.output.is_empty() {
+        result += &format!("            {};\n            Ok(Value::Null)\n", call);
+    } else {
+        result += &format!("            serde_json::to_value({})\n", call);
+        result += "                .map_err(|e| format!(\"Could not serialize: {}\", e))\n";
+    }
+    result
+}
+
+fn unsupported(method: &Method, reason: &str) -> String {
+    format!(
+        "            Err(\"{} {}, and cannot be called by the interpreter\".to_string())\n",
+        method.name, reason
+    )
+}
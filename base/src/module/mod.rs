@@ -1,7 +1,9 @@
 mod builder;
 mod config;
+mod dispatch;
 
 pub use self::builder::{Builder, Instruction, InstructionParameter};
 pub use self::config::{
     Config, Input, Method, NumericConstraint, Output, ParameterType, StringConstraint,
 };
+pub use self::dispatch::dispatch_source;
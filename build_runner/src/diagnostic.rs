@@ -0,0 +1,84 @@
+/// A compiler message, as emitted by `cargo --message-format=json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub level: String,
+    pub message: String,
+    pub code: Option<DiagnosticCode>,
+    pub spans: Vec<Span>,
+    pub rendered: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticCode {
+    pub code: String,
+}
+
+/// A location in the generated crate. Lines and columns start at 1.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Span {
+    pub file_name: String,
+    pub line_start: usize,
+    pub line_end: usize,
+    pub column_start: usize,
+    pub column_end: usize,
+    pub is_primary: bool,
+    pub label: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn primary_span(&self) -> Option<&Span> {
+        self.spans.iter().find(|s| s.is_primary)
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.level == "error"
+    }
+}
+
+#[derive(Deserialize)]
+struct CargoMessage {
+    reason: String,
+    message: Option<Diagnostic>,
+}
+
+/// Parse a single line of `cargo --message-format=json` output.
+/// Returns `None` for lines that are not compiler messages.
+pub fn parse_line(line: &str) -> Option<Diagnostic> {
+    let message: CargoMessage = ::serde_json::from_str(line).ok()?;
+    if message.reason != "compiler-message" {
+        return None;
+    }
+    message.message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compiler_messages_are_parsed() {
+        let line = r#"{"reason":"compiler-message","package_id":"app 0.1.0","message":{
+            "level":"error","message":"mismatched types","code":{"code":"E0308","explanation":null},
+            "spans":[{"file_name":"src/lib.rs","line_start":3,"line_end":3,"column_start":18,
+            "column_end":23,"is_primary":true,"label":"expected i32","byte_start":40}],
+            "children":[],"rendered":"error[E0308]: mismatched types"}}"#
+            .replace('\n', "");
+        let diagnostic = parse_line(&line).unwrap();
+        assert!(diagnostic.is_error());
+        assert_eq!("mismatched types", diagnostic.message);
+        assert_eq!("E0308", diagnostic.code.unwrap().code);
+        let span = &diagnostic.spans[0];
+        assert_eq!("src/lib.rs", span.file_name);
+        assert_eq!((3, 18), (span.line_start, span.column_start));
+        assert_eq!(Some("expected i32"), span.label.as_ref().map(|l| &l[..]));
+    }
+
+    #[test]
+    fn other_lines_are_skipped() {
+        let artifact = r#"{"reason":"compiler-artifact","package_id":"app 0.1.0","filenames":[]}"#;
+        assert!(parse_line(artifact).is_none());
+        assert!(parse_line(r#"{"reason":"build-finished","success":true}"#).is_none());
+        assert!(parse_line("   Compiling app v0.1.0").is_none());
+        assert!(parse_line("").is_none());
+    }
+}
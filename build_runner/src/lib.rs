@@ -0,0 +1,15 @@
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate tempfile;
+#[cfg(unix)]
+extern crate libc;
+
+mod diagnostic;
+mod runner;
+mod workspace;
+
+pub use diagnostic::{Diagnostic, DiagnosticCode, Span};
+pub use runner::{BuildResult, Command, Runner};
+pub use workspace::Workspace;
@@ -0,0 +1,283 @@
+use diagnostic::{self, Diagnostic};
+use std::io::{self, BufRead, BufReader, Read};
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::process::{self, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use workspace::Workspace;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Command {
+    Check,
+    Build,
+}
+
+impl Command {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Command::Check => "check",
+            Command::Build => "build",
+        }
+    }
+}
+
+/// Runs cargo on a `Workspace`
+#[derive(Debug, Clone)]
+pub struct Runner {
+    pub command: Command,
+    /// The build is killed if it takes longer than this
+    pub timeout: Duration,
+    /// Pass `--offline` to cargo, so the build can't fetch anything from the network
+    pub offline: bool,
+    /// Share a target directory between builds, so dependencies are only compiled once
+    pub target_dir: Option<PathBuf>,
+    /// Features of the generated crate to enable, e.g. `trace`
+    pub features: Vec<String>,
+}
+
+impl Default for Runner {
+    fn default() -> Runner {
+        Runner {
+            command: Command::Check,
+            timeout: Duration::from_secs(120),
+            offline: true,
+            target_dir: None,
+            features: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildResult {
+    pub success: bool,
+    pub timed_out: bool,
+    pub duration_ms: u64,
+    /// Cargo's own output, e.g. dependency resolution errors
+    pub stderr: String,
+}
+
+impl Runner {
+    pub fn new(command: Command) -> Runner {
+        Runner {
+            command,
+            ..Runner::default()
+        }
+    }
+
+    /// Run cargo in the given workspace, calling `on_diagnostic` for every compiler message as it is emitted
+    pub fn run<F>(&self, workspace: &Workspace, mut on_diagnostic: F) -> io::Result<BuildResult>
+    where
+        F: FnMut(Diagnostic),
+    {
+        let start = Instant::now();
+        let mut command = process::Command::new("cargo");
+        command
+            .arg(self.command.as_str())
+            .arg("--message-format=json")
+            .current_dir(workspace.path())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if self.offline {
+            command.arg("--offline");
+        }
+        if let Some(target_dir) = &self.target_dir {
+            command.arg("--target-dir").arg(target_dir);
+        }
+        if !self.features.is_empty() {
+            command.arg("--features").arg(self.features.join(" "));
+        }
+        own_process_group(&mut command);
+        let mut child = command.spawn()?;
+
+        let stdout = child.stdout.take().unwrap();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(_) => break,
+                };
+                if let Some(diagnostic) = diagnostic::parse_line(&line) {
+                    if sender.send(diagnostic).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        let mut stderr = child.stderr.take().unwrap();
+        let stderr = thread::spawn(move || {
+            let mut output = String::new();
+            let _ = stderr.read_to_string(&mut output);
+            output
+        });
+
+        let deadline = start + self.timeout;
+        let mut timed_out = false;
+        loop {
+            let now = Instant::now();
+            let remaining = if now < deadline {
+                deadline - now
+            } else {
+                Duration::from_secs(0)
+            };
+            match receiver.recv_timeout(remaining) {
+                Ok(diagnostic) => on_diagnostic(diagnostic),
+                Err(RecvTimeoutError::Disconnected) => break,
+                Err(RecvTimeoutError::Timeout) => {
+                    timed_out = true;
+                    kill(&mut child);
+                    break;
+                }
+            }
+        }
+
+        let status = child.wait()?;
+        let elapsed = start.elapsed();
+        Ok(BuildResult {
+            success: !timed_out && status.success(),
+            timed_out,
+            duration_ms: elapsed.as_secs() * 1000 + u64::from(elapsed.subsec_millis()),
+            // Processes that left the process group of cargo can keep stderr open after a timeout
+            stderr: if timed_out {
+                String::new()
+            } else {
+                stderr.join().unwrap_or_default()
+            },
+        })
+    }
+}
+
+/// Start cargo in a new process group, so `kill` also stops the rustc processes and build
+/// scripts it spawned
+#[cfg(unix)]
+fn own_process_group(command: &mut process::Command) {
+    unsafe {
+        command.pre_exec(|| {
+            if ::libc::setpgid(0, 0) == 0 {
+                Ok(())
+            } else {
+                Err(io::Error::last_os_error())
+            }
+        });
+    }
+}
+
+/// Without process groups, only cargo itself is killed, and the processes it spawned keep
+/// running until they finish
+#[cfg(not(unix))]
+fn own_process_group(_command: &mut process::Command) {}
+
+/// Kill cargo and the processes it spawned.
+/// The build may have finished in the meantime, in which case this does nothing.
+#[cfg(unix)]
+fn kill(child: &mut process::Child) {
+    unsafe {
+        ::libc::kill(-(child.id() as ::libc::pid_t), ::libc::SIGKILL);
+    }
+}
+
+#[cfg(not(unix))]
+fn kill(child: &mut process::Child) {
+    let _ = child.kill();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::fs;
+
+    fn workspace(dependencies: &str, files: &[(&str, &str)]) -> Workspace {
+        let mut map = HashMap::new();
+        map.insert(
+            "Cargo.toml".to_string(),
+            format!(
+                "[package]\nname = \"generated\"\nversion = \"0.1.0\"\nauthors = [\"\"]\n\n\
+                 [dependencies]\n{}",
+                dependencies
+            ),
+        );
+        for (path, content) in files {
+            map.insert(path.to_string(), content.to_string());
+        }
+        Workspace::new(&map).unwrap()
+    }
+
+    #[test]
+    fn errors_are_reported_as_they_are_emitted() {
+        let workspace = workspace(
+            "",
+            &[("src/main.rs", "fn main() {\n    let x: i32 = \"one\";\n}\n")],
+        );
+        let mut diagnostics = Vec::new();
+        let result = Runner::default()
+            .run(&workspace, |d| diagnostics.push(d))
+            .unwrap();
+        assert!(!result.success);
+        assert!(!result.timed_out);
+        let error = diagnostics.iter().find(|d| d.is_error()).unwrap();
+        assert_eq!("E0308", error.code.as_ref().unwrap().code);
+        let span = error.primary_span().unwrap();
+        assert_eq!(("src/main.rs", 2), (&span.file_name[..], span.line_start));
+    }
+
+    #[test]
+    fn builds_are_offline_by_default() {
+        assert!(Runner::new(Command::Build).offline);
+        let workspace = workspace(
+            "build_runner_missing_dependency = \"1\"\n",
+            &[("src/main.rs", "fn main() {}\n")],
+        );
+        let result = Runner::default().run(&workspace, |_| {}).unwrap();
+        assert!(!result.success);
+        assert!(result.stderr.contains("offline"), "{}", result.stderr);
+    }
+
+    #[test]
+    fn slow_builds_are_killed() {
+        // The build script records its process id, to check that it is killed along with cargo
+        let build = "use std::{env, fs, process, thread, time};\n\
+                     fn main() {\n\
+                     let dir = env::var(\"CARGO_MANIFEST_DIR\").unwrap();\n\
+                     fs::write(dir + \"/build.pid\", process::id().to_string()).unwrap();\n\
+                     thread::sleep(time::Duration::from_secs(120));\n\
+                     }\n";
+        let workspace = workspace("", &[("build.rs", build), ("src/main.rs", "fn main() {}\n")]);
+        let runner = Runner {
+            timeout: Duration::from_secs(5),
+            ..Runner::default()
+        };
+        let result = runner.run(&workspace, |_| {}).unwrap();
+        assert!(result.timed_out);
+        assert!(!result.success);
+        assert!(result.duration_ms < 60_000, "{}", result.duration_ms);
+
+        let pid = fs::read_to_string(workspace.path().join("build.pid"))
+            .expect("The build script did not start before the timeout");
+        assert!(!is_running(pid.trim()), "The build script {} is still running", pid);
+    }
+
+    /// Whether the process is running, and not a zombie waiting to be reaped
+    #[cfg(target_os = "linux")]
+    fn is_running(pid: &str) -> bool {
+        for _ in 0..50 {
+            match fs::read_to_string(format!("/proc/{}/stat", pid)) {
+                Ok(ref stat) if !stat.contains(") Z ") => {}
+                _ => return false,
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        true
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn is_running(_pid: &str) -> bool {
+        false
+    }
+}
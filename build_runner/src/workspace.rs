@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use tempfile::{Builder, TempDir};
+
+/// A directory containing a generated crate
+#[derive(Debug)]
+pub struct Workspace {
+    dir: Dir,
+}
+
+#[derive(Debug)]
+enum Dir {
+    /// Removed when the workspace is dropped
+    Temp(TempDir),
+    Kept(PathBuf),
+}
+
+impl Workspace {
+    /// Write the given files, keyed by their path relative to the crate root, into a new
+    /// temporary directory. The directory is removed when the workspace is dropped.
+    pub fn new(files: &HashMap<String, String>) -> io::Result<Workspace> {
+        let dir = Builder::new().prefix("build_runner").tempdir()?;
+        write_files(dir.path(), files)?;
+        Ok(Workspace { dir: Dir::Temp(dir) })
+    }
+
+    /// Write the given files into `path`, creating it if needed. Other files in the directory,
+    /// like a previous `target` directory, are left alone, and nothing is removed when the
+    /// workspace is dropped.
+    pub fn create_in(
+        path: impl Into<PathBuf>,
+        files: &HashMap<String, String>,
+    ) -> io::Result<Workspace> {
+        let path = path.into();
+        fs::create_dir_all(&path)?;
+        write_files(&path, files)?;
+        Ok(Workspace {
+            dir: Dir::Kept(path),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        match &self.dir {
+            Dir::Temp(dir) => dir.path(),
+            Dir::Kept(path) => path,
+        }
+    }
+}
+
+fn write_files(root: &Path, files: &HashMap<String, String>) -> io::Result<()> {
+    for (name, content) in files {
+        let path = root.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        File::create(&path)?.write_all(content.as_bytes())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    fn files() -> HashMap<String, String> {
+        let mut files = HashMap::new();
+        files.insert("src/lib.rs".to_string(), "pub fn app() {}\n".to_string());
+        files
+    }
+
+    #[test]
+    fn temporary_workspaces_are_removed_on_drop() {
+        let workspace = Workspace::new(&files()).unwrap();
+        let path = workspace.path().to_path_buf();
+        assert_eq!(
+            "pub fn app() {}\n",
+            fs::read_to_string(path.join("src/lib.rs")).unwrap()
+        );
+        drop(workspace);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn kept_workspaces_stay() {
+        let path = env::temp_dir().join(format!("build_runner_kept_{}", process::id()));
+        fs::create_dir_all(path.join("target")).unwrap();
+        drop(Workspace::create_in(path.clone(), &files()).unwrap());
+        assert!(path.join("src/lib.rs").exists());
+        assert!(path.join("target").exists());
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
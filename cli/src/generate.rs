@@ -0,0 +1,236 @@
+use project::{check_call, Instruction, Project};
+use rust_functional::{self, Config, Instruction as BaseInstruction};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use web_api_generator::{self, Backend, Builder, EndPoint};
+
+/// Load the `module.json` of every module in the project.
+/// Relative module paths are resolved against `root`, the directory of the project file.
+pub fn load_modules(project: &Project, root: &Path) -> Result<HashMap<String, Rc<Config>>, String> {
+    let mut modules = HashMap::with_capacity(project.modules.len());
+    for module in &project.modules {
+        let path = root.join(&module.path);
+        let config = Config::try_from_path(&path.to_string_lossy()).map_err(|e| {
+            format!(
+                "Could not load module {:?} from {}: {}",
+                module.name,
+                path.display(),
+                e
+            )
+        })?;
+        modules.insert(module.name.clone(), Rc::new(config));
+    }
+    Ok(modules)
+}
+
+/// Write the dispatch function of a module to its `src/dispatch.rs`, returning the path written
+pub fn write_dispatch(module: &Config) -> Result<PathBuf, String> {
+    let path = module.url.join("src").join("dispatch.rs");
+    File::create(&path)
+        .and_then(|mut f| f.write_all(rust_functional::dispatch_source(module).as_bytes()))
+        .map_err(|e| format!("Could not write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Check every instruction against its module and parse its arguments.
+/// Returns a message for every problem found.
+pub fn check(project: &Project, modules: &HashMap<String, Rc<Config>>) -> Vec<String> {
+    let mut problems = Vec::new();
+    for endpoint in &project.endpoints {
+        for (index, instruction) in endpoint.instructions.iter().enumerate() {
+            let mut report = |problem: String| {
+                problems.push(format!(
+                    "endpoint {:?}, instruction {}: {}",
+                    endpoint.name,
+                    index + 1,
+                    problem
+                ))
+            };
+            match instruction {
+                Instruction::CallMethod {
+                    module,
+                    method,
+                    arguments,
+                    ..
+                } => {
+                    for problem in check_call(&modules[module], method, arguments) {
+                        report(problem.to_string());
+                    }
+                    for argument in arguments {
+                        if let Err(e) = argument.arg_type.parse(&argument.arg_type_value) {
+                            report(e.to_string());
+                        }
+                    }
+                }
+                Instruction::JsonReturn {
+                    arg_type,
+                    arg_type_value,
+                    ..
+                } => {
+                    if let Err(e) = arg_type.parse(arg_type_value) {
+                        report(e.to_string());
+                    }
+                }
+            }
+        }
+    }
+    problems
+}
+
+/// Create a builder for all endpoints of a project. `check` must have passed.
+pub fn builder(
+    project: &Project,
+    modules: &HashMap<String, Rc<Config>>,
+    backend: Box<dyn Backend>,
+    generate_tests: bool,
+) -> Result<Builder, String> {
+    let mut builder = Builder::default();
+    builder.set_backend(backend);
+    builder.set_generate_tests(generate_tests);
+    for module in &project.modules {
+        builder.add_module(modules[&module.name].clone());
+    }
+    for endpoint in &project.endpoints {
+        let mut generated = EndPoint::new(endpoint.name.as_str(), endpoint.url.as_str());
+        for instruction in &endpoint.instructions {
+            generated.add_instruction(to_generator(instruction, modules));
+        }
+        builder.add_endpoint(generated);
+    }
+    builder.validate().map_err(|e| e.to_string())?;
+    Ok(builder)
+}
+
+fn to_generator(
+    instruction: &Instruction,
+    modules: &HashMap<String, Rc<Config>>,
+) -> web_api_generator::Instruction {
+    match instruction {
+        Instruction::CallMethod {
+            module,
+            method,
+            out_variable_name,
+            arguments,
+            ..
+        } => web_api_generator::Instruction::BaseInstruction(BaseInstruction::CallModule {
+            config: modules[module].clone(),
+            method: method.clone(),
+            out_variable_name: out_variable_name.clone(),
+            parameters: arguments.iter().map(Into::into).collect(),
+        }),
+        Instruction::JsonReturn {
+            arg_type,
+            arg_type_value,
+            ..
+        } => web_api_generator::Instruction::Json(
+            arg_type
+                .parse(arg_type_value)
+                .expect("Instruction arguments are checked before generating"),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use project::{ArgType, CallMethodArgument, Module, ProjectEndpoint, VERSION};
+    use web_api_generator::backend_by_name;
+
+    const NIL: &str = "00000000-0000-0000-0000-000000000000";
+
+    fn root() -> &'static Path {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+    }
+
+    fn argument(name: &str, arg_type: ArgType, value: &str) -> CallMethodArgument {
+        CallMethodArgument {
+            name: name.to_string(),
+            arg_type,
+            arg_type_value: value.to_string(),
+        }
+    }
+
+    /// A project with an endpoint `/add/{x}` that returns `a + x`
+    fn project(a: CallMethodArgument) -> Project {
+        Project {
+            version: VERSION,
+            modules: vec![Module {
+                name: "adder".to_string(),
+                path: "../modules/adder".to_string(),
+            }],
+            endpoints: vec![ProjectEndpoint {
+                id: NIL.parse().unwrap(),
+                name: "add".to_string(),
+                description: String::new(),
+                url: "/add/{x}".to_string(),
+                instructions: vec![
+                    Instruction::CallMethod {
+                        id: NIL.parse().unwrap(),
+                        module: "adder".to_string(),
+                        method: "add".to_string(),
+                        out_variable_name: "sum".to_string(),
+                        arguments: vec![a, argument("b", ArgType::Parameter, "x")],
+                    },
+                    Instruction::JsonReturn {
+                        id: NIL.parse().unwrap(),
+                        arg_type: ArgType::Parameter,
+                        arg_type_value: "sum".to_string(),
+                    },
+                ],
+            }],
+        }
+    }
+
+    #[test]
+    fn modules_are_loaded_relative_to_the_project() {
+        let project = project(argument("a", ArgType::Number, "1"));
+        let modules = load_modules(&project, root()).unwrap();
+        assert_eq!("adder", modules["adder"].name);
+
+        let error = load_modules(&project, &root().join("src")).unwrap_err();
+        assert!(
+            error.starts_with("Could not load module \"adder\" from "),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn problems_name_their_instruction() {
+        let project = project(argument("a", ArgType::Number, "1"));
+        let modules = load_modules(&project, root()).unwrap();
+        assert!(check(&project, &modules).is_empty());
+
+        let project = self::project(argument("a", ArgType::Number, "one"));
+        assert_eq!(
+            vec!["endpoint \"add\", instruction 1: \"one\" is not a valid Number argument"],
+            check(&project, &modules)
+        );
+        let project = self::project(argument("c", ArgType::String, "1"));
+        assert_eq!(
+            vec![
+                "endpoint \"add\", instruction 1: Argument \"c\" does not exist",
+                "endpoint \"add\", instruction 1: Argument \"a\" is missing",
+            ],
+            check(&project, &modules)
+        );
+    }
+
+    #[test]
+    fn builders_contain_every_endpoint() {
+        let project = project(argument("a", ArgType::Number, "1"));
+        let modules = load_modules(&project, root()).unwrap();
+        let backend = backend_by_name("actix").unwrap();
+        let files = builder(&project, &modules, backend, true)
+            .unwrap()
+            .build()
+            .unwrap();
+        let lib = &files["src/lib.rs"];
+        assert!(lib.contains("\"/add/{x}\""), "{}", lib);
+        assert!(lib.contains("adder::add(1, &x)"), "{}", lib);
+        assert!(files.contains_key("tests/endpoints.rs"));
+    }
+}
@@ -0,0 +1,387 @@
+extern crate build_runner;
+extern crate project;
+extern crate rust_functional;
+extern crate web_api_generator;
+
+mod generate;
+
+use build_runner::{Command, Diagnostic, Runner, Workspace};
+use project::{Format, Project};
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::Duration;
+use web_api_generator::SourceMap;
+
+const USAGE: &str = "Usage: rust_functional <command> <project file> [options]
+
+Commands:
+    check       Check the endpoints of the project against their modules
+    generate    Check the project and generate the crate
+    build       Generate the crate and build it with cargo
+    run         Generate, build and run the crate
+    dispatch    Write the `src/dispatch.rs` the interpreter calls each module through
+
+Options:
+    --out <dir>         The directory to generate the crate into, defaults to `output`
+    --backend <name>    The backend to generate for, defaults to `actix`
+    --tests             Also generate tests, which are run with `cargo test --features mock`
+    --trace             Generate a `trace` feature that returns the module calls of every
+                        request with its response, and enable it for `build` and `run`.
+                        Not supported by the `cgi` backend
+    --offline           Pass `--offline` to cargo
+    --timeout <secs>    Stop the build after this many seconds, defaults to 600
+
+Module paths in the project file are relative to the directory of the project file.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Check,
+    Generate,
+    Build,
+    Run,
+    Dispatch,
+}
+
+#[derive(Debug)]
+struct Options {
+    action: Action,
+    project: PathBuf,
+    out: PathBuf,
+    backend: String,
+    tests: bool,
+    trace: bool,
+    offline: bool,
+    timeout: Duration,
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut args = args.iter();
+    let action = match args.next().map(|a| a.as_str()) {
+        Some("check") => Action::Check,
+        Some("generate") => Action::Generate,
+        Some("build") => Action::Build,
+        Some("run") => Action::Run,
+        Some("dispatch") => Action::Dispatch,
+        Some(command) => return Err(format!("Unknown command {:?}", command)),
+        None => return Err("Missing command".to_string()),
+    };
+    let mut options = Options {
+        action,
+        project: PathBuf::new(),
+        out: PathBuf::from("output"),
+        backend: "actix".to_string(),
+        tests: false,
+        trace: false,
+        offline: false,
+        timeout: Duration::from_secs(600),
+    };
+    let mut project = None;
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .cloned()
+                .ok_or_else(|| format!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--out" => options.out = PathBuf::from(value()?),
+            "--backend" => options.backend = value()?,
+            "--tests" => options.tests = true,
+            "--trace" => options.trace = true,
+            "--offline" => options.offline = true,
+            "--timeout" => {
+                let value = value()?;
+                let seconds = value
+                    .parse()
+                    .map_err(|_| format!("Invalid timeout {:?}", value))?;
+                options.timeout = Duration::from_secs(seconds);
+            }
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+            _ if project.is_none() => project = Some(PathBuf::from(arg)),
+            _ => return Err(format!("Unexpected argument {:?}", arg)),
+        }
+    }
+    options.project = project.ok_or_else(|| "Missing project file".to_string())?;
+    Ok(options)
+}
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        println!("{}", USAGE);
+        return;
+    }
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+    match run(&options) {
+        Ok(code) => process::exit(code),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn read_project(path: &Path) -> Result<Project, String> {
+    let format = path
+        .extension()
+        .and_then(|e| Format::from_extension(&e.to_string_lossy()))
+        .ok_or_else(|| {
+            format!(
+                "Unknown project format {}, expected a .json or .yaml file",
+                path.display()
+            )
+        })?;
+    let mut content = Vec::new();
+    File::open(path)
+        .and_then(|mut f| f.read_to_end(&mut content))
+        .map_err(|e| format!("Could not read {}: {}", path.display(), e))?;
+    Project::read(format, &content).map_err(|e| e.to_string())
+}
+
+/// Run the action, returning the exit code
+fn run(options: &Options) -> Result<i32, String> {
+    let project = read_project(&options.project)?;
+    let root = options.project.parent().unwrap_or_else(|| Path::new("."));
+    let modules = generate::load_modules(&project, root)?;
+    if options.action == Action::Dispatch {
+        for module in modules.values() {
+            let path = generate::write_dispatch(module)?;
+            println!("Generated {}", path.display());
+        }
+        return Ok(0);
+    }
+
+    let problems = generate::check(&project, &modules);
+    for problem in &problems {
+        eprintln!("error: {}", problem);
+    }
+    if !problems.is_empty() {
+        return Ok(1);
+    }
+    if options.action == Action::Check {
+        println!("{} endpoints are valid", project.endpoints.len());
+        return Ok(0);
+    }
+
+    let backend = web_api_generator::backend_by_name(&options.backend).ok_or_else(|| {
+        format!(
+            "Unknown backend {:?}, expected one of {:?}",
+            options.backend,
+            web_api_generator::backend_names()
+        )
+    })?;
+    let mut builder = generate::builder(&project, &modules, backend, options.tests)?;
+    builder.set_trace(options.trace);
+    let (files, source_map) = builder.build_with_source_map().map_err(|e| e.to_string())?;
+    let workspace = Workspace::create_in(options.out.clone(), &files)
+        .map_err(|e| format!("Could not write {}: {}", options.out.display(), e))?;
+    println!(
+        "Generated {} files in {}",
+        files.len(),
+        options.out.display()
+    );
+    if options.action == Action::Generate {
+        return Ok(0);
+    }
+
+    let mut runner = Runner::new(Command::Build);
+    runner.offline = options.offline;
+    runner.timeout = options.timeout;
+    if options.trace {
+        runner.features.push("trace".to_string());
+    }
+    let result = runner
+        .run(&workspace, |diagnostic| {
+            print_diagnostic(&diagnostic, &source_map)
+        })
+        .map_err(|e| format!("Could not run cargo: {}", e))?;
+    if result.timed_out {
+        eprintln!("error: the build timed out");
+        return Ok(1);
+    }
+    if !result.success {
+        eprint!("{}", result.stderr);
+        return Ok(1);
+    }
+    if options.action == Action::Build {
+        return Ok(0);
+    }
+
+    let mut cargo = process::Command::new("cargo");
+    cargo.arg("run").current_dir(workspace.path());
+    if options.trace {
+        cargo.arg("--features").arg("trace");
+    }
+    if options.offline {
+        cargo.arg("--offline");
+    }
+    let status = cargo
+        .status()
+        .map_err(|e| format!("Could not run cargo: {}", e))?;
+    Ok(status.code().unwrap_or(1))
+}
+
+/// Print a compiler message, followed by the instruction it was generated from
+fn print_diagnostic(diagnostic: &Diagnostic, source_map: &SourceMap) {
+    match &diagnostic.rendered {
+        Some(rendered) => eprint!("{}", rendered),
+        None => eprintln!("{}: {}", diagnostic.level, diagnostic.message),
+    }
+    let span = match diagnostic.primary_span() {
+        Some(span) => span,
+        None => return,
+    };
+    if let Some(entry) = source_map.lookup(&span.file_name, span.line_start) {
+        match entry.argument_at(span.line_start, span.column_start) {
+            Some(argument) => eprintln!(
+                "  = note: in argument {:?} of instruction {} of endpoint {:?}\n",
+                argument.name,
+                entry.instruction + 1,
+                entry.endpoint
+            ),
+            None => eprintln!(
+                "  = note: in instruction {} of endpoint {:?}\n",
+                entry.instruction + 1,
+                entry.endpoint
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{self, File};
+    use std::io::Write;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    /// Write a project with an `add` endpoint that calls `method` of the adder module
+    /// to a fresh directory, returning the options to run `action` on it
+    fn project(name: &str, method: &str, action: Action) -> Options {
+        let dir = env::temp_dir().join(format!("rust_functional_cli_{}", name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let adder = Path::new(env!("CARGO_MANIFEST_DIR")).join("../modules/adder");
+        let project = format!(
+            r#"{{
+    "version": 1,
+    "modules": [{{ "name": "adder", "path": {:?} }}],
+    "endpoints": [{{
+        "id": "6f9c7c5e-0c4f-4f5e-9a57-0d6f3d1b2a01",
+        "name": "add",
+        "url": "/add/{{x}}",
+        "instructions": [
+            {{ "CallMethod": {{
+                "id": "0b7d1f0e-3c1a-4d0e-8e43-6a8a4f5c2b01",
+                "module": "adder",
+                "method": {:?},
+                "out_variable_name": "sum",
+                "arguments": [
+                    {{ "name": "a", "arg_type": "Number", "arg_type_value": "1" }},
+                    {{ "name": "b", "arg_type": "Parameter", "arg_type_value": "x" }}
+                ]
+            }} }},
+            {{ "JsonReturn": {{
+                "id": "0b7d1f0e-3c1a-4d0e-8e43-6a8a4f5c2b02",
+                "arg_type": "Parameter",
+                "arg_type_value": "sum"
+            }} }}
+        ]
+    }}]
+}}"#,
+            adder.to_string_lossy(),
+            method
+        );
+        let path = dir.join("project.json");
+        File::create(&path)
+            .unwrap()
+            .write_all(project.as_bytes())
+            .unwrap();
+        let mut options = parse_args(&args(&["check", "project.json"])).unwrap();
+        options.action = action;
+        options.project = path;
+        options.out = dir.join("output");
+        options
+    }
+
+    #[test]
+    fn parse_options() {
+        let options = parse_args(&args(&[
+            "build",
+            "project.yaml",
+            "--out",
+            "target/api",
+            "--backend",
+            "cgi",
+            "--tests",
+            "--offline",
+            "--timeout",
+            "30",
+        ]))
+        .unwrap();
+        assert_eq!(Action::Build, options.action);
+        assert_eq!(Path::new("project.yaml"), options.project);
+        assert_eq!(Path::new("target/api"), options.out);
+        assert_eq!("cgi", options.backend);
+        assert!(options.tests);
+        assert!(!options.trace);
+        assert!(options.offline);
+        assert_eq!(Duration::from_secs(30), options.timeout);
+
+        let options = parse_args(&args(&["check", "project.json"])).unwrap();
+        assert_eq!(Action::Check, options.action);
+        assert_eq!(Path::new("output"), options.out);
+        assert_eq!("actix", options.backend);
+        assert_eq!(Duration::from_secs(600), options.timeout);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |a: &[&str]| parse_args(&args(a)).unwrap_err();
+        assert_eq!("Missing command", error(&[]));
+        assert_eq!("Unknown command \"test\"", error(&["test", "project.json"]));
+        assert_eq!("Missing project file", error(&["check"]));
+        assert_eq!("Missing value for --out", error(&["check", "project.json", "--out"]));
+        assert_eq!("Invalid timeout \"soon\"", error(&["run", "p.json", "--timeout", "soon"]));
+        assert_eq!("Unknown option --release", error(&["run", "p.json", "--release"]));
+        assert_eq!("Unexpected argument \"b.json\"", error(&["run", "a.json", "b.json"]));
+    }
+
+    #[test]
+    fn check_reports_problems() {
+        assert_eq!(Ok(0), run(&project("check_valid", "add", Action::Check)));
+        assert_eq!(Ok(1), run(&project("check_invalid", "subtract", Action::Check)));
+    }
+
+    #[test]
+    fn generate_writes_the_crate() {
+        let options = project("generate", "add", Action::Generate);
+        assert_eq!(Ok(0), run(&options));
+        assert!(options.out.join("Cargo.toml").is_file());
+        assert!(options.out.join("src/main.rs").is_file());
+        assert!(!options.out.join("src/tests.rs").exists());
+
+        let mut options = project("generate_invalid", "subtract", Action::Generate);
+        options.backend = "none".to_string();
+        assert_eq!(Ok(1), run(&options));
+        assert!(!options.out.exists());
+
+        let options = project("generate_backend", "add", Action::Generate);
+        let options = Options {
+            backend: "none".to_string(),
+            ..options
+        };
+        assert!(run(&options).unwrap_err().starts_with("Unknown backend \"none\""));
+    }
+}
@@ -21,6 +21,8 @@ declare namespace endpoints {
         description: string;
         input: Input[];
         output: Output[];
+        async?: boolean;
+        mock?: any;
     }
 
     export interface Config2 {
@@ -38,6 +40,7 @@ declare namespace endpoints {
 
     export interface Argument {
         name: string;
+        /** "Parameter", "String", "Number" or "Float". Numbers are stored as their decimal text. */
         arg_type: string;
         arg_type_value: string;
     }
@@ -54,6 +57,7 @@ declare namespace endpoints {
 
     export interface JsonReturn {
         id: string;
+        /** See `Argument.arg_type` */
         arg_type: string;
         arg_type_value: string;
     }
@@ -70,6 +74,186 @@ declare namespace endpoints {
         description: string;
         url: string;
         instructions: Instruction[];
+        version?: number;
+        updated_at?: string;
+        /** The id of the user who created the endpoint, set by the server */
+        owner?: string | null;
+    }
+
+    /** "Read" may only look, "Write" may change their own endpoints, "Admin" everything */
+    export type Role = "Read" | "Write" | "Admin";
+
+    export interface User {
+        id: string;
+        name: string;
+        role: Role;
+    }
+
+    export interface RevisionSummary {
+        version: number;
+        author: string;
+        created_at: string;
+    }
+
+    export interface Revision extends RevisionSummary {
+        endpoint: string;
+        snapshot: Endpoint;
+    }
+
+    export interface Span {
+        file_name: string;
+        line_start: number;
+        line_end: number;
+        column_start: number;
+        column_end: number;
+        is_primary: boolean;
+        label: string | null;
+    }
+
+    export interface Diagnostic {
+        level: string;
+        message: string;
+        spans: Span[];
+        rendered: string | null;
+    }
+
+    export interface BuildEvent {
+        type: "diagnostic" | "finished" | "failed";
+        diagnostic?: Diagnostic;
+        endpoint?: string | null;
+        instruction?: number | null;
+        argument?: string | null;
+        success?: boolean;
+        timed_out?: boolean;
+        message?: string;
+    }
+
+    /** A stored instruction that no longer matches its module.json, see `validation::Problem` */
+    export interface Problem {
+        problem:
+            | "missing_config"
+            | "missing_method"
+            | "missing_argument"
+            | "unknown_argument"
+            | "type_mismatch";
+        config?: string;
+        method?: string;
+        name?: string;
+        arg_type?: string;
+        /** The new name declared in `renamed_from`, if the problem can be fixed automatically */
+        renamed_to?: string | null;
+    }
+
+    export interface BrokenInstruction {
+        id: string;
+        index: number;
+        problems: Problem[];
+    }
+
+    export interface BrokenEndpoint {
+        id: string;
+        name: string;
+        version: number;
+        instructions: BrokenInstruction[];
+        fixable: boolean;
+    }
+
+    export interface FixResult {
+        fixed: number;
+        endpoint: Endpoint;
+        remaining: BrokenEndpoint | null;
+    }
+
+    export interface ImportedEndpoint {
+        id: string;
+        name: string;
+        version: number;
+        status: "created" | "updated" | "unchanged";
+    }
+
+    export interface ImportResult {
+        /** Modules that had no config with the same name, and were added */
+        created_configs: string[];
+        endpoints: ImportedEndpoint[];
+        broken: BrokenEndpoint[];
+    }
+
+    /** A problem that prevents an unsaved endpoint from being generated */
+    export interface PreviewDiagnostic {
+        /** `null` if the problem concerns the whole endpoint */
+        instruction: string | null;
+        index: number | null;
+        message: string;
+    }
+
+    export interface Preview {
+        /** The generated files by path, empty if there are any diagnostics */
+        files: { [path: string]: string };
+        diagnostics: PreviewDiagnostic[];
+    }
+
+    /** The result of running an endpoint with the interpreter, see `POST /api/run` */
+    export type Outcome = { Return: any } | { Exit: number } | "Finished";
+
+    /** A module call made by an interpreter run */
+    export interface TraceEntry {
+        /** The index of the instruction that made the call */
+        instruction: number;
+        module: string;
+        method: string;
+        inputs: { [name: string]: any };
+        /** `null` if the call failed */
+        output: any;
+        duration_us: number;
+    }
+
+    /** The result of `POST /api/trace` */
+    export interface Trace {
+        /** `null` if the run failed */
+        outcome: Outcome | null;
+        error: string | null;
+        calls: TraceEntry[];
+    }
+
+    /** A change to an endpoint or config, see `GET /api/audit` */
+    export interface AuditEntry {
+        id: number;
+        created_at: string;
+        /** The name of the user who made the change */
+        actor: string;
+        action: "create" | "update" | "delete";
+        /** Exactly one of `endpoint` and `config` is set */
+        endpoint: string | null;
+        config: string | null;
+        /** The endpoint, or the id, name and path of the config. `null` before a create. */
+        before: any;
+        /** `null` after a delete */
+        after: any;
+    }
+
+    /** A change published on `GET /api/events` */
+    export interface ChangeEvent {
+        type: "endpoint_saved" | "endpoint_deleted" | "config_saved" | "config_deleted";
+        id: string;
+        /** Set for `endpoint_saved` and `config_saved` */
+        name?: string;
+        /** The version of the endpoint after an `endpoint_saved`, to detect conflicts */
+        version?: number;
+        /** The name of the user who made the change */
+        actor: string;
+    }
+
+    export interface ServiceEndpoint {
+        endpoint: string;
+        prefix: string;
+    }
+
+    export interface Service {
+        id: string;
+        name: string;
+        description: string;
+        backend: string;
+        endpoints: ServiceEndpoint[];
     }
 
     export interface RootObject {
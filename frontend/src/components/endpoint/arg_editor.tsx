@@ -86,6 +86,16 @@ export class ArgEditor extends React.Component<ArgEditorProps, ArgEditorState> {
                         onChange={this.setValue.bind(this)}
                     />
                 );
+            case "Number":
+            case "Float":
+                return (
+                    <input
+                        type="number"
+                        step={type == "Number" ? 1 : "any"}
+                        value={this.props.prop.arg_type_value}
+                        onChange={this.setValue.bind(this)}
+                    />
+                );
             default:
                 return (
                     <b>Unknown arg type {JSON.stringify(this.props.prop)}</b>
@@ -142,6 +152,8 @@ export class ArgEditor extends React.Component<ArgEditorProps, ArgEditorState> {
                     ) : null}
                     <option value="Parameter">Parameter</option>
                     <option value="String">String</option>
+                    <option value="Number">Number</option>
+                    <option value="Float">Float</option>
                 </select>
                 {this.renderEditor(type, suggested)}
                 {text}
@@ -168,6 +180,8 @@ export function argToString(type: {
         case "String":
             return '"' + type.arg_type_value + '"';
         case "Parameter":
+        case "Number":
+        case "Float":
             return type.arg_type_value;
     }
 }
@@ -1,5 +1,5 @@
 import * as React from "react";
-import { Stack, guid } from "./instruction_base";
+import { ErrorList, Stack, guid } from "./instruction_base";
 import { ArgEditor, getTypeName, argToString } from "./arg_editor";
 
 interface CallMethodProps {
@@ -8,6 +8,7 @@ interface CallMethodProps {
     stack: Stack;
     onDelete: (ev: React.MouseEvent<HTMLElement>) => void;
     onChange: (m: endpoints.CallMethod) => void;
+    errors: endpoints.BuildEvent[];
 }
 
 interface CallMethodState {
@@ -228,6 +229,7 @@ export class CallMethod extends React.Component<
                         ))}
                         );<br />
                     </code>
+                    <ErrorList errors={this.props.errors} />
                 </li>
             );
         }
@@ -267,6 +269,7 @@ export class CallMethod extends React.Component<
                     </tbody>
                 </table>
                 {output}
+                <ErrorList errors={this.props.errors} />
             </li>
         );
     }
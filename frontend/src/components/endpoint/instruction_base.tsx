@@ -7,11 +7,35 @@ export interface InstructionBase {
         stack: Stack;
         onDelete: (m: React.MouseEvent<HTMLElement>) => void;
         onChange: (m: endpoints.AnyInstruction) => void;
+        errors: endpoints.BuildEvent[];
     }>;
     description(): string;
     create(): endpoints.Instruction;
 }
 
+export function ErrorList(props: { errors: endpoints.BuildEvent[] }) {
+    if (!props.errors.length) {
+        return null;
+    }
+    return (
+        <ul className="list-unstyled">
+            {props.errors.map((e, index) => (
+                <li
+                    key={index}
+                    className={
+                        e.diagnostic.level == "error"
+                            ? "alert alert-danger"
+                            : "alert alert-warning"
+                    }
+                >
+                    {e.argument ? <b>{e.argument}: </b> : null}
+                    {e.diagnostic.message}
+                </li>
+            ))}
+        </ul>
+    );
+}
+
 export class Stack {
     constructor() {
         this.variables = {};
@@ -1,5 +1,5 @@
 import * as React from "react";
-import { Stack, guid } from "./instruction_base";
+import { ErrorList, Stack, guid } from "./instruction_base";
 import { ArgEditor, argToString } from "./arg_editor";
 
 interface JsonReturnProps {
@@ -8,6 +8,7 @@ interface JsonReturnProps {
     stack: Stack;
     onDelete: (ev: React.MouseEvent<HTMLElement>) => void;
     onChange: (m: endpoints.JsonReturn) => void;
+    errors: endpoints.BuildEvent[];
 }
 
 interface JsonReturnState {
@@ -57,6 +58,7 @@ export class JsonReturn extends React.Component<
                     <code>
                         return Json({argToString(this.props.instruction)});
                     </code>
+                    <ErrorList errors={this.props.errors} />
                 </li>
             );
         }
@@ -77,6 +79,7 @@ export class JsonReturn extends React.Component<
                     stack={this.props.stack}
                     propChanged={this.propChanged.bind(this)}
                 />
+                <ErrorList errors={this.props.errors} />
             </li>
         );
     }
@@ -2,7 +2,7 @@ import * as React from "react";
 import cloneDeep = require("lodash/cloneDeep");
 import { CallMethod } from "./call_method";
 import { JsonReturn } from "./json_return";
-import { InstructionBase, Stack } from "./instruction_base";
+import { ErrorList, InstructionBase, Stack } from "./instruction_base";
 
 const instruction_renderers: { [key: string]: InstructionBase } = {
     CallMethod: CallMethod,
@@ -21,6 +21,13 @@ export class OverviewState {
     changeIndex: number;
     hasChanges: boolean;
     output: string | null;
+    /** Whether `output` was generated from unsaved changes, which cannot be downloaded */
+    previewed: boolean;
+    checking: boolean;
+    diagnostics: endpoints.BuildEvent[];
+    /** The trace being stepped through, and the index of the call shown */
+    trace: endpoints.Trace | null;
+    step: number;
 }
 
 export class Overview extends React.Component<OverviewProps, OverviewState> {
@@ -33,7 +40,12 @@ export class Overview extends React.Component<OverviewProps, OverviewState> {
             endpoint: cloneDeep(props.endpoint),
             hasChanges: false,
             changeIndex: props.changeIndex,
-            output: null
+            output: null,
+            previewed: false,
+            checking: false,
+            diagnostics: [],
+            trace: null,
+            step: 0
         };
         this.drag_from = React.createRef();
         this.drag_to = React.createRef();
@@ -48,6 +60,7 @@ export class Overview extends React.Component<OverviewProps, OverviewState> {
                 endpoint: cloneDeep(props.endpoint),
                 changeIndex: props.changeIndex,
                 hasChanges: false,
+                diagnostics: [],
             };
         } else {
             return null;
@@ -61,7 +74,8 @@ export class Overview extends React.Component<OverviewProps, OverviewState> {
         let endpoint = Object.assign({}, this.state.endpoint);
         endpoint.instructions.splice(index, 1);
 
-        this.setState({ endpoint, hasChanges: true });
+        // Diagnostics refer to instruction indices, which are shifted by this
+        this.setState({ endpoint, hasChanges: true, diagnostics: [] });
 
         return false;
     }
@@ -120,7 +134,8 @@ export class Overview extends React.Component<OverviewProps, OverviewState> {
         endpoint.instructions.splice(index, 0, instruction);
         this.setState({
             endpoint,
-            hasChanges: true
+            hasChanges: true,
+            diagnostics: []
         });
     }
 
@@ -140,16 +155,197 @@ export class Overview extends React.Component<OverviewProps, OverviewState> {
         });
     }
     generate() {
-        fetch("/api/generate/" + this.state.endpoint.id)
+        fetch("/api/generate/" + this.state.endpoint.id, { credentials: "same-origin" })
             .then(o => o.text())
             .then(t => {
                 this.setState({
-                    output: t
+                    output: t,
+                    previewed: false
+                });
+            });
+    }
+    preview() {
+        fetch("/api/preview", {
+            body: JSON.stringify(this.state.endpoint),
+            credentials: "same-origin",
+            headers: {
+                "content-type": "application/json"
+            },
+            method: "POST"
+        })
+            .then(r => r.json())
+            .then((preview: endpoints.Preview) => {
+                if (preview.diagnostics.length == 0) {
+                    let output = Object.keys(preview.files)
+                        .sort()
+                        .map(path => "== " + path + "\n" + preview.files[path])
+                        .join("\n");
+                    this.setState({ output, previewed: true, diagnostics: [] });
+                    return;
+                }
+                let endpoint = preview.diagnostics.filter(d => d.index === null);
+                if (endpoint.length > 0) {
+                    alert(endpoint.map(d => d.message).join("\n"));
+                }
+                let diagnostics = preview.diagnostics
+                    .filter(d => d.index !== null)
+                    .map(d => ({
+                        type: "diagnostic",
+                        instruction: d.index,
+                        diagnostic: { level: "error", message: d.message, spans: [], rendered: null }
+                    } as endpoints.BuildEvent));
+                this.setState({ diagnostics });
+            });
+    }
+    /** Ask for the value of every `{name}` segment of the url, or `null` if cancelled */
+    promptInputs(): { [name: string]: any } | null {
+        let inputs: { [name: string]: any } = {};
+        for (const segment of this.state.endpoint.url.match(/\{[^}]+\}/g) || []) {
+            let name = segment.slice(1, -1);
+            let value = prompt("Value for " + name);
+            if (value === null) {
+                return null;
+            }
+            try {
+                inputs[name] = JSON.parse(value);
+            } catch (e) {
+                inputs[name] = value;
+            }
+        }
+        return inputs;
+    }
+    run() {
+        let inputs = this.promptInputs();
+        if (inputs === null) {
+            return;
+        }
+        fetch("/api/run", {
+            body: JSON.stringify({ endpoint: this.state.endpoint, inputs }),
+            credentials: "same-origin",
+            headers: {
+                "content-type": "application/json"
+            },
+            method: "POST"
+        })
+            .then(r => r.json().then(body => ({ ok: r.ok, body })))
+            .then(({ ok, body }) => {
+                if (!ok) {
+                    alert(body.message);
+                    return;
+                }
+                let outcome: endpoints.Outcome = body;
+                this.setState({
+                    output: JSON.stringify(outcome, null, 2),
+                    previewed: true
                 });
             });
     }
+    debug() {
+        let inputs = this.promptInputs();
+        if (inputs === null) {
+            return;
+        }
+        fetch("/api/trace", {
+            body: JSON.stringify({ endpoint: this.state.endpoint, inputs }),
+            credentials: "same-origin",
+            headers: {
+                "content-type": "application/json"
+            },
+            method: "POST"
+        })
+            .then(r => r.json().then(body => ({ ok: r.ok, body })))
+            .then(({ ok, body }) => {
+                if (!ok) {
+                    alert(body.message);
+                    return;
+                }
+                this.setState({ trace: body as endpoints.Trace, step: 0 });
+            });
+    }
+    renderTrace(trace: endpoints.Trace) {
+        // One step for every call, and a last step for the result
+        let step = this.state.step;
+        let call = trace.calls[step];
+        return (
+            <>
+                <button
+                    className="btn float-right btn-primary"
+                    onClick={() => this.setState({ trace: null })}
+                >
+                    &times;
+                </button>
+                <button
+                    className="btn btn-secondary"
+                    disabled={step == 0}
+                    onClick={() => this.setState({ step: step - 1 })}
+                >
+                    Previous
+                </button>
+                <button
+                    className="btn btn-secondary"
+                    disabled={step >= trace.calls.length}
+                    onClick={() => this.setState({ step: step + 1 })}
+                >
+                    Next
+                </button>
+                {call ? (
+                    <>
+                        <h5>
+                            Call {step + 1} of {trace.calls.length}: instruction {call.instruction + 1},{" "}
+                            {call.module}::{call.method} ({call.duration_us} µs)
+                        </h5>
+                        <h6>Inputs</h6>
+                        <pre>
+                            <code>{JSON.stringify(call.inputs, null, 2)}</code>
+                        </pre>
+                        <h6>Output</h6>
+                        <pre>
+                            <code>{call.output === null ? "Failed" : JSON.stringify(call.output, null, 2)}</code>
+                        </pre>
+                    </>
+                ) : (
+                    <>
+                        <h5>{trace.error === null ? "Result" : "Error"}</h5>
+                        <pre>
+                            <code>
+                                {trace.error === null ? JSON.stringify(trace.outcome, null, 2) : trace.error}
+                            </code>
+                        </pre>
+                    </>
+                )}
+            </>
+        );
+    }
+    check() {
+        this.setState({ checking: true, diagnostics: [] });
+        fetch("/api/endpoints/" + this.state.endpoint.id + "/build", {
+            credentials: "same-origin",
+            method: "POST"
+        })
+            .then(r => r.text())
+            .then(t => {
+                let diagnostics = t
+                    .split("\n")
+                    .filter(line => line.length)
+                    .map(line => JSON.parse(line) as endpoints.BuildEvent)
+                    .filter(e => e.type == "diagnostic");
+                this.setState({ checking: false, diagnostics });
+            });
+    }
+    errorsFor(index: number) {
+        return this.state.diagnostics.filter(d => d.instruction === index);
+    }
     render() {
         let stack = new Stack();
+        let unmapped = this.state.diagnostics.filter(
+            d =>
+                d.instruction === null &&
+                d.diagnostic.level == "error" &&
+                d.diagnostic.spans.length > 0
+        );
+        if (this.state.trace !== null) {
+            return this.renderTrace(this.state.trace);
+        }
         if (this.state.output !== null) {
             return (
                 <>
@@ -159,6 +355,22 @@ export class Overview extends React.Component<OverviewProps, OverviewState> {
                     >
                         &times;
                     </button>
+                    {this.state.previewed ? null : (
+                        <>
+                            <a
+                                className="btn float-right btn-secondary"
+                                href={"/api/generate/" + this.state.endpoint.id + "?format=zip"}
+                            >
+                                Download .zip
+                            </a>
+                            <a
+                                className="btn float-right btn-secondary"
+                                href={"/api/generate/" + this.state.endpoint.id + "?format=tar.gz"}
+                            >
+                                Download .tar.gz
+                            </a>
+                        </>
+                    )}
                     <pre>
                         <code>{this.state.output}</code>
                     </pre>
@@ -179,8 +391,38 @@ export class Overview extends React.Component<OverviewProps, OverviewState> {
                             >
                                 {this.state.hasChanges ? "Save" : "Generate"}
                             </button>
+                            {this.state.hasChanges ? (
+                                <button
+                                    className="btn float-right btn-secondary"
+                                    onClick={this.preview.bind(this)}
+                                >
+                                    Preview
+                                </button>
+                            ) : null}
+                            <button
+                                className="btn float-right btn-secondary"
+                                onClick={this.run.bind(this)}
+                            >
+                                Run
+                            </button>
+                            <button
+                                className="btn float-right btn-secondary"
+                                onClick={this.debug.bind(this)}
+                            >
+                                Debug
+                            </button>
+                            {this.state.hasChanges ? null : (
+                                <button
+                                    className="btn float-right btn-secondary"
+                                    disabled={this.state.checking}
+                                    onClick={this.check.bind(this)}
+                                >
+                                    {this.state.checking ? "Checking..." : "Check"}
+                                </button>
+                            )}
                         </div>
                     </div>
+                    <ErrorList errors={unmapped} />
                     <ul ref={this.drag_to}>
                         {this.state.endpoint.instructions.map(
                             (i: endpoints.Instruction, index: number) => {
@@ -201,6 +443,7 @@ export class Overview extends React.Component<OverviewProps, OverviewState> {
                                             index,
                                             key
                                         )}
+                                        errors={this.errorsFor(index)}
                                     />
                                 );
                             }
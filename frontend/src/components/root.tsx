@@ -8,9 +8,18 @@ interface RootState {
     configs: endpoints.Config[];
     active: endpoints.Endpoint | null;
     activeIndex: number,
+    broken: endpoints.BrokenEndpoint[];
+    /** `null` until the session is known, `false` if nobody is logged in */
+    user: endpoints.User | false | null;
+    loginError: string | null;
+    /** The last change made by someone else */
+    notice: string | null;
 }
 
 export class Root extends React.Component<RootProps, RootState> {
+    /** The changes published by the server, see `subscribe` */
+    events: EventSource | null = null;
+
     constructor(props: RootProps, context?: any) {
         super(props, context);
         this.state = {
@@ -18,9 +27,72 @@ export class Root extends React.Component<RootProps, RootState> {
             configs: [],
             active: null,
             activeIndex: 0,
+            broken: [],
+            user: null,
+            loginError: null,
+            notice: null,
         };
 
-        fetch("/api/endpoints")
+        this.loadUser();
+    }
+
+    loadUser() {
+        fetch("/api/me", { credentials: "same-origin" }).then(r => {
+            if (r.status == 401) {
+                this.setState({ user: false });
+                return;
+            }
+            r.json().then((user: endpoints.User) => {
+                this.setState({ user });
+                this.loadEndpoints();
+                this.subscribe();
+            });
+        });
+    }
+
+    login(e: React.FormEvent<HTMLFormElement>) {
+        e.preventDefault();
+        let form = e.currentTarget;
+        let field = (name: string) => (form.elements.namedItem(name) as HTMLInputElement).value;
+        fetch("/api/login", {
+            body: JSON.stringify({ name: field("name"), password: field("password") }),
+            credentials: "same-origin",
+            headers: {
+                "content-type": "application/json"
+            },
+            method: "POST"
+        })
+            .then(r => r.json().then(body => ({ ok: r.ok, body })))
+            .then(({ ok, body }) => {
+                if (!ok) {
+                    this.setState({ loginError: body.message });
+                    return;
+                }
+                this.setState({ user: body, loginError: null });
+                this.loadEndpoints();
+                this.subscribe();
+            });
+    }
+
+    logout(e: React.MouseEvent<HTMLAnchorElement>) {
+        e.preventDefault();
+        if (this.events) {
+            this.events.close();
+            this.events = null;
+        }
+        fetch("/api/logout", { credentials: "same-origin", method: "POST" }).then(() =>
+            this.setState({
+                user: false,
+                endpoints: [],
+                configs: [],
+                active: null,
+                broken: [],
+            })
+        );
+    }
+
+    loadEndpoints() {
+        fetch("/api/endpoints", { credentials: "same-origin" })
             .then(r => r.json())
             .then((r: endpoints.RootObject) => {
                 let active =
@@ -34,9 +106,164 @@ export class Root extends React.Component<RootProps, RootState> {
                     endpoints: r.endpoints,
                     configs: r.configs,
                     active,
-                    activeIndex: 1,
+                    activeIndex: this.state.activeIndex + 1,
                 });
             });
+        this.loadBroken();
+    }
+
+    subscribe() {
+        if (this.events) {
+            this.events.close();
+        }
+        this.events = new EventSource("/api/events");
+        this.events.onmessage = (e: MessageEvent) => this.changed(JSON.parse(e.data));
+    }
+
+    /** Apply a change published by the server, which may have been made by this user */
+    changed(event: endpoints.ChangeEvent) {
+        let user = this.state.user;
+        if (user && user.name != event.actor) {
+            let name = event.name || event.id;
+            let notice =
+                event.type == "endpoint_saved" ? "saved " + name + " (version " + event.version + ")" :
+                event.type == "endpoint_deleted" ? "deleted an endpoint" :
+                event.type == "config_saved" ? "saved the config " + name :
+                "deleted a config";
+            this.setState({ notice: event.actor + " " + notice });
+        }
+        if (event.type == "endpoint_saved") {
+            let current = this.state.endpoints.find(e => e.id == event.id);
+            if (current && current.version !== undefined && current.version >= event.version!) {
+                return;
+            }
+            fetch("/api/endpoints/" + event.id, { credentials: "same-origin" })
+                .then(r => r.json())
+                .then((endpoint: endpoints.Endpoint) => this.replaceEndpoint(endpoint));
+        } else if (event.type == "endpoint_deleted") {
+            let endpoints = this.state.endpoints.filter(e => e.id != event.id);
+            let active = this.state.active;
+            if (active && active.id == event.id) {
+                active = endpoints[0] || null;
+            }
+            this.setState({
+                endpoints,
+                active,
+                activeIndex: this.state.activeIndex + 1,
+            });
+        } else {
+            fetch("/api/configs", { credentials: "same-origin" })
+                .then(r => r.json())
+                .then((configs: endpoints.Config[]) => this.setState({ configs }));
+        }
+    }
+
+    /** Show a newer version of an endpoint, or a new endpoint */
+    replaceEndpoint(endpoint: endpoints.Endpoint) {
+        let index = this.state.endpoints.findIndex(e => e.id == endpoint.id);
+        let endpoints = Object.assign([], this.state.endpoints);
+        if (index == -1) {
+            endpoints.push(endpoint);
+            endpoints.sort((a, b) => a.name.localeCompare(b.name));
+        } else {
+            endpoints[index] = endpoint;
+        }
+        let active = this.state.active;
+        if (active && active.id == endpoint.id) {
+            this.setState({
+                endpoints,
+                active: endpoint,
+                activeIndex: this.state.activeIndex + 1,
+            });
+        } else {
+            this.setState({ endpoints });
+        }
+        this.loadBroken();
+    }
+
+    importProject(e: React.ChangeEvent<HTMLInputElement>) {
+        let input = e.currentTarget;
+        let file = input.files && input.files[0];
+        if (!file) {
+            return;
+        }
+        let yaml = /\.ya?ml$/.test(file.name);
+        fetch("/api/project", {
+            body: file,
+            credentials: "same-origin",
+            headers: {
+                "content-type": yaml ? "application/x-yaml" : "application/json"
+            },
+            method: "POST"
+        })
+            .then(r => r.json().then(body => ({ ok: r.ok, body })))
+            .then(({ ok, body }) => {
+                input.value = "";
+                if (!ok) {
+                    alert(body.message);
+                    return;
+                }
+                let result: endpoints.ImportResult = body;
+                let changed = result.endpoints.filter(e => e.status != "unchanged");
+                alert(
+                    "Imported " + changed.length + " of " + result.endpoints.length + " endpoints" +
+                    (result.broken.length > 0 ? ", " + result.broken.length + " are broken" : "")
+                );
+                this.loadEndpoints();
+            });
+    }
+
+    loadBroken() {
+        fetch("/api/endpoints/broken", { credentials: "same-origin" })
+            .then(r => r.json())
+            .then((broken: endpoints.BrokenEndpoint[]) => this.setState({ broken }));
+    }
+
+    fixEndpoint(broken: endpoints.BrokenEndpoint, e: React.MouseEvent<HTMLButtonElement>) {
+        e.preventDefault();
+        fetch("/api/endpoints/" + broken.id + "/fix", { credentials: "same-origin", method: "POST" })
+            .then(r => r.json())
+            .then((r: endpoints.FixResult) => {
+                let index = this.state.endpoints.findIndex(e => e.id == r.endpoint.id);
+                let endpoints = Object.assign([], this.state.endpoints);
+                endpoints[index] = r.endpoint;
+                let active =
+                    this.state.active && this.state.active.id == r.endpoint.id
+                        ? r.endpoint
+                        : this.state.active;
+                this.setState({
+                    endpoints,
+                    active,
+                    activeIndex: this.state.activeIndex + 1,
+                });
+                this.loadBroken();
+            });
+    }
+
+    renderBroken(endpoint: endpoints.Endpoint) {
+        let broken = this.state.broken.find(b => b.id == endpoint.id);
+        if (!broken) {
+            return null;
+        }
+        let problems = broken.instructions
+            .map(i => "Instruction " + (i.index + 1) + ": " + i.problems.map(p => p.problem).join(", "))
+            .join("\n");
+        return (
+            <span>
+                {" "}
+                <span className="badge badge-danger" title={problems}>
+                    broken
+                </span>
+                {broken.fixable ? (
+                    <button
+                        className="btn btn-sm btn-link"
+                        onClick={this.fixEndpoint.bind(this, broken)}
+                    >
+                        fix
+                    </button>
+                ) : null}
+            </span>
+        );
     }
 
     renderEndpoint(endpoint: endpoints.Endpoint, index: number) {
@@ -52,6 +279,7 @@ export class Root extends React.Component<RootProps, RootState> {
                     onClick={this.selectEndpoint.bind(this, endpoint)}
                 >
                     {endpoint.name}
+                    {this.renderBroken(endpoint)}
                 </a>
             </li>
         );
@@ -67,12 +295,22 @@ export class Root extends React.Component<RootProps, RootState> {
 
         fetch("/api/endpoints", {
             body: JSON.stringify(endpoint),
+            credentials: "same-origin",
             headers: {
                 "content-type": "application/json"
             },
             method: "POST"
         })
-            .then(r => r.json())
+            .then(r => {
+                if (r.status == 409 || r.status == 403) {
+                    return r.json().then(e => {
+                        alert(e.message);
+                        return fetch("/api/endpoints/" + endpoint.id, { credentials: "same-origin" })
+                            .then(r => r.json());
+                    });
+                }
+                return r.json();
+            })
             .then(r => {
                 let index = this.state.endpoints.findIndex(e => e.id == r.id);
                 let endpoints = Object.assign([], this.state.endpoints);
@@ -82,6 +320,7 @@ export class Root extends React.Component<RootProps, RootState> {
                     active: r,
                     activeIndex: this.state.activeIndex + 1,
                 });
+                this.loadBroken();
             });
     }
 
@@ -96,11 +335,65 @@ export class Root extends React.Component<RootProps, RootState> {
         e.currentTarget.blur();
     }
 
+    renderLogin() {
+        return (
+            <form className="mx-auto mt-5" style={{ maxWidth: "20rem" }} onSubmit={this.login.bind(this)}>
+                <div className="form-group">
+                    <label htmlFor="login-name">Name</label>
+                    <input id="login-name" name="name" className="form-control" autoFocus />
+                </div>
+                <div className="form-group">
+                    <label htmlFor="login-password">Password</label>
+                    <input id="login-password" name="password" type="password" className="form-control" />
+                </div>
+                {this.state.loginError ? (
+                    <div className="alert alert-danger">{this.state.loginError}</div>
+                ) : null}
+                <button type="submit" className="btn btn-primary">
+                    Log in
+                </button>
+            </form>
+        );
+    }
+
     render() {
+        let user = this.state.user;
+        if (user === null) {
+            return null;
+        }
+        if (user === false) {
+            return this.renderLogin();
+        }
         return (
             <div>
+                {this.state.notice ? (
+                    <div className="alert alert-info mb-0" onClick={() => this.setState({ notice: null })}>
+                        {this.state.notice}
+                    </div>
+                ) : null}
                 <ul className="nav nav-tabs">
                     {this.state.endpoints.map(this.renderEndpoint.bind(this))}
+                    <li className="nav-item ml-auto">
+                        <a className="nav-link" href="/api/project?format=yaml">
+                            Export project
+                        </a>
+                    </li>
+                    <li className="nav-item">
+                        <label className="nav-link mb-0">
+                            Import project
+                            <input
+                                type="file"
+                                accept=".json,.yaml,.yml"
+                                hidden
+                                onChange={this.importProject.bind(this)}
+                            />
+                        </label>
+                    </li>
+                    <li className="nav-item">
+                        <a className="nav-link" href="#" title={user.role} onClick={this.logout.bind(this)}>
+                            Log out {user.name}
+                        </a>
+                    </li>
                 </ul>
                 {this.state.active ? (
                     <Overview
@@ -37,6 +37,9 @@ fn main() {
                     description: get_docs(&f.attrs),
                     input: Vec::new(),
                     output: Vec::new(),
+                    is_async: false,
+                    mock: None,
+                    renamed_from: Vec::new(),
                 };
                 if let syn::ReturnType::Type(_, ty) = &f.decl.output {
                     let mut output = Output {
@@ -57,6 +60,7 @@ fn main() {
                         name: get_parameter_name(&input),
                         description: "".to_string(),
                         value_type: resolver.get_fn_arg_type(&input),
+                        renamed_from: Vec::new(),
                     };
                     let mut line_iter = method.description.lines();
                     while let Some(line) = line_iter.next() {
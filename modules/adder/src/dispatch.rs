@@ -0,0 +1,19 @@
+//! Generated from the module.json of `adder` by `rust_functional dispatch`, do not edit
+
+use serde_json::{self, Value};
+
+/// Call a method of this module by name, with one JSON value for every input of the method
+pub fn call(method: &str, args: Vec<Value>) -> Result<Value, String> {
+    let mut args = args.into_iter();
+    match method {
+        "add" => {
+            let a = serde_json::from_value(args.next().unwrap_or(Value::Null))
+                .map_err(|e| format!("Invalid value for a: {}", e))?;
+            let b = serde_json::from_value(args.next().unwrap_or(Value::Null))
+                .map_err(|e| format!("Invalid value for b: {}", e))?;
+            serde_json::to_value(super::add(a, b))
+                .map_err(|e| format!("Could not serialize: {}", e))
+        }
+        _ => Err(format!("Unknown method {:?}", method)),
+    }
+}
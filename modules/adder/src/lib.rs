@@ -1,9 +1,13 @@
 //! Module with several adding methods
 
+extern crate serde_json;
+
+pub mod dispatch;
+
 /// Add numbers A and B together
 ///
 /// * `a`: Numeric value a
-///        Between 0 and 100
+///   Between 0 and 100
 /// * `b`: Numeric value b
 ///
 /// return: Values A and B added together
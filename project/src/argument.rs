@@ -0,0 +1,135 @@
+use rust_functional::InstructionParameter;
+use std::error::Error as StdError;
+use std::fmt;
+
+/// A named argument passed to a module method
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallMethodArgument {
+    pub name: String,
+    pub arg_type: ArgType,
+    pub arg_type_value: String,
+}
+
+impl<'a> From<&'a CallMethodArgument> for (String, InstructionParameter) {
+    fn from(m: &'a CallMethodArgument) -> (String, InstructionParameter) {
+        (
+            m.name.clone(),
+            m.arg_type
+                .parse(&m.arg_type_value)
+                .expect("Instruction arguments are validated before generating"),
+        )
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
+pub enum ArgType {
+    Parameter,
+    String,
+    Number,
+    Float,
+}
+
+impl ArgType {
+    /// Parse a stored `arg_type_value` into the parameter it represents
+    pub fn parse(self, value: &str) -> Result<InstructionParameter, InvalidArgument> {
+        let invalid = || InvalidArgument {
+            arg_type: self,
+            value: value.to_string(),
+        };
+        Ok(match self {
+            ArgType::Parameter => InstructionParameter::Variable(value.to_string()),
+            ArgType::String => InstructionParameter::String(value.to_string()),
+            ArgType::Number => {
+                InstructionParameter::Number(value.trim().parse().map_err(|_| invalid())?)
+            }
+            // `inf` and `NaN` parse, but cannot be written as a literal
+            ArgType::Float => match value.trim().parse::<f32>() {
+                Ok(float) if float.is_finite() => InstructionParameter::Float(float),
+                _ => return Err(invalid()),
+            },
+        })
+    }
+
+    /// Get the `arg_type` and `arg_type_value` to store the given parameter as
+    pub fn from_parameter(parameter: &InstructionParameter) -> (ArgType, String) {
+        match parameter {
+            InstructionParameter::Variable(name) => (ArgType::Parameter, name.clone()),
+            InstructionParameter::String(value) => (ArgType::String, value.clone()),
+            InstructionParameter::Number(value) => (ArgType::Number, value.to_string()),
+            InstructionParameter::Float(value) => (ArgType::Float, value.to_string()),
+        }
+    }
+}
+
+/// An argument value that can not be parsed as its `ArgType`
+#[derive(Debug)]
+pub struct InvalidArgument {
+    pub arg_type: ArgType,
+    pub value: String,
+}
+
+impl fmt::Display for InvalidArgument {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "{:?} is not a valid {:?} argument",
+            self.value, self.arg_type
+        )
+    }
+}
+
+impl StdError for InvalidArgument {
+    fn description(&self) -> &str {
+        "Invalid argument"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_are_whole_i32s() {
+        match ArgType::Number.parse(" -12\n") {
+            Ok(InstructionParameter::Number(value)) => assert_eq!(-12, value),
+            result => panic!("Expected a number, got {:?}", result),
+        }
+        for value in &["", "1.5", "1e3", "2147483648", "twelve"] {
+            let error = ArgType::Number.parse(value).unwrap_err();
+            assert_eq!(ArgType::Number, error.arg_type);
+            assert_eq!(*value, error.value);
+        }
+        assert_eq!(
+            "\"1.5\" is not a valid Number argument",
+            ArgType::Number.parse("1.5").unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn parameters_are_stored_as_they_are_parsed() {
+        let parameters = vec![
+            InstructionParameter::Variable("x".to_string()),
+            InstructionParameter::String(" 1 ".to_string()),
+            InstructionParameter::Number(-3),
+            InstructionParameter::Float(0.25),
+        ];
+        for parameter in parameters {
+            let (arg_type, value) = ArgType::from_parameter(&parameter);
+            assert_eq!(
+                format!("{:?}", parameter),
+                format!("{:?}", arg_type.parse(&value).unwrap())
+            );
+        }
+    }
+
+    #[test]
+    fn floats_are_finite() {
+        match ArgType::Float.parse(" 2.5 ") {
+            Ok(InstructionParameter::Float(value)) => assert_eq!(2.5, value),
+            result => panic!("Expected a float, got {:?}", result),
+        }
+        for value in &["inf", "-inf", "NaN", "1e40", "two"] {
+            assert!(ArgType::Float.parse(value).is_err(), "{}", value);
+        }
+    }
+}
@@ -0,0 +1,15 @@
+extern crate rust_functional;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate serde_yaml;
+extern crate uuid;
+
+mod argument;
+mod project;
+mod validation;
+
+pub use argument::{ArgType, CallMethodArgument, InvalidArgument};
+pub use project::{Format, Instruction, Module, Project, ProjectEndpoint, ProjectError, VERSION};
+pub use validation::{check_call, fix_call, Problem};
@@ -0,0 +1,253 @@
+use argument::{ArgType, CallMethodArgument};
+use serde_json;
+use serde_yaml;
+use std::collections::HashSet;
+use std::error::Error as StdError;
+use std::fmt;
+use uuid::Uuid;
+
+/// The version of the project format written by this crate. Older versions can still be read.
+pub const VERSION: u32 = 1;
+
+/// A portable set of endpoints and the modules they call. Modules are referenced by name
+/// instead of by database id, so a project can be stored in git and moved between instances.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Project {
+    pub version: u32,
+    #[serde(default)]
+    pub modules: Vec<Module>,
+    #[serde(default)]
+    pub endpoints: Vec<ProjectEndpoint>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Module {
+    pub name: String,
+    /// The directory containing the `module.json` of this module
+    pub path: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectEndpoint {
+    pub id: Uuid,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub url: String,
+    #[serde(default)]
+    pub instructions: Vec<Instruction>,
+}
+
+/// An instruction of an endpoint, with the module it calls referenced by name
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Instruction {
+    CallMethod {
+        id: Uuid,
+        module: String,
+        method: String,
+        out_variable_name: String,
+        #[serde(default)]
+        arguments: Vec<CallMethodArgument>,
+    },
+    JsonReturn {
+        id: Uuid,
+        arg_type: ArgType,
+        arg_type_value: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Yaml,
+}
+
+impl Format {
+    /// Parse a file extension, e.g. `json` or `yaml`
+    pub fn from_extension(extension: &str) -> Option<Format> {
+        match extension {
+            "json" => Some(Format::Json),
+            "yaml" | "yml" => Some(Format::Yaml),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Yaml => "yaml",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Format::Json => "application/json",
+            Format::Yaml => "application/x-yaml",
+        }
+    }
+}
+
+impl Project {
+    /// Parse and validate a project document
+    pub fn read(format: Format, bytes: &[u8]) -> Result<Project, ProjectError> {
+        let project: Project = match format {
+            Format::Json => serde_json::from_slice(bytes).map_err(ProjectError::Json)?,
+            Format::Yaml => serde_yaml::from_slice(bytes).map_err(ProjectError::Yaml)?,
+        };
+        project.validate()?;
+        Ok(project)
+    }
+
+    pub fn write(&self, format: Format) -> Result<Vec<u8>, ProjectError> {
+        match format {
+            Format::Json => serde_json::to_vec_pretty(self).map_err(ProjectError::Json),
+            Format::Yaml => serde_yaml::to_string(self)
+                .map(String::into_bytes)
+                .map_err(ProjectError::Yaml),
+        }
+    }
+
+    /// Check the version, and that every module is declared once and every called module is declared
+    pub fn validate(&self) -> Result<(), ProjectError> {
+        if self.version > VERSION {
+            return Err(ProjectError::UnsupportedVersion(self.version));
+        }
+        let mut modules = HashSet::new();
+        for module in &self.modules {
+            if !modules.insert(module.name.as_str()) {
+                return Err(ProjectError::DuplicateModule(module.name.clone()));
+            }
+        }
+        for endpoint in &self.endpoints {
+            for instruction in &endpoint.instructions {
+                if let Instruction::CallMethod { module, .. } = instruction {
+                    if !modules.contains(module.as_str()) {
+                        return Err(ProjectError::UnknownModule {
+                            endpoint: endpoint.name.clone(),
+                            module: module.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn module(&self, name: &str) -> Option<&Module> {
+        self.modules.iter().find(|m| m.name == name)
+    }
+}
+
+#[derive(Debug)]
+pub enum ProjectError {
+    Json(serde_json::Error),
+    Yaml(serde_yaml::Error),
+    /// The project was written by a newer version
+    UnsupportedVersion(u32),
+    DuplicateModule(String),
+    /// An endpoint calls a module that is not declared in `modules`
+    UnknownModule { endpoint: String, module: String },
+}
+
+impl fmt::Display for ProjectError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ProjectError::Json(e) => write!(fmt, "Invalid project: {}", e),
+            ProjectError::Yaml(e) => write!(fmt, "Invalid project: {}", e),
+            ProjectError::UnsupportedVersion(version) => write!(
+                fmt,
+                "Project version {} is not supported, expected {} or lower",
+                version, VERSION
+            ),
+            ProjectError::DuplicateModule(name) => write!(fmt, "Duplicate module {:?}", name),
+            ProjectError::UnknownModule { endpoint, module } => write!(
+                fmt,
+                "Endpoint {:?} calls module {:?}, which is not declared",
+                endpoint, module
+            ),
+        }
+    }
+}
+
+impl StdError for ProjectError {
+    fn description(&self) -> &str {
+        match self {
+            ProjectError::Json(_) | ProjectError::Yaml(_) => "Invalid project",
+            ProjectError::UnsupportedVersion(_) => "Unsupported project version",
+            ProjectError::DuplicateModule(_) => "Duplicate module",
+            ProjectError::UnknownModule { .. } => "Unknown module",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project() -> Project {
+        Project {
+            version: VERSION,
+            modules: vec![Module {
+                name: "adder".to_string(),
+                path: "modules/adder".to_string(),
+            }],
+            endpoints: vec![ProjectEndpoint {
+                id: Uuid::nil(),
+                name: "add".to_string(),
+                description: String::new(),
+                url: "/add".to_string(),
+                instructions: vec![Instruction::CallMethod {
+                    id: Uuid::nil(),
+                    module: "adder".to_string(),
+                    method: "add".to_string(),
+                    out_variable_name: "out".to_string(),
+                    arguments: vec![CallMethodArgument {
+                        name: "a".to_string(),
+                        arg_type: ArgType::Number,
+                        arg_type_value: "1".to_string(),
+                    }],
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn formats_round_trip() {
+        let project = project();
+        for format in &[Format::Json, Format::Yaml] {
+            let bytes = project.write(*format).unwrap();
+            assert_eq!(project, Project::read(*format, &bytes).unwrap(), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn validate() {
+        assert!(project().validate().is_ok());
+
+        let mut newer = project();
+        newer.version = VERSION + 1;
+        match newer.validate() {
+            Err(ProjectError::UnsupportedVersion(version)) => assert_eq!(VERSION + 1, version),
+            result => panic!("Expected UnsupportedVersion, got {:?}", result),
+        }
+
+        let mut duplicate = project();
+        let module = duplicate.modules[0].clone();
+        duplicate.modules.push(module);
+        match duplicate.validate() {
+            Err(ProjectError::DuplicateModule(ref name)) if name == "adder" => {}
+            result => panic!("Expected DuplicateModule, got {:?}", result),
+        }
+
+        let mut unknown = project();
+        unknown.modules.clear();
+        match unknown.validate() {
+            Err(ProjectError::UnknownModule { ref endpoint, ref module })
+                if endpoint == "add" && module == "adder" => {}
+            result => panic!("Expected UnknownModule, got {:?}", result),
+        }
+        // `read` validates as well
+        let bytes = unknown.write(Format::Json).unwrap();
+        assert!(Project::read(Format::Json, &bytes).is_err());
+    }
+}
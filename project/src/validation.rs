@@ -0,0 +1,328 @@
+use argument::{ArgType, CallMethodArgument};
+use rust_functional::{Config, Method, ParameterType};
+use std::fmt;
+use uuid::Uuid;
+
+/// A reason an instruction does not match the current module configs
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "problem", rename_all = "snake_case")]
+pub enum Problem {
+    /// The config the instruction calls into was deleted
+    MissingConfig { config: Uuid },
+    /// The method does not exist. `renamed_to` is set if a method declares it in `renamed_from`.
+    MissingMethod {
+        method: String,
+        renamed_to: Option<String>,
+    },
+    /// The method has an input that the instruction does not pass
+    MissingArgument { name: String },
+    /// The instruction passes an argument the method does not have.
+    /// `renamed_to` is set if an input declares it in `renamed_from`.
+    UnknownArgument {
+        name: String,
+        renamed_to: Option<String>,
+    },
+    /// A literal argument does not match the type of the input anymore
+    TypeMismatch { name: String, arg_type: ArgType },
+}
+
+impl Problem {
+    /// Whether `fix_call` can resolve this problem without user input
+    pub fn is_fixable(&self) -> bool {
+        match self {
+            Problem::MissingMethod { renamed_to, .. }
+            | Problem::UnknownArgument { renamed_to, .. } => renamed_to.is_some(),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Problem::MissingConfig { config } => write!(fmt, "Config {} does not exist", config),
+            Problem::MissingMethod {
+                method,
+                renamed_to: Some(to),
+            } => write!(fmt, "Method {:?} was renamed to {:?}", method, to),
+            Problem::MissingMethod { method, .. } => {
+                write!(fmt, "Method {:?} does not exist", method)
+            }
+            Problem::MissingArgument { name } => write!(fmt, "Argument {:?} is missing", name),
+            Problem::UnknownArgument {
+                name,
+                renamed_to: Some(to),
+            } => write!(fmt, "Argument {:?} was renamed to {:?}", name, to),
+            Problem::UnknownArgument { name, .. } => {
+                write!(fmt, "Argument {:?} does not exist", name)
+            }
+            Problem::TypeMismatch { name, arg_type } => write!(
+                fmt,
+                "Argument {:?} can not be passed as a {:?}",
+                name, arg_type
+            ),
+        }
+    }
+}
+
+/// Check a call to `method` of the given module against its `module.json`
+pub fn check_call(module: &Config, method: &str, arguments: &[CallMethodArgument]) -> Vec<Problem> {
+    let found = match module.methods.iter().find(|m| m.name == method) {
+        Some(m) => m,
+        None => {
+            return vec![Problem::MissingMethod {
+                method: method.to_string(),
+                renamed_to: renamed(&module.methods, method),
+            }]
+        }
+    };
+
+    let mut problems = Vec::new();
+    for argument in arguments {
+        match found.input.iter().find(|i| i.name == argument.name) {
+            Some(input) => {
+                if !accepts(&input.value_type, argument.arg_type) {
+                    problems.push(Problem::TypeMismatch {
+                        name: argument.name.clone(),
+                        arg_type: argument.arg_type,
+                    });
+                }
+            }
+            None => problems.push(Problem::UnknownArgument {
+                name: argument.name.clone(),
+                renamed_to: found
+                    .input
+                    .iter()
+                    .find(|i| i.renamed_from.contains(&argument.name))
+                    .map(|i| i.name.clone()),
+            }),
+        }
+    }
+    for input in &found.input {
+        let passed = arguments.iter().any(|a| {
+            a.name == input.name || input.renamed_from.contains(&a.name)
+        });
+        if !passed {
+            problems.push(Problem::MissingArgument {
+                name: input.name.clone(),
+            });
+        }
+    }
+    problems
+}
+
+fn renamed(methods: &[Method], name: &str) -> Option<String> {
+    methods
+        .iter()
+        .find(|m| m.renamed_from.iter().any(|n| n == name))
+        .map(|m| m.name.clone())
+}
+
+/// Whether a literal of the given type can be passed to an input. Variables are not checked,
+/// because their type is only known when the generated crate is compiled.
+fn accepts(value_type: &ParameterType, arg_type: ArgType) -> bool {
+    matches!(
+        (value_type, arg_type),
+        (_, ArgType::Parameter)
+            | (ParameterType::Unknown, _)
+            | (ParameterType::Numeric(_), ArgType::Number)
+            | (ParameterType::Numeric(_), ArgType::Float)
+            | (ParameterType::String(_), ArgType::String)
+    )
+}
+
+/// Apply the renames declared in the module config to a call.
+/// Returns the number of method and argument names that were changed.
+pub fn fix_call(
+    module: &Config,
+    method: &mut String,
+    arguments: &mut [CallMethodArgument],
+) -> usize {
+    let mut fixed = 0;
+    if !module.methods.iter().any(|m| &m.name == method) {
+        if let Some(to) = renamed(&module.methods, method) {
+            *method = to;
+            fixed += 1;
+        }
+    }
+    let found = match module.methods.iter().find(|m| &m.name == method) {
+        Some(m) => m,
+        None => return fixed,
+    };
+    for input in &found.input {
+        if arguments.iter().any(|a| a.name == input.name) {
+            continue;
+        }
+        if let Some(argument) = arguments
+            .iter_mut()
+            .find(|a| input.renamed_from.contains(&a.name))
+        {
+            argument.name = input.name.clone();
+            fixed += 1;
+        }
+    }
+    fixed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A module with `sum(a: numeric, b: numeric)`, formerly `add(x, b)`, and `greet(name: string)`
+    fn module() -> Config {
+        ::serde_json::from_str(
+            r#"{
+                "name": "adder",
+                "description": "",
+                "methods": [
+                    {
+                        "name": "sum",
+                        "description": "",
+                        "renamed_from": ["add"],
+                        "input": [
+                            {
+                                "name": "a",
+                                "description": "",
+                                "type": { "type": "numeric" },
+                                "renamed_from": ["x"]
+                            },
+                            { "name": "b", "description": "", "type": { "type": "numeric" } }
+                        ],
+                        "output": []
+                    },
+                    {
+                        "name": "greet",
+                        "description": "",
+                        "input": {
+                            "name": "name",
+                            "description": "",
+                            "type": { "type": "string" }
+                        },
+                        "output": []
+                    }
+                ]
+                }"#,
+        )
+        .unwrap()
+    }
+
+    fn argument(name: &str, arg_type: ArgType, value: &str) -> CallMethodArgument {
+        CallMethodArgument {
+            name: name.to_string(),
+            arg_type,
+            arg_type_value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn valid_calls_have_no_problems() {
+        let arguments = [
+            argument("a", ArgType::Number, "1"),
+            argument("b", ArgType::Parameter, "x"),
+        ];
+        assert_eq!(Vec::<Problem>::new(), check_call(&module(), "sum", &arguments));
+        let arguments = [argument("name", ArgType::String, "world")];
+        assert_eq!(Vec::<Problem>::new(), check_call(&module(), "greet", &arguments));
+    }
+
+    #[test]
+    fn missing_methods() {
+        let problems = check_call(&module(), "add", &[]);
+        assert_eq!(
+            vec![Problem::MissingMethod {
+                method: "add".to_string(),
+                renamed_to: Some("sum".to_string()),
+            }],
+            problems
+        );
+        assert!(problems[0].is_fixable());
+
+        let problems = check_call(&module(), "subtract", &[]);
+        assert_eq!(
+            vec![Problem::MissingMethod {
+                method: "subtract".to_string(),
+                renamed_to: None,
+            }],
+            problems
+        );
+        assert!(!problems[0].is_fixable());
+        assert_eq!("Method \"subtract\" does not exist", problems[0].to_string());
+    }
+
+    #[test]
+    fn missing_and_unknown_arguments() {
+        let arguments = [
+            argument("x", ArgType::Number, "1"),
+            argument("c", ArgType::Number, "2"),
+        ];
+        assert_eq!(
+            vec![
+                Problem::UnknownArgument {
+                    name: "x".to_string(),
+                    renamed_to: Some("a".to_string()),
+                },
+                Problem::UnknownArgument {
+                    name: "c".to_string(),
+                    renamed_to: None,
+                },
+                Problem::MissingArgument {
+                    name: "b".to_string(),
+                },
+            ],
+            check_call(&module(), "sum", &arguments)
+        );
+    }
+
+    #[test]
+    fn literals_must_match_the_input_type() {
+        let arguments = [
+            argument("a", ArgType::Float, "1.5"),
+            argument("b", ArgType::String, "2"),
+        ];
+        assert_eq!(
+            vec![Problem::TypeMismatch {
+                name: "b".to_string(),
+                arg_type: ArgType::String,
+            }],
+            check_call(&module(), "sum", &arguments)
+        );
+        let arguments = [argument("name", ArgType::Number, "1")];
+        let problems = check_call(&module(), "greet", &arguments);
+        assert_eq!(
+            vec![Problem::TypeMismatch {
+                name: "name".to_string(),
+                arg_type: ArgType::Number,
+            }],
+            problems
+        );
+        assert!(!problems[0].is_fixable());
+    }
+
+    #[test]
+    fn renames_are_fixed() {
+        let module = module();
+        let mut method = "add".to_string();
+        let mut arguments = [
+            argument("x", ArgType::Number, "1"),
+            argument("b", ArgType::Number, "2"),
+            argument("c", ArgType::Number, "3"),
+        ];
+        assert_eq!(2, fix_call(&module, &mut method, &mut arguments));
+        assert_eq!("sum", method);
+        assert_eq!("a", arguments[0].name);
+        assert_eq!("1", arguments[0].arg_type_value);
+        // Problems without a declared rename are left alone
+        assert_eq!(
+            vec![Problem::UnknownArgument {
+                name: "c".to_string(),
+                renamed_to: None,
+            }],
+            check_call(&module, &method, &arguments)
+        );
+        assert_eq!(0, fix_call(&module, &mut method, &mut arguments));
+
+        let mut method = "subtract".to_string();
+        assert_eq!(0, fix_call(&module, &mut method, &mut arguments));
+        assert_eq!("subtract", method);
+    }
+}
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE service_endpoint;
+DROP TABLE service;
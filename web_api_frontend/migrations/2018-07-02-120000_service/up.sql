@@ -0,0 +1,13 @@
+CREATE TABLE service (
+    id UUID PRIMARY KEY DEFAULT (uuid_generate_v4()),
+    name TEXT NOT NULL UNIQUE,
+    description TEXT NOT NULL
+);
+
+CREATE TABLE service_endpoint (
+    service UUID NOT NULL REFERENCES service(id) ON DELETE CASCADE,
+    endpoint UUID NOT NULL REFERENCES endpoint(id) ON DELETE CASCADE,
+    prefix TEXT NOT NULL,
+
+    PRIMARY KEY (service, endpoint)
+);
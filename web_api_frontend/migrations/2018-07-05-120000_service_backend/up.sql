@@ -0,0 +1 @@
+ALTER TABLE service ADD COLUMN backend TEXT NOT NULL DEFAULT 'actix';
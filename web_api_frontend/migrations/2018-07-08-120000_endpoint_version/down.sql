@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER set_updated_at ON endpoint;
+ALTER TABLE endpoint DROP COLUMN updated_at;
+ALTER TABLE endpoint DROP COLUMN version;
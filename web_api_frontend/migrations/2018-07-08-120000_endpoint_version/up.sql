@@ -0,0 +1,3 @@
+ALTER TABLE endpoint ADD COLUMN version INT NOT NULL DEFAULT 1;
+ALTER TABLE endpoint ADD COLUMN updated_at TIMESTAMP NOT NULL DEFAULT NOW();
+SELECT diesel_manage_updated_at('endpoint');
@@ -0,0 +1,10 @@
+CREATE TABLE endpoint_revision (
+    id UUID PRIMARY KEY DEFAULT (uuid_generate_v4()),
+    endpoint UUID NOT NULL REFERENCES endpoint(id) ON DELETE CASCADE,
+    version INT NOT NULL,
+    author TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    snapshot JSONB NOT NULL,
+
+    UNIQUE(endpoint, version)
+);
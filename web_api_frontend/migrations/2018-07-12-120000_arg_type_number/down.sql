@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE instruction_call_module_parameter DROP CONSTRAINT instruction_call_module_parameter_arg_type;
+ALTER TABLE instruction_json_return DROP CONSTRAINT instruction_json_return_arg_type;
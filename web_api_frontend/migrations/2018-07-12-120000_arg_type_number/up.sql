@@ -0,0 +1,5 @@
+-- arg_type: 1 = Parameter, 2 = String, 3 = Number, 4 = Float
+ALTER TABLE instruction_json_return ADD CONSTRAINT instruction_json_return_arg_type
+    CHECK (arg_type BETWEEN 1 AND 4);
+ALTER TABLE instruction_call_module_parameter ADD CONSTRAINT instruction_call_module_parameter_arg_type
+    CHECK (arg_type BETWEEN 1 AND 4);
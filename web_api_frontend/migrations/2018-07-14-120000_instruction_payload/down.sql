@@ -0,0 +1,77 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER config_in_use ON config;
+DROP FUNCTION config_in_use();
+
+CREATE TABLE instruction_json_return (
+    instruction_id UUID NOT NULL REFERENCES instruction(id) ON DELETE CASCADE,
+    arg_type SMALLINT NOT NULL,
+    arg_type_value TEXT NOT NULL,
+
+    PRIMARY KEY (instruction_id),
+    CONSTRAINT instruction_json_return_arg_type CHECK (arg_type BETWEEN 1 AND 4)
+);
+
+CREATE TABLE instruction_call_module (
+    instruction_id UUID NOT NULL REFERENCES instruction(id) ON DELETE CASCADE,
+    config UUID NOT NULL REFERENCES config(id),
+    method TEXT NOT NULL,
+    out_variable_name TEXT NOT NULL,
+
+    PRIMARY KEY (instruction_id)
+);
+
+CREATE TABLE instruction_call_module_parameter (
+    instruction_call_module_id UUID NOT NULL REFERENCES instruction_call_module(instruction_id) ON DELETE CASCADE,
+    sequence SMALLINT NOT NULL,
+    name TEXT NOT NULL,
+    arg_type SMALLINT NOT NULL,
+    arg_type_value TEXT NOT NULL,
+
+    PRIMARY KEY (instruction_call_module_id, sequence),
+    CONSTRAINT instruction_call_module_parameter_arg_type CHECK (arg_type BETWEEN 1 AND 4)
+);
+
+CREATE FUNCTION arg_type_value(name TEXT) RETURNS SMALLINT AS $$
+    SELECT CASE name
+        WHEN 'Parameter' THEN 1
+        WHEN 'String' THEN 2
+        WHEN 'Number' THEN 3
+        WHEN 'Float' THEN 4
+    END::SMALLINT;
+$$ LANGUAGE SQL IMMUTABLE;
+
+ALTER TABLE instruction ADD COLUMN type SMALLINT;
+
+UPDATE instruction SET type = 1 WHERE payload ? 'CallMethod';
+UPDATE instruction SET type = 2 WHERE payload ? 'JsonReturn';
+
+INSERT INTO instruction_call_module (instruction_id, config, method, out_variable_name)
+SELECT id,
+    (payload->'CallMethod'->>'config')::UUID,
+    payload->'CallMethod'->>'method',
+    payload->'CallMethod'->>'out_variable_name'
+FROM instruction
+WHERE type = 1;
+
+INSERT INTO instruction_call_module_parameter (instruction_call_module_id, sequence, name, arg_type, arg_type_value)
+SELECT i.id,
+    (a.index - 1)::SMALLINT,
+    a.argument->>'name',
+    arg_type_value(a.argument->>'arg_type'),
+    a.argument->>'arg_type_value'
+FROM instruction i,
+    jsonb_array_elements(i.payload->'CallMethod'->'arguments') WITH ORDINALITY AS a(argument, index)
+WHERE i.type = 1;
+
+INSERT INTO instruction_json_return (instruction_id, arg_type, arg_type_value)
+SELECT id,
+    arg_type_value(payload->'JsonReturn'->>'arg_type'),
+    payload->'JsonReturn'->>'arg_type_value'
+FROM instruction
+WHERE type = 2;
+
+DROP FUNCTION arg_type_value(TEXT);
+
+DELETE FROM instruction WHERE type IS NULL;
+ALTER TABLE instruction ALTER COLUMN type SET NOT NULL;
+ALTER TABLE instruction DROP COLUMN payload;
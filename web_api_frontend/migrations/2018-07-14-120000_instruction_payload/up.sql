@@ -0,0 +1,61 @@
+-- Instructions are stored as the JSON of `endpoint::Instruction`, instead of one table per kind
+ALTER TABLE instruction ADD COLUMN payload JSONB;
+
+CREATE FUNCTION arg_type_name(arg_type SMALLINT) RETURNS TEXT AS $$
+    SELECT CASE arg_type
+        WHEN 1 THEN 'Parameter'
+        WHEN 2 THEN 'String'
+        WHEN 3 THEN 'Number'
+        WHEN 4 THEN 'Float'
+    END;
+$$ LANGUAGE SQL IMMUTABLE;
+
+UPDATE instruction SET payload = jsonb_build_object('CallMethod', jsonb_build_object(
+    'id', instruction.id,
+    'config', m.config,
+    'method', m.method,
+    'out_variable_name', m.out_variable_name,
+    'arguments', COALESCE((
+        SELECT jsonb_agg(jsonb_build_object(
+            'name', p.name,
+            'arg_type', arg_type_name(p.arg_type),
+            'arg_type_value', p.arg_type_value
+        ) ORDER BY p.sequence)
+        FROM instruction_call_module_parameter p
+        WHERE p.instruction_call_module_id = m.instruction_id
+    ), '[]'::jsonb)
+))
+FROM instruction_call_module m
+WHERE m.instruction_id = instruction.id;
+
+UPDATE instruction SET payload = jsonb_build_object('JsonReturn', jsonb_build_object(
+    'id', instruction.id,
+    'arg_type', arg_type_name(r.arg_type),
+    'arg_type_value', r.arg_type_value
+))
+FROM instruction_json_return r
+WHERE r.instruction_id = instruction.id;
+
+DROP FUNCTION arg_type_name(SMALLINT);
+
+DELETE FROM instruction WHERE payload IS NULL;
+ALTER TABLE instruction ALTER COLUMN payload SET NOT NULL;
+ALTER TABLE instruction DROP COLUMN type;
+
+DROP TABLE instruction_call_module_parameter;
+DROP TABLE instruction_call_module;
+DROP TABLE instruction_json_return;
+
+-- Replaces the foreign key from instruction_call_module to config
+CREATE FUNCTION config_in_use() RETURNS trigger AS $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM instruction WHERE payload->'CallMethod'->>'config' = OLD.id::text) THEN
+        RAISE EXCEPTION 'Config % is used by an instruction', OLD.id
+            USING ERRCODE = 'foreign_key_violation';
+    END IF;
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER config_in_use BEFORE DELETE ON config
+    FOR EACH ROW EXECUTE PROCEDURE config_in_use();
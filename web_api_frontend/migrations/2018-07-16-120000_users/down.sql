@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE endpoint DROP COLUMN owner;
+DROP TABLE app_user;
@@ -0,0 +1,12 @@
+-- `user` is a reserved word in Postgres
+CREATE TABLE app_user (
+    id UUID PRIMARY KEY DEFAULT (uuid_generate_v4()),
+    name TEXT NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL,
+    role TEXT NOT NULL,
+
+    CONSTRAINT app_user_role CHECK (role IN ('Read', 'Write', 'Admin'))
+);
+
+-- Existing endpoints have no owner, and can only be changed by admins
+ALTER TABLE endpoint ADD COLUMN owner UUID REFERENCES app_user(id) ON DELETE SET NULL;
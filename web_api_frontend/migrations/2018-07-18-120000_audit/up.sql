@@ -0,0 +1,17 @@
+-- Entries are kept when their endpoint or config is deleted, so there are no foreign keys
+CREATE TABLE audit (
+    id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    actor TEXT NOT NULL,
+    action TEXT NOT NULL,
+    endpoint UUID,
+    config UUID,
+    before JSONB,
+    after JSONB,
+
+    CONSTRAINT audit_action CHECK (action IN ('create', 'update', 'delete')),
+    CONSTRAINT audit_subject CHECK ((endpoint IS NULL) <> (config IS NULL))
+);
+
+CREATE INDEX audit_endpoint ON audit (endpoint);
+CREATE INDEX audit_created_at ON audit (created_at);
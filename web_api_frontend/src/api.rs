@@ -0,0 +1,725 @@
+use actix_web::dev::Route;
+use actix_web::error::ErrorInternalServerError;
+use actix_web::{App, HttpMessage, HttpRequest, HttpResponse, Json, Responder};
+use audit::{AuditEntry, AuditFilter};
+use auth;
+use build;
+use build_runner::{Command, Runner};
+use bytes::Bytes;
+use endpoint::{
+    Config, ConfigForm, Endpoint, Endpoints, Instruction, Preview, SaveError, Trace,
+};
+use error::ApiError;
+use events::Event;
+use futures::{stream, Stream};
+use import_export::{self, ImportResult, ImportStatus};
+use project::{Format, Project};
+use rust_functional::Outcome;
+use revision::{self, Revision, RevisionSummary};
+use serde::de::DeserializeOwned;
+use serde_json::{self, Value};
+use service::Service;
+use std::collections::HashMap;
+use std::str::FromStr;
+use store::Store;
+use user::{hash_password, Role, User, UserForm};
+use uuid::Uuid;
+use validation::{self, BrokenEndpoint};
+use web_api_generator::{self, ArchiveFormat};
+use AppState;
+
+/// Register all `/api` routes
+pub fn register(app: App<AppState>) -> App<AppState> {
+    app.resource("/api/login", |r| with_json(r.post(), login))
+        .resource("/api/logout", |r| r.post().f(logout))
+        .resource("/api/me", |r| r.get().f(get_me))
+        .resource("/api/users", |r| {
+            r.get().f(get_users);
+            with_json(r.post(), post_user);
+        })
+        .resource("/api/users/{id}", |r| with_json(r.put(), put_user))
+        .resource("/api/endpoints", |r| {
+            r.get().f(get_endpoints);
+            with_json(r.post(), set_endpoints);
+        })
+        // Registered before `/api/endpoints/{id}`, which would match it as well
+        .resource("/api/endpoints/broken", |r| r.get().f(get_broken_endpoints))
+        .resource("/api/endpoints/{id}", |r| {
+            r.get().f(get_endpoint);
+            with_json(r.put(), put_endpoint);
+            r.delete().f(delete_endpoint);
+        })
+        .resource("/api/endpoints/{id}/build", |r| r.post().f(build_endpoint))
+        .resource("/api/endpoints/{id}/fix", |r| r.post().f(fix_endpoint))
+        .resource("/api/endpoints/{id}/owner", |r| with_json(r.put(), put_owner))
+        .resource("/api/endpoints/{id}/instructions", |r| {
+            with_json(r.post(), insert_instruction);
+            with_json(r.put(), reorder_instructions);
+        })
+        .resource("/api/endpoints/{id}/instructions/{instruction}", |r| {
+            r.delete().f(delete_instruction);
+        })
+        .resource("/api/endpoints/{id}/revisions", |r| r.get().f(get_revisions))
+        .resource("/api/endpoints/{id}/revisions/{version}", |r| r.get().f(get_revision))
+        .resource("/api/endpoints/{id}/revisions/{version}/diff/{other}", |r| {
+            r.get().f(diff_revisions);
+        })
+        .resource("/api/endpoints/{id}/revisions/{version}/restore", |r| {
+            r.post().f(restore_revision);
+        })
+        .resource("/api/endpoints/{id}/revisions/{version}/generate", |r| {
+            r.get().f(generate_revision);
+        })
+        .resource("/api/configs", |r| {
+            r.get().f(get_configs);
+            with_json(r.post(), post_config);
+        })
+        .resource("/api/configs/{id}", |r| {
+            r.get().f(get_config);
+            with_json(r.put(), put_config);
+            r.delete().f(delete_config);
+        })
+        .resource("/api/generate/{id}", |r| r.get().f(generate))
+        .resource("/api/preview", |r| with_json(r.post(), preview))
+        .resource("/api/run", |r| with_json(r.post(), run))
+        .resource("/api/trace", |r| with_json(r.post(), trace))
+        .resource("/api/project", |r| {
+            r.get().f(export_project);
+            r.post().with(import_project);
+        })
+        .resource("/api/services", |r| {
+            r.get().f(get_services);
+            with_json(r.post(), set_service);
+        })
+        .resource("/api/services/{id}/generate", |r| r.get().f(generate_service))
+        .resource("/api/audit", |r| r.get().f(get_audit))
+        .resource("/api/events", |r| r.get().f(get_events))
+}
+
+/// Register a handler that takes a JSON body, returning a body that cannot be parsed as an
+/// `ApiError` like every other error
+fn with_json<T, F, R>(route: &mut Route<AppState>, handler: F)
+where
+    T: DeserializeOwned + 'static,
+    F: Fn((HttpRequest<AppState>, Json<T>)) -> R + 'static,
+    R: Responder + 'static,
+{
+    route
+        .with(handler)
+        .1
+        .error_handler(|e, _| ApiError::BadRequest(e.to_string()).into());
+}
+
+fn store(req: &HttpRequest<AppState>) -> &dyn Store {
+    &*req.state().store
+}
+
+fn path_id(req: &HttpRequest<AppState>, name: &str) -> Result<Uuid, ApiError> {
+    let value = req
+        .match_info()
+        .get(name)
+        .ok_or_else(|| ApiError::BadRequest(format!("Missing {:?} in url", name)))?;
+    Ok(Uuid::parse_str(value)?)
+}
+
+fn path_version(req: &HttpRequest<AppState>, name: &str) -> Result<i32, ApiError> {
+    req.match_info()
+        .get(name)
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| ApiError::BadRequest(format!("Invalid {:?} in url", name)))
+}
+
+/// Parse the query parameter `name`, or `None` if it is not given
+fn query_value<T: FromStr>(req: &HttpRequest<AppState>, name: &str) -> Result<Option<T>, ApiError> {
+    match req.query().get(name) {
+        Some(value) => value
+            .parse()
+            .map(Some)
+            .map_err(|_| ApiError::BadRequest(format!("Invalid {:?} in query", name))),
+        None => Ok(None),
+    }
+}
+
+/// Tell the clients listening on `/api/events` about a change
+fn publish(req: &HttpRequest<AppState>, event: Event) {
+    req.state().events.publish(event);
+}
+
+fn load_endpoint(store: &dyn Store, id: Uuid) -> Result<Endpoint, ApiError> {
+    store
+        .endpoint(id)?
+        .ok_or_else(|| ApiError::NotFound(format!("Endpoint {} not found", id)))
+}
+
+fn load_revision(store: &dyn Store, id: Uuid, version: i32) -> Result<Revision, ApiError> {
+    store.revision(id, version)?.ok_or_else(|| {
+        ApiError::NotFound(format!("Revision {} of endpoint {} not found", version, id))
+    })
+}
+
+fn format_files(output: HashMap<String, String>) -> String {
+    let mut result = String::new();
+    for (key, value) in output {
+        result += &format!("== {}\n{}\n\n", key, value);
+    }
+    result
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Login {
+    pub name: String,
+    pub password: String,
+}
+
+/// Check the password and start a session, returning the user
+fn login((req, form): (HttpRequest<AppState>, Json<Login>)) -> Result<HttpResponse, ApiError> {
+    let user = store(&req)
+        .user_by_name(&form.name)?
+        .filter(|u| u.check_password(&form.password))
+        .ok_or_else(|| ApiError::Unauthorized("Unknown name or wrong password".to_string()))?;
+    Ok(auth::login(&req.state().key, &user))
+}
+
+fn logout(_req: HttpRequest<AppState>) -> HttpResponse {
+    auth::logout()
+}
+
+fn get_me(req: HttpRequest<AppState>) -> Result<Json<User>, ApiError> {
+    Ok(Json(auth::user(&req)?))
+}
+
+fn get_users(req: HttpRequest<AppState>) -> Result<Json<Vec<User>>, ApiError> {
+    auth::require(&req, Role::Admin)?;
+    Ok(Json(store(&req).users()?))
+}
+
+fn post_user((req, form): (HttpRequest<AppState>, Json<UserForm>)) -> Result<Json<User>, ApiError> {
+    auth::require(&req, Role::Admin)?;
+    let UserForm {
+        name,
+        role,
+        password,
+    } = form.into_inner();
+    let password = password
+        .ok_or_else(|| ApiError::BadRequest("A new user needs a password".to_string()))?;
+    let user = User::new(name, &password, role);
+    store(&req).save_user(&user)?;
+    Ok(Json(user))
+}
+
+fn put_user((req, form): (HttpRequest<AppState>, Json<UserForm>)) -> Result<Json<User>, ApiError> {
+    auth::require(&req, Role::Admin)?;
+    let store = store(&req);
+    let id = path_id(&req, "id")?;
+    let mut user = store
+        .user(id)?
+        .ok_or_else(|| ApiError::NotFound(format!("User {} not found", id)))?;
+    let UserForm {
+        name,
+        role,
+        password,
+    } = form.into_inner();
+    user.name = name;
+    user.role = role;
+    if let Some(password) = password {
+        user.password_hash = hash_password(&password);
+    }
+    store.save_user(&user)?;
+    Ok(Json(user))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Owner {
+    /// The new owner, or `None` to only let admins change the endpoint
+    pub owner: Option<Uuid>,
+}
+
+/// Give an endpoint to another user
+fn put_owner(
+    (req, form): (HttpRequest<AppState>, Json<Owner>),
+) -> Result<Json<Endpoint>, ApiError> {
+    let user = auth::require(&req, Role::Admin)?;
+    let store = store(&req);
+    let id = path_id(&req, "id")?;
+    if !store.set_endpoint_owner(id, form.owner, &user)? {
+        return Err(ApiError::NotFound(format!("Endpoint {} not found", id)));
+    }
+    let endpoint = load_endpoint(store, id)?;
+    publish(&req, Event::endpoint_saved(&endpoint, &user));
+    Ok(Json(endpoint))
+}
+
+fn get_endpoints(req: HttpRequest<AppState>) -> Result<Json<Endpoints>, ApiError> {
+    auth::user(&req)?;
+    let endpoints = Endpoints::load(store(&req))?;
+    Ok(Json(endpoints))
+}
+
+fn set_endpoints(
+    (req, form): (HttpRequest<AppState>, Json<Endpoint>),
+) -> Result<Json<Endpoint>, ApiError> {
+    let user = auth::user(&req)?;
+    let mut endpoint = form.into_inner();
+    endpoint.insert_or_update(store(&req), &user)?;
+    publish(&req, Event::endpoint_saved(&endpoint, &user));
+    Ok(Json(endpoint))
+}
+
+fn get_endpoint(req: HttpRequest<AppState>) -> Result<Json<Endpoint>, ApiError> {
+    auth::user(&req)?;
+    let id = path_id(&req, "id")?;
+    Ok(Json(load_endpoint(store(&req), id)?))
+}
+
+fn put_endpoint(
+    (req, form): (HttpRequest<AppState>, Json<Endpoint>),
+) -> Result<Json<Endpoint>, ApiError> {
+    let user = auth::user(&req)?;
+    let mut endpoint = form.into_inner();
+    endpoint.id = path_id(&req, "id")?;
+    endpoint.insert_or_update(store(&req), &user)?;
+    publish(&req, Event::endpoint_saved(&endpoint, &user));
+    Ok(Json(endpoint))
+}
+
+fn delete_endpoint(req: HttpRequest<AppState>) -> Result<HttpResponse, ApiError> {
+    let user = auth::user(&req)?;
+    let id = path_id(&req, "id")?;
+    if !user.can_change(load_endpoint(store(&req), id)?.owner) {
+        return Err(SaveError::Forbidden(id).into());
+    }
+    if !store(&req).delete_endpoint(id, &user)? {
+        return Err(ApiError::NotFound(format!("Endpoint {} not found", id)));
+    }
+    publish(
+        &req,
+        Event::EndpointDeleted {
+            id,
+            actor: user.name,
+        },
+    );
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Check every stored endpoint against the current module configs
+fn get_broken_endpoints(
+    req: HttpRequest<AppState>,
+) -> Result<Json<Vec<BrokenEndpoint>>, ApiError> {
+    auth::user(&req)?;
+    let store = store(&req);
+    let endpoints = store.endpoints()?;
+    let config_ids = endpoints
+        .iter()
+        .flat_map(|e| e.config_ids())
+        .collect::<Vec<_>>();
+    let configs = store.module_configs(&config_ids)?;
+    Ok(Json(
+        endpoints
+            .iter()
+            .filter_map(|e| validation::check(e, &configs))
+            .collect(),
+    ))
+}
+
+#[derive(Debug, Serialize)]
+pub struct FixResult {
+    /// The number of method and argument names that were renamed
+    pub fixed: usize,
+    pub endpoint: Endpoint,
+    /// The problems that could not be fixed automatically
+    pub remaining: Option<BrokenEndpoint>,
+}
+
+/// Apply the renames declared in the module configs to an endpoint, and save it if anything changed
+fn fix_endpoint(req: HttpRequest<AppState>) -> Result<Json<FixResult>, ApiError> {
+    let user = auth::user(&req)?;
+    let store = store(&req);
+    let mut endpoint = load_endpoint(store, path_id(&req, "id")?)?;
+    let configs = store.module_configs(&endpoint.config_ids())?;
+    let fixed = validation::fix(&mut endpoint, &configs);
+    if fixed > 0 {
+        endpoint.insert_or_update(store, &user)?;
+        publish(&req, Event::endpoint_saved(&endpoint, &user));
+    }
+    Ok(Json(FixResult {
+        fixed,
+        remaining: validation::check(&endpoint, &configs),
+        endpoint,
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct InsertInstruction {
+    /// The index to insert the instruction at. Appends the instruction if omitted.
+    pub index: Option<usize>,
+    pub instruction: Instruction,
+}
+
+fn insert_instruction(
+    (req, form): (HttpRequest<AppState>, Json<InsertInstruction>),
+) -> Result<Json<Endpoint>, ApiError> {
+    let user = auth::user(&req)?;
+    let store = store(&req);
+    let mut endpoint = load_endpoint(store, path_id(&req, "id")?)?;
+    let InsertInstruction { index, instruction } = form.into_inner();
+    let index = index.unwrap_or(endpoint.instructions.len());
+    endpoint.insert_instruction(index, instruction);
+    endpoint.insert_or_update(store, &user)?;
+    publish(&req, Event::endpoint_saved(&endpoint, &user));
+    Ok(Json(endpoint))
+}
+
+fn reorder_instructions(
+    (req, form): (HttpRequest<AppState>, Json<Vec<Uuid>>),
+) -> Result<Json<Endpoint>, ApiError> {
+    let user = auth::user(&req)?;
+    let store = store(&req);
+    let mut endpoint = load_endpoint(store, path_id(&req, "id")?)?;
+    if !endpoint.reorder_instructions(&form) {
+        return Err(ApiError::BadRequest(
+            "The new order must contain every instruction id exactly once".to_string(),
+        ));
+    }
+    endpoint.insert_or_update(store, &user)?;
+    publish(&req, Event::endpoint_saved(&endpoint, &user));
+    Ok(Json(endpoint))
+}
+
+fn delete_instruction(req: HttpRequest<AppState>) -> Result<Json<Endpoint>, ApiError> {
+    let user = auth::user(&req)?;
+    let store = store(&req);
+    let mut endpoint = load_endpoint(store, path_id(&req, "id")?)?;
+    let instruction = path_id(&req, "instruction")?;
+    if !endpoint.remove_instruction(instruction) {
+        return Err(ApiError::NotFound(format!(
+            "Instruction {} not found",
+            instruction
+        )));
+    }
+    endpoint.insert_or_update(store, &user)?;
+    publish(&req, Event::endpoint_saved(&endpoint, &user));
+    Ok(Json(endpoint))
+}
+
+fn get_revisions(req: HttpRequest<AppState>) -> Result<Json<Vec<RevisionSummary>>, ApiError> {
+    auth::user(&req)?;
+    let store = store(&req);
+    let id = path_id(&req, "id")?;
+    load_endpoint(store, id)?;
+    Ok(Json(store.revisions(id)?))
+}
+
+fn get_revision(req: HttpRequest<AppState>) -> Result<Json<Revision>, ApiError> {
+    auth::user(&req)?;
+    let revision = load_revision(
+        store(&req),
+        path_id(&req, "id")?,
+        path_version(&req, "version")?,
+    )?;
+    Ok(Json(revision))
+}
+
+fn diff_revisions(req: HttpRequest<AppState>) -> Result<HttpResponse, ApiError> {
+    auth::user(&req)?;
+    let store = store(&req);
+    let id = path_id(&req, "id")?;
+    let from = load_revision(store, id, path_version(&req, "version")?)?;
+    let to = load_revision(store, id, path_version(&req, "other")?)?;
+    Ok(HttpResponse::Ok().json(revision::diff(&from, &to)))
+}
+
+fn restore_revision(req: HttpRequest<AppState>) -> Result<Json<Endpoint>, ApiError> {
+    let user = auth::user(&req)?;
+    let store = store(&req);
+    let id = path_id(&req, "id")?;
+    let current = load_endpoint(store, id)?;
+    let revision = load_revision(store, id, path_version(&req, "version")?)?;
+    let mut endpoint = revision.restore(current.version);
+    endpoint.insert_or_update(store, &user)?;
+    publish(&req, Event::endpoint_saved(&endpoint, &user));
+    Ok(Json(endpoint))
+}
+
+fn generate_revision(req: HttpRequest<AppState>) -> Result<String, ApiError> {
+    auth::user(&req)?;
+    let store = store(&req);
+    let revision = load_revision(
+        store,
+        path_id(&req, "id")?,
+        path_version(&req, "version")?,
+    )?;
+    let output = revision.snapshot.generate(store)?;
+    Ok(format_files(output))
+}
+
+fn get_configs(req: HttpRequest<AppState>) -> Result<Json<Vec<Config>>, ApiError> {
+    auth::user(&req)?;
+    let configs = store(&req).configs()?;
+    Ok(Json(configs))
+}
+
+fn get_config(req: HttpRequest<AppState>) -> Result<Json<Config>, ApiError> {
+    auth::user(&req)?;
+    let id = path_id(&req, "id")?;
+    store(&req)
+        .config(id)?
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("Config {} not found", id)))
+}
+
+fn post_config(
+    (req, form): (HttpRequest<AppState>, Json<ConfigForm>),
+) -> Result<Json<Config>, ApiError> {
+    let user = auth::require(&req, Role::Write)?;
+    let config = Config::insert_or_update(store(&req), None, form.into_inner(), &user)?;
+    publish(&req, Event::config_saved(&config, &user));
+    Ok(Json(config))
+}
+
+fn put_config(
+    (req, form): (HttpRequest<AppState>, Json<ConfigForm>),
+) -> Result<Json<Config>, ApiError> {
+    let user = auth::require(&req, Role::Write)?;
+    let id = path_id(&req, "id")?;
+    let config = Config::insert_or_update(store(&req), Some(id), form.into_inner(), &user)?;
+    publish(&req, Event::config_saved(&config, &user));
+    Ok(Json(config))
+}
+
+fn delete_config(req: HttpRequest<AppState>) -> Result<HttpResponse, ApiError> {
+    let user = auth::require(&req, Role::Write)?;
+    let id = path_id(&req, "id")?;
+    if !store(&req).delete_config(id, &user)? {
+        return Err(ApiError::NotFound(format!("Config {} not found", id)));
+    }
+    publish(
+        &req,
+        Event::ConfigDeleted {
+            id,
+            actor: user.name,
+        },
+    );
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Returns the generated files as text, or as an archive if `?format=tar.gz` or `?format=zip` is given
+fn generate(req: HttpRequest<AppState>) -> Result<HttpResponse, ApiError> {
+    auth::user(&req)?;
+    let store = store(&req);
+    let endpoint = load_endpoint(store, path_id(&req, "id")?)?;
+    let format = match req.query().get("format") {
+        Some(format) => ArchiveFormat::from_extension(format).ok_or_else(|| {
+            ApiError::BadRequest(format!("Unknown archive format {:?}", format))
+        })?,
+        None => return Ok(HttpResponse::Ok().body(format_files(endpoint.generate(store)?))),
+    };
+    let archive = endpoint.generate_archive(store, format)?;
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .header(
+            "Content-Disposition",
+            format!(
+                "attachment; filename=\"{}.{}\"",
+                endpoint.archive_name(),
+                format.extension()
+            ),
+        )
+        .body(archive))
+}
+
+/// Generate an endpoint sent in the body without saving it
+fn preview(
+    (req, form): (HttpRequest<AppState>, Json<Endpoint>),
+) -> Result<Json<Preview>, ApiError> {
+    auth::user(&req)?;
+    Ok(Json(form.preview(store(&req))?))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RunEndpoint {
+    pub endpoint: Endpoint,
+    /// The values of the `{name}` segments of the endpoint url
+    #[serde(default)]
+    pub inputs: HashMap<String, Value>,
+}
+
+/// Run an endpoint sent in the body with the interpreter, without saving or building it
+fn run((req, form): (HttpRequest<AppState>, Json<RunEndpoint>)) -> Result<Json<Outcome>, ApiError> {
+    auth::user(&req)?;
+    let RunEndpoint { endpoint, inputs } = form.into_inner();
+    let outcome = endpoint.run(store(&req), &req.state().interpreter, inputs)?;
+    Ok(Json(outcome))
+}
+
+/// Run an endpoint like `run`, and return the module calls it made
+fn trace((req, form): (HttpRequest<AppState>, Json<RunEndpoint>)) -> Result<Json<Trace>, ApiError> {
+    auth::user(&req)?;
+    let RunEndpoint { endpoint, inputs } = form.into_inner();
+    let trace = endpoint.trace(store(&req), &req.state().interpreter, inputs)?;
+    Ok(Json(trace))
+}
+
+/// The project format given with `?format=json` or `?format=yaml`, or by a yaml `Content-Type`
+fn project_format(req: &HttpRequest<AppState>) -> Result<Format, ApiError> {
+    if let Some(format) = req.query().get("format") {
+        return Format::from_extension(format)
+            .ok_or_else(|| ApiError::BadRequest(format!("Unknown project format {:?}", format)));
+    }
+    let yaml = req
+        .headers()
+        .get("Content-Type")
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|h| h.contains("yaml"));
+    Ok(if yaml { Format::Yaml } else { Format::Json })
+}
+
+/// Download a project file with the endpoints given as `?endpoints=<id>,<id>`, or all endpoints
+fn export_project(req: HttpRequest<AppState>) -> Result<HttpResponse, ApiError> {
+    auth::user(&req)?;
+    let format = project_format(&req)?;
+    let ids = match req.query().get("endpoints") {
+        Some(ids) => Some(
+            ids.split(',')
+                .map(Uuid::parse_str)
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        None => None,
+    };
+    let project = import_export::export(store(&req), ids.as_ref().map(|ids| &ids[..]))?;
+    let body = project
+        .write(format)
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .header(
+            "Content-Disposition",
+            format!("attachment; filename=\"project.{}\"", format.extension()),
+        )
+        .body(body))
+}
+
+fn import_project(
+    (req, body): (HttpRequest<AppState>, Bytes),
+) -> Result<Json<ImportResult>, ApiError> {
+    let user = auth::require(&req, Role::Write)?;
+    let project = Project::read(project_format(&req)?, &body)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    let result = import_export::import(store(&req), project, &user)?;
+    for name in &result.created_configs {
+        if let Some(config) = store(&req).config_by_name(name)? {
+            publish(&req, Event::config_saved(&config, &user));
+        }
+    }
+    for endpoint in &result.endpoints {
+        if endpoint.status != ImportStatus::Unchanged {
+            publish(
+                &req,
+                Event::EndpointSaved {
+                    id: endpoint.id,
+                    name: endpoint.name.clone(),
+                    version: endpoint.version,
+                    actor: user.name.clone(),
+                },
+            );
+        }
+    }
+    Ok(Json(result))
+}
+
+/// Compile the generated crate with `?command=check` (the default) or `?command=build`.
+/// Diagnostics are streamed as newline-delimited `build::BuildEvent`s.
+fn build_endpoint(req: HttpRequest<AppState>) -> Result<HttpResponse, ApiError> {
+    auth::require(&req, Role::Write)?;
+    let store = store(&req);
+    let endpoint = load_endpoint(store, path_id(&req, "id")?)?;
+    let command = match req.query().get("command") {
+        None | Some("check") => Command::Check,
+        Some("build") => Command::Build,
+        Some(command) => {
+            return Err(ApiError::BadRequest(format!(
+                "Unknown command {:?}, expected \"check\" or \"build\"",
+                command
+            )))
+        }
+    };
+    let (files, source_map) = endpoint.generate_with_source_map(store)?;
+    let endpoints = vec![(endpoint.name.clone(), endpoint.id)].into_iter().collect();
+    let output_dir = req.state().output_dir.as_deref();
+    let workspace = build::workspace(output_dir, &endpoint.id.to_string(), &files)
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+    let events = build::start(Runner::new(command), workspace, source_map, endpoints);
+    let body = events
+        .map(|event| {
+            let mut line = serde_json::to_vec(&event).unwrap();
+            line.push(b'\n');
+            Bytes::from(line)
+        })
+        .map_err(|()| ErrorInternalServerError("The build stopped unexpectedly"));
+    Ok(HttpResponse::Ok()
+        .content_type("application/x-ndjson")
+        .streaming(body))
+}
+
+fn get_services(req: HttpRequest<AppState>) -> Result<Json<Vec<Service>>, ApiError> {
+    auth::user(&req)?;
+    let services = store(&req).services()?;
+    Ok(Json(services))
+}
+
+fn set_service(
+    (req, form): (HttpRequest<AppState>, Json<Service>),
+) -> Result<Json<Service>, ApiError> {
+    auth::require(&req, Role::Write)?;
+    let service = form.into_inner();
+    if web_api_generator::backend_by_name(&service.backend).is_none() {
+        return Err(ApiError::BadRequest(format!(
+            "Unknown backend {:?}, expected one of {:?}",
+            service.backend,
+            web_api_generator::backend_names()
+        )));
+    }
+    store(&req).save_service(&service)?;
+    Ok(Json(service))
+}
+
+fn generate_service(req: HttpRequest<AppState>) -> Result<String, ApiError> {
+    auth::user(&req)?;
+    let store = store(&req);
+    let id = path_id(&req, "id")?;
+    let service = store
+        .service(id)?
+        .ok_or_else(|| ApiError::NotFound(format!("Service {} not found", id)))?;
+    let output = service.generate(store)?;
+    Ok(format_files(output))
+}
+
+/// List the changes to endpoints and configs, newest first. They can be filtered with
+/// `?endpoint=<id>`, `?config=<id>`, `?from=<time>` and `?to=<time>`, with UTC times like
+/// `2018-07-18T12:00:00`.
+fn get_audit(req: HttpRequest<AppState>) -> Result<Json<Vec<AuditEntry>>, ApiError> {
+    auth::user(&req)?;
+    let filter = AuditFilter {
+        endpoint: query_value(&req, "endpoint")?,
+        config: query_value(&req, "config")?,
+        from: query_value(&req, "from")?,
+        to: query_value(&req, "to")?,
+    };
+    Ok(Json(store(&req).audit_log(&filter)?))
+}
+
+/// Stream the changes made from now on as server-sent events, see `events::Event`
+fn get_events(req: HttpRequest<AppState>) -> Result<HttpResponse, ApiError> {
+    auth::user(&req)?;
+    // A comment, so that the response starts before the first change
+    let connected = stream::once(Ok(Bytes::from_static(b": connected\n\n")));
+    let events = req
+        .state()
+        .events
+        .subscribe()
+        .map(|event| event.to_message())
+        .map_err(|()| ErrorInternalServerError("The event stream stopped unexpectedly"));
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .header("Cache-Control", "no-cache")
+        .streaming(connected.chain(events)))
+}
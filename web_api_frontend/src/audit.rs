@@ -0,0 +1,263 @@
+//! A log of who changed which endpoint or config, written by the stores together with the change
+
+use chrono::NaiveDateTime;
+use endpoint::Endpoint;
+use models::Config as DbConfig;
+use serde_json::{self, Value};
+use std::fmt;
+use std::str::FromStr;
+use user::User;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Create,
+    Update,
+    Delete,
+}
+
+impl Action {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Create => "create",
+            Action::Update => "update",
+            Action::Delete => "delete",
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Action, String> {
+        match s {
+            "create" => Ok(Action::Create),
+            "update" => Ok(Action::Update),
+            "delete" => Ok(Action::Delete),
+            _ => Err(format!("Unknown audit action {:?}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(self.as_str())
+    }
+}
+
+/// A change to record. Changes to instructions are recorded as updates of their endpoint.
+#[derive(Debug, Clone)]
+pub struct Change {
+    /// The name of the user who made the change
+    pub actor: String,
+    pub action: Action,
+    /// The changed endpoint, `None` if a config changed
+    pub endpoint: Option<Uuid>,
+    /// The changed config, `None` if an endpoint changed
+    pub config: Option<Uuid>,
+    /// `None` if the endpoint or config was created
+    pub before: Option<Value>,
+    /// `None` if the endpoint or config was deleted
+    pub after: Option<Value>,
+}
+
+/// What is recorded of a config, which does not include its module.json
+#[derive(Serialize)]
+struct ConfigSnapshot<'a> {
+    id: Uuid,
+    name: &'a str,
+    path: &'a str,
+}
+
+fn action(before: bool, after: bool) -> Action {
+    match (before, after) {
+        (false, _) => Action::Create,
+        (true, true) => Action::Update,
+        (true, false) => Action::Delete,
+    }
+}
+
+impl Change {
+    /// A change of an endpoint, given as it was before and after the change
+    pub fn endpoint(actor: &User, before: Option<&Endpoint>, after: Option<&Endpoint>) -> Change {
+        let id = before.or(after).expect("A change needs an endpoint").id;
+        let json = |e: &Endpoint| serde_json::to_value(e).expect("Endpoint is always serializable");
+        Change {
+            actor: actor.name.clone(),
+            action: action(before.is_some(), after.is_some()),
+            endpoint: Some(id),
+            config: None,
+            before: before.map(json),
+            after: after.map(json),
+        }
+    }
+
+    /// A change of a config, given as it was before and after the change
+    pub fn config(actor: &User, before: Option<&DbConfig>, after: Option<&DbConfig>) -> Change {
+        let id = before.or(after).expect("A change needs a config").id;
+        let json = |c: &DbConfig| {
+            serde_json::to_value(ConfigSnapshot {
+                id: c.id,
+                name: &c.name,
+                path: &c.path,
+            }).expect("Config is always serializable")
+        };
+        Change {
+            actor: actor.name.clone(),
+            action: action(before.is_some(), after.is_some()),
+            endpoint: None,
+            config: Some(id),
+            before: before.map(json),
+            after: after.map(json),
+        }
+    }
+}
+
+/// A recorded change
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    /// Increases with every entry
+    pub id: i64,
+    pub created_at: NaiveDateTime,
+    pub actor: String,
+    pub action: Action,
+    pub endpoint: Option<Uuid>,
+    pub config: Option<Uuid>,
+    /// The serialized `endpoint::Endpoint`, or the id, name and path of the config
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl AuditEntry {
+    pub fn new(id: i64, created_at: NaiveDateTime, change: Change) -> AuditEntry {
+        AuditEntry {
+            id,
+            created_at,
+            actor: change.actor,
+            action: change.action,
+            endpoint: change.endpoint,
+            config: change.config,
+            before: change.before,
+            after: change.after,
+        }
+    }
+}
+
+/// Which entries to list. Every field that is set must match.
+#[derive(Debug, Default)]
+pub struct AuditFilter {
+    pub endpoint: Option<Uuid>,
+    pub config: Option<Uuid>,
+    /// Only entries created at or after this time
+    pub from: Option<NaiveDateTime>,
+    /// Only entries created before this time
+    pub to: Option<NaiveDateTime>,
+}
+
+impl AuditFilter {
+    pub fn matches(&self, entry: &AuditEntry) -> bool {
+        self.endpoint.is_none_or(|id| entry.endpoint == Some(id))
+            && self.config.is_none_or(|id| entry.config == Some(id))
+            && self.from.is_none_or(|from| entry.created_at >= from)
+            && self.to.is_none_or(|to| entry.created_at < to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use user::Role;
+
+    fn alice() -> User {
+        User {
+            id: Uuid::new_v4(),
+            name: "alice".to_string(),
+            role: Role::Write,
+            password_hash: String::new(),
+        }
+    }
+
+    fn config(path: &str) -> DbConfig {
+        DbConfig {
+            id: Uuid::nil(),
+            name: "adder".to_string(),
+            path: path.to_string(),
+        }
+    }
+
+    fn at(hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2018, 7, 20)
+            .and_then(|date| date.and_hms_opt(hour, 0, 0))
+            .unwrap()
+    }
+
+    #[test]
+    fn actions_follow_the_snapshots() {
+        let (old, new) = (config("../modules/adder"), config("modules/adder"));
+        let create = Change::config(&alice(), None, Some(&new));
+        assert_eq!(Action::Create, create.action);
+        assert_eq!(Some(Uuid::nil()), create.config);
+        assert_eq!(None, create.endpoint);
+        assert_eq!(None, create.before);
+        let update = Change::config(&alice(), Some(&old), Some(&new));
+        assert_eq!(Action::Update, update.action);
+        assert_eq!("alice", update.actor);
+        assert_eq!(Some("../modules/adder"), update.before.unwrap()["path"].as_str());
+        assert_eq!(Some("modules/adder"), update.after.unwrap()["path"].as_str());
+        let delete = Change::config(&alice(), Some(&old), None);
+        assert_eq!(Action::Delete, delete.action);
+        assert_eq!(None, delete.after);
+
+        for action in &[Action::Create, Action::Update, Action::Delete] {
+            assert_eq!(Ok(*action), action.to_string().parse());
+        }
+        assert!("Create".parse::<Action>().is_err());
+    }
+
+    #[test]
+    fn filters_match_every_field_that_is_set() {
+        let endpoint = Uuid::new_v4();
+        let entry = AuditEntry {
+            id: 1,
+            created_at: at(12),
+            actor: "alice".to_string(),
+            action: Action::Update,
+            endpoint: Some(endpoint),
+            config: None,
+            before: None,
+            after: None,
+        };
+        assert!(AuditFilter::default().matches(&entry));
+        let filter = AuditFilter {
+            endpoint: Some(endpoint),
+            from: Some(at(12)),
+            to: Some(at(13)),
+            ..AuditFilter::default()
+        };
+        assert!(filter.matches(&entry));
+        let filters = [
+            AuditFilter {
+                endpoint: Some(Uuid::new_v4()),
+                ..AuditFilter::default()
+            },
+            AuditFilter {
+                config: Some(Uuid::nil()),
+                ..AuditFilter::default()
+            },
+            AuditFilter {
+                from: Some(at(13)),
+                ..AuditFilter::default()
+            },
+            // `to` is exclusive
+            AuditFilter {
+                to: Some(at(12)),
+                ..AuditFilter::default()
+            },
+        ];
+        for filter in &filters {
+            assert!(!filter.matches(&entry), "{:?}", filter);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! Sessions, kept in a private cookie that holds the id of the logged in user
+
+use actix_web::dev::HttpResponseBuilder;
+use actix_web::{HttpRequest, HttpResponse};
+use cookie::{Cookie, CookieJar, Key, SameSite};
+use error::ApiError;
+use time::Duration;
+use user::{Role, User};
+use uuid::Uuid;
+use AppState;
+
+const SESSION_COOKIE: &str = "session";
+/// How long the browser keeps a session after logging in
+const SESSION_DAYS: i64 = 7;
+
+/// The logged in user, or `ApiError::Unauthorized` if the request has no valid session
+pub fn user(req: &HttpRequest<AppState>) -> Result<User, ApiError> {
+    let mut jar = CookieJar::new();
+    if let Some(cookie) = req.cookie(SESSION_COOKIE) {
+        jar.add_original(cookie.clone().into_owned());
+    }
+    let id = jar
+        .private(&req.state().key)
+        .get(SESSION_COOKIE)
+        .and_then(|cookie| Uuid::parse_str(cookie.value()).ok())
+        .ok_or_else(|| ApiError::Unauthorized("Not logged in".to_string()))?;
+    req.state()
+        .store
+        .user(id)?
+        .ok_or_else(|| ApiError::Unauthorized("The user of this session was removed".to_string()))
+}
+
+/// The logged in user, or `ApiError::Forbidden` if their role is below `role`
+pub fn require(req: &HttpRequest<AppState>, role: Role) -> Result<User, ApiError> {
+    let user = user(req)?;
+    if user.role < role {
+        return Err(ApiError::Forbidden(format!(
+            "{} has the {} role, this needs the {} role",
+            user.name, user.role, role
+        )));
+    }
+    Ok(user)
+}
+
+/// A response with the user, that starts a session for them
+pub fn login(key: &Key, user: &User) -> HttpResponse {
+    let mut jar = CookieJar::new();
+    jar.private(key).add(session(user.id.to_string()));
+    with_changes(&jar).json(user)
+}
+
+/// An empty response that ends the session
+pub fn logout() -> HttpResponse {
+    let mut jar = CookieJar::new();
+    jar.add_original(Cookie::named(SESSION_COOKIE));
+    // Removing sets an empty value that expires immediately
+    jar.remove(session(String::new()));
+    with_changes(&jar).finish()
+}
+
+fn session(value: String) -> Cookie<'static> {
+    Cookie::build(SESSION_COOKIE, value)
+        .path("/")
+        .max_age(Duration::days(SESSION_DAYS))
+        .http_only(true)
+        .same_site(SameSite::Strict)
+        .finish()
+}
+
+/// A response that sets the cookies changed in the jar
+fn with_changes(jar: &CookieJar) -> HttpResponseBuilder {
+    let mut response = HttpResponse::Ok();
+    for cookie in jar.delta() {
+        response.cookie(cookie.clone());
+    }
+    response
+}
@@ -0,0 +1,94 @@
+use build_runner::{BuildResult, Diagnostic, Runner, Workspace};
+use futures::sync::mpsc::{self, UnboundedReceiver};
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::thread;
+use uuid::Uuid;
+use web_api_generator::SourceMap;
+
+/// A message streamed to the client while a generated crate is being compiled
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BuildEvent {
+    Diagnostic {
+        diagnostic: Diagnostic,
+        /// The endpoint, instruction index and argument name the primary span of the diagnostic was generated from
+        endpoint: Option<Uuid>,
+        instruction: Option<usize>,
+        argument: Option<String>,
+    },
+    Finished(BuildResult),
+    Failed {
+        message: String,
+    },
+}
+
+/// Write the files of a generated crate to `<output_dir>/<name>`, where they are kept after the
+/// build, or to a new temporary directory if there is no output dir
+pub fn workspace(
+    output_dir: Option<&Path>,
+    name: &str,
+    files: &HashMap<String, String>,
+) -> io::Result<Workspace> {
+    match output_dir {
+        Some(dir) => Workspace::create_in(dir.join(name), files),
+        None => Workspace::new(files),
+    }
+}
+
+/// Compile the workspace on a background thread.
+/// `endpoints` maps the names of the generated endpoints to their ids.
+///
+/// Unless the runner has a `target_dir`, every build compiles into the `target` directory of its
+/// own workspace. Every generated crate is named `test`, so builds that share a target directory
+/// would wait for each other on cargo's lock and overwrite each other's artifacts.
+pub fn start(
+    runner: Runner,
+    workspace: Workspace,
+    source_map: SourceMap,
+    endpoints: HashMap<String, Uuid>,
+) -> UnboundedReceiver<BuildEvent> {
+    let (sender, receiver) = mpsc::unbounded();
+    thread::spawn(move || {
+        let result = runner.run(&workspace, |diagnostic| {
+            let (endpoint, instruction, argument) = locate(&source_map, &endpoints, &diagnostic);
+            let _ = sender.unbounded_send(BuildEvent::Diagnostic {
+                diagnostic,
+                endpoint,
+                instruction,
+                argument,
+            });
+        });
+        let _ = sender.unbounded_send(match result {
+            Ok(result) => BuildEvent::Finished(result),
+            Err(e) => BuildEvent::Failed {
+                message: e.to_string(),
+            },
+        });
+    });
+    receiver
+}
+
+/// Find the endpoint, instruction and argument that generated the primary span of a diagnostic
+fn locate(
+    source_map: &SourceMap,
+    endpoints: &HashMap<String, Uuid>,
+    diagnostic: &Diagnostic,
+) -> (Option<Uuid>, Option<usize>, Option<String>) {
+    let span = match diagnostic.primary_span() {
+        Some(span) => span,
+        None => return (None, None, None),
+    };
+    let entry = match source_map.lookup(&span.file_name, span.line_start) {
+        Some(entry) => entry,
+        None => return (None, None, None),
+    };
+    (
+        endpoints.get(&entry.endpoint).cloned(),
+        Some(entry.instruction),
+        entry
+            .argument_at(span.line_start, span.column_start)
+            .map(|a| a.name.clone()),
+    )
+}
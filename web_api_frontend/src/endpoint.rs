@@ -1,18 +1,22 @@
-use diesel::pg::PgConnection;
-use diesel::prelude::*;
-use diesel::QueryResult;
-use enum_primitive::FromPrimitive;
+use chrono::NaiveDateTime;
+use diesel::result::Error as DieselError;
+use failure::Error;
 use itertools::Itertools;
-use models::{
-    CallModule as DBCallModule, CallModuleParameter as DBCallModuleParameter, Config as DbConfig,
-    Endpoint as DBEndpoint, Instruction as DBInstruction, JsonReturn as DBJsonReturn,
-};
-use rust_functional::Config as BaseConfig;
-use schema;
+use models::{Config as DbConfig, Endpoint as DBEndpoint};
+use rust_functional::{Config as BaseConfig, Interpreter, Outcome, TraceEntry};
+use serde_json::Value;
 use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fmt;
+use std::io;
+use std::rc::Rc;
+use store::{Store, StoreError, StoreResult};
+use user::User;
 use uuid::Uuid;
+use validation;
 use web_api_generator;
-use std::rc::Rc;
+
+pub use project::{ArgType, CallMethodArgument, InvalidArgument};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Endpoints {
@@ -21,13 +25,9 @@ pub struct Endpoints {
 }
 
 impl Endpoints {
-    pub fn load(conn: &PgConnection) -> QueryResult<Endpoints> {
-        let configs = schema::config::table
-            .get_results::<DbConfig>(&*conn)?
-            .into_iter()
-            .map(Into::into)
-            .collect();
-        let endpoints = Endpoint::load(conn)?;
+    pub fn load(store: &dyn Store) -> StoreResult<Endpoints> {
+        let configs = store.configs()?;
+        let endpoints = store.endpoints()?;
         Ok(Endpoints { configs, endpoints })
     }
 }
@@ -40,24 +40,95 @@ pub struct Config {
     pub config: BaseConfig,
 }
 
-impl From<DbConfig> for Config {
-    fn from(c: DbConfig) -> Config {
-        Config {
+impl Config {
+    /// Load the module.json of a stored config. Fails with `StoreError::Internal` if it was
+    /// removed or changed into invalid JSON after the config was saved.
+    pub fn load(c: DbConfig) -> StoreResult<Config> {
+        let config = BaseConfig::try_from_path(&c.path).map_err(|e| {
+            StoreError::Internal(format!(
+                "Could not load the module.json of config {:?} in {}: {}",
+                c.name, c.path, e
+            ))
+        })?;
+        Ok(Config {
             id: c.id,
             name: c.name,
-            config: BaseConfig::from_path(&c.path),
             path: c.path,
-        }
+            config,
+        })
     }
 }
 
+/// The fields of a config that can be set through the API
 #[derive(Debug, Serialize, Deserialize)]
+pub struct ConfigForm {
+    pub name: String,
+    pub path: String,
+}
+
+/// The path of a `ConfigForm` does not contain a valid module.json
+#[derive(Debug)]
+pub struct InvalidConfigPath {
+    pub path: String,
+    pub error: io::Error,
+}
+
+impl fmt::Display for InvalidConfigPath {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "Could not load a module.json from {:?}: {}",
+            self.path, self.error
+        )
+    }
+}
+
+impl StdError for InvalidConfigPath {
+    fn description(&self) -> &str {
+        "Invalid config path"
+    }
+}
+
+impl Config {
+    /// Validate that `form.path` contains a valid module.json, and store it with the given id.
+    /// A new id is generated if `id` is `None`. The change is recorded as made by `actor`.
+    pub fn insert_or_update(
+        store: &dyn Store,
+        id: Option<Uuid>,
+        form: ConfigForm,
+        actor: &User,
+    ) -> Result<Config, Error> {
+        let config = BaseConfig::try_from_path(&form.path).map_err(|error| InvalidConfigPath {
+            path: form.path.clone(),
+            error,
+        })?;
+        let config = Config {
+            id: id.unwrap_or_else(Uuid::new_v4),
+            config,
+            name: form.name,
+            path: form.path,
+        };
+        store.save_config(&config, actor)?;
+        Ok(config)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Endpoint {
     pub id: Uuid,
     pub name: String,
     pub description: String,
     pub url: String,
     pub instructions: Vec<Instruction>,
+    /// The version this endpoint was loaded at. Saving fails with `SaveError::Conflict` if it has changed since.
+    #[serde(default)]
+    pub version: i32,
+    #[serde(default)]
+    pub updated_at: Option<NaiveDateTime>,
+    /// The user that created the endpoint, set by the store and ignored when saving.
+    /// `None` for endpoints created before there were users.
+    #[serde(default)]
+    pub owner: Option<Uuid>,
 }
 
 impl From<DBEndpoint> for Endpoint {
@@ -68,198 +139,342 @@ impl From<DBEndpoint> for Endpoint {
             description: endpoint.description,
             url: endpoint.url,
             instructions: Vec::new(),
+            version: endpoint.version,
+            updated_at: Some(endpoint.updated_at),
+            owner: endpoint.owner,
         }
     }
 }
 
-impl Endpoint {
-    pub fn load(conn: &PgConnection) -> QueryResult<Vec<Endpoint>> {
-        let endpoints: Vec<DBEndpoint> = schema::endpoint::table.get_results(&*conn).unwrap();
-        let instructions = DBInstruction::belonging_to(&endpoints)
-            .get_results::<DBInstruction>(&*conn)
-            .unwrap();
-        let grouped_by_type = instructions
-            .into_iter()
-            .map(|i| (InstructionType::from_i16(i.type_).unwrap(), i))
-            .into_group_map();
-
-        let mut instructions = HashMap::with_capacity(endpoints.len());
-
-        for (key, values) in grouped_by_type {
-            for wrapper in Instruction::load(conn, key, &values)? {
-                instructions
-                    .entry(wrapper.endpoint_id)
-                    .or_insert_with(Vec::new)
-                    .push(wrapper);
+/// An error returned by `Endpoint::insert_or_update`
+#[derive(Debug)]
+pub enum SaveError {
+    /// The endpoint was saved by someone else since it was loaded
+    Conflict { id: Uuid, expected: i32, actual: i32 },
+    /// The author may not create endpoints, or may not change this endpoint, see `User::can_change`
+    Forbidden(Uuid),
+    Invalid(InvalidArgument),
+    /// Two instructions have the given id
+    DuplicateInstruction(Uuid),
+    Store(StoreError),
+}
+
+impl From<InvalidArgument> for SaveError {
+    fn from(e: InvalidArgument) -> SaveError {
+        SaveError::Invalid(e)
+    }
+}
+
+impl From<StoreError> for SaveError {
+    fn from(e: StoreError) -> SaveError {
+        SaveError::Store(e)
+    }
+}
+
+impl From<DieselError> for SaveError {
+    fn from(e: DieselError) -> SaveError {
+        SaveError::Store(e.into())
+    }
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Conflict {
+                id,
+                expected,
+                actual,
+            } => write!(
+                fmt,
+                "Endpoint {} was modified by someone else (expected version {}, found {})",
+                id, expected, actual
+            ),
+            SaveError::Forbidden(id) => write!(fmt, "You may not change endpoint {}", id),
+            SaveError::Invalid(e) => write!(fmt, "{}", e),
+            SaveError::DuplicateInstruction(id) => {
+                write!(fmt, "More than one instruction has the id {}", id)
             }
+            SaveError::Store(e) => write!(fmt, "{}", e),
         }
+    }
+}
 
-        let mut endpoints: Vec<Endpoint> = endpoints.into_iter().map(Into::into).collect();
-
-        for (key, value) in instructions {
-            let instructions = value
-                .into_iter()
-                .sorted_by_key(|v| v.sequence)
-                .into_iter()
-                .map(|v| v.instruction)
-                .collect();
-            let endpoint = endpoints.iter_mut().find(|e| e.id == key).unwrap();
-            endpoint.instructions = instructions;
+impl StdError for SaveError {
+    fn description(&self) -> &str {
+        match self {
+            SaveError::Conflict { .. } => "Endpoint was modified by someone else",
+            SaveError::Forbidden(_) => "Endpoint may not be changed",
+            SaveError::Invalid(_) => "Invalid argument",
+            SaveError::DuplicateInstruction(_) => "Duplicate instruction id",
+            SaveError::Store(_) => "Store error",
         }
-        Ok(endpoints)
-    }
-
-    pub fn load_one(conn: &PgConnection, id: Uuid) -> QueryResult<Option<Endpoint>> {
-        let endpoint: Option<DBEndpoint> = schema::endpoint::table
-            .filter(schema::endpoint::id.eq(id))
-            .get_result(&*conn)
-            .optional()
-            .unwrap();
-        let endpoint = match endpoint {
-            Some(e) => e,
-            None => return Ok(None),
-        };
-        let instructions = DBInstruction::belonging_to(&endpoint)
-            .get_results::<DBInstruction>(&*conn)
-            .unwrap();
-        let grouped_by_type = instructions
-            .into_iter()
-            .map(|i| (InstructionType::from_i16(i.type_).unwrap(), i))
-            .into_group_map();
-
-        let mut instructions = Vec::new();
-
-        for (key, values) in grouped_by_type {
-            for wrapper in Instruction::load(conn, key, &values)? {
-                instructions.push(wrapper);
+    }
+
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            SaveError::Invalid(e) => Some(e),
+            SaveError::Store(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl Endpoint {
+    pub fn generate(&self, store: &dyn Store) -> Result<HashMap<String, String>, Error> {
+        Ok(self.builder(store)?.build()?)
+    }
+
+    pub fn generate_with_source_map(
+        &self,
+        store: &dyn Store,
+    ) -> Result<(HashMap<String, String>, web_api_generator::SourceMap), Error> {
+        Ok(self.builder(store)?.build_with_source_map()?)
+    }
+
+    /// Generate the crate as an archive that builds on its own, with its modules copied in
+    pub fn generate_archive(
+        &self,
+        store: &dyn Store,
+        format: web_api_generator::ArchiveFormat,
+    ) -> Result<Vec<u8>, Error> {
+        Ok(self.builder(store)?.build_archive(format, &self.archive_name())?)
+    }
+
+    /// The name of the generated archive and its root directory, without an extension
+    pub fn archive_name(&self) -> String {
+        self.name
+            .chars()
+            .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+            .collect()
+    }
+
+    /// Generate the files of an endpoint that has not been saved, e.g. while it is edited.
+    /// Problems are returned as diagnostics instead of errors, and no files are generated
+    /// if there are any.
+    pub fn preview(&self, store: &dyn Store) -> StoreResult<Preview> {
+        let mut diagnostics = Vec::new();
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            if let Err(e) = instruction.validate() {
+                diagnostics.push(PreviewDiagnostic::instruction(
+                    index,
+                    instruction,
+                    e.to_string(),
+                ));
             }
         }
+        let configs = store.module_configs(&self.config_ids())?;
+        if let Some(broken) = validation::check(self, &configs) {
+            for broken in broken.instructions {
+                let instruction = &self.instructions[broken.index];
+                for problem in broken.problems {
+                    diagnostics.push(PreviewDiagnostic::instruction(
+                        broken.index,
+                        instruction,
+                        problem.to_string(),
+                    ));
+                }
+            }
+        }
+        if !diagnostics.is_empty() {
+            return Ok(Preview {
+                files: HashMap::new(),
+                diagnostics,
+            });
+        }
+
+        let (files, diagnostics) = match self.unchecked_builder(&configs).build() {
+            Ok(files) => (files, Vec::new()),
+            Err(e) => (
+                HashMap::new(),
+                vec![PreviewDiagnostic::endpoint(e.to_string())],
+            ),
+        };
+        Ok(Preview { files, diagnostics })
+    }
+
+    /// Run the endpoint with the interpreter, without saving or building it.
+    /// `inputs` are the values of the `{name}` segments of the url.
+    pub fn run(
+        &self,
+        store: &dyn Store,
+        interpreter: &Interpreter,
+        inputs: HashMap<String, Value>,
+    ) -> Result<Outcome, Error> {
+        let configs = self.checked_configs(store)?;
+        Ok(self.to_generator(&configs).interpret(interpreter, inputs)?)
+    }
 
-        let mut endpoint: Endpoint = endpoint.into();
+    /// Run the endpoint like `run`, and record the module calls it makes.
+    /// A failed run is not an error, so the calls made before the failure can be inspected.
+    pub fn trace(
+        &self,
+        store: &dyn Store,
+        interpreter: &Interpreter,
+        inputs: HashMap<String, Value>,
+    ) -> Result<Trace, Error> {
+        let configs = self.checked_configs(store)?;
+        let mut run = interpreter.start_traced(inputs);
+        let result = self.to_generator(&configs).interpret_with(&mut run);
+        let (outcome, error) = match result {
+            Ok(outcome) => (Some(outcome), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+        Ok(Trace {
+            outcome,
+            error,
+            calls: run.into_trace(),
+        })
+    }
 
-        let instructions = instructions
-            .into_iter()
-            .sorted_by_key(|v| v.sequence)
-            .into_iter()
-            .map(|v| v.instruction)
-            .collect();
-        endpoint.instructions = instructions;
-        Ok(Some(endpoint))
+    /// A builder for the endpoint, which is validated when it builds
+    fn builder(&self, store: &dyn Store) -> Result<web_api_generator::Builder, Error> {
+        let configs = self.checked_configs(store)?;
+        Ok(self.unchecked_builder(&configs))
     }
 
-    pub fn generate(&self, conn: &PgConnection) -> QueryResult<HashMap<String, String>> {
+    /// Validate the instructions, and load the configs they call
+    fn checked_configs(&self, store: &dyn Store) -> Result<Vec<(Uuid, Rc<BaseConfig>)>, Error> {
+        for instruction in &self.instructions {
+            instruction.validate()?;
+        }
+        let configs = store.module_configs(&self.config_ids())?;
+        if let Some(broken) = validation::check(self, &configs) {
+            return Err(broken.into());
+        }
+        Ok(configs)
+    }
+
+    /// A builder with the endpoint and its modules, for instructions that have been checked
+    fn unchecked_builder(
+        &self,
+        configs: &Vec<(Uuid, Rc<BaseConfig>)>,
+    ) -> web_api_generator::Builder {
         let mut builder = web_api_generator::Builder::default();
-        let config_ids = self
-            .instructions
+        for config in configs {
+            builder.add_module(config.1.clone());
+        }
+        builder.add_endpoint(self.to_generator(configs));
+        builder
+    }
+
+    pub fn config_ids(&self) -> Vec<Uuid> {
+        self.instructions
             .iter()
             .filter_map(|i| i.get_config_id())
             .unique()
-            .collect::<Vec<_>>();
-        let result = {
-            let configs: Vec<Config> = schema::config::table
-                .filter(schema::config::id.eq_any(&config_ids))
-                .get_results::<DbConfig>(conn)?
-                .into_iter()
-                .map(Into::into)
-                .collect();
-            let result = {
-                let configs = configs.into_iter().map(|c| (c.id, Rc::new(c.config))).collect::<Vec<_>>();
-                for config in &configs {
-                    builder.add_module(config.1.clone());
-                }
-                builder.add_endpoint(web_api_generator::EndPoint {
-                    name: self.name.clone(),
-                    url: self.url.clone(),
-                    instructions: self
-                        .instructions
-                        .iter()
-                        .map(|i| (i, &configs))
-                        .map(Convert::from)
-                        .collect(),
-                });
-                let result = builder.build();
-                result
-            };
-            result
-        };
-        Ok(result)
+            .collect()
     }
 
-    pub fn insert_or_update(&mut self, conn: &PgConnection) -> QueryResult<()> {
-        let db = DBEndpoint {
-            id: self.id,
+    pub fn to_generator(
+        &self,
+        configs: &Vec<(Uuid, Rc<BaseConfig>)>,
+    ) -> web_api_generator::EndPoint {
+        web_api_generator::EndPoint {
             name: self.name.clone(),
-            description: self.description.clone(),
             url: self.url.clone(),
-        };
-
-        let id: Uuid = ::diesel::insert_into(schema::endpoint::table)
-            .values(&db)
-            .on_conflict(schema::endpoint::dsl::id)
-            .do_update()
-            .set(&db)
-            .returning(schema::endpoint::dsl::id)
-            .get_result(conn)?;
+            instructions: self
+                .instructions
+                .iter()
+                .map(|i| (i, configs))
+                .map(Convert::from)
+                .collect(),
+        }
+    }
 
-        self.id = id;
+    /// Insert an instruction before the given index, or at the end if the index is out of range
+    pub fn insert_instruction(&mut self, index: usize, instruction: Instruction) {
+        let index = index.min(self.instructions.len());
+        self.instructions.insert(index, instruction);
+    }
 
-        ::diesel::sql_query("UPDATE instruction SET sequence = -1 - sequence WHERE endpoint = $1")
-            .bind::<::diesel::sql_types::Uuid, _>(id)
-            .execute(conn)?;
+    /// Remove the instruction with the given id. Returns `false` if no such instruction exists.
+    pub fn remove_instruction(&mut self, id: Uuid) -> bool {
+        match self.instructions.iter().position(|i| i.get_id() == id) {
+            Some(index) => {
+                self.instructions.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
 
-        for (index, instruction) in self.instructions.iter_mut().enumerate() {
-            instruction.insert_or_update(id, index as i32, conn)?;
+    /// Order the instructions by the given list of instruction ids.
+    /// Returns `false` if `order` does not contain every instruction exactly once.
+    pub fn reorder_instructions(&mut self, order: &[Uuid]) -> bool {
+        if order.len() != self.instructions.len()
+            || order.iter().unique().count() != order.len()
+            || !order
+                .iter()
+                .all(|id| self.instructions.iter().any(|i| i.get_id() == *id))
+        {
+            return false;
         }
+        self.instructions
+            .sort_by_key(|i| order.iter().position(|id| *id == i.get_id()));
+        true
+    }
 
-        ::diesel::delete(
-            schema::instruction::table.filter(schema::instruction::dsl::sequence.lt(0)),
-        ).execute(conn)?;
-        Ok(())
+    /// Validate the instructions and check that their ids are unique, then save the endpoint and
+    /// record a new revision. Fails with `SaveError::Conflict` if the stored version differs from
+    /// `self.version`, and with `SaveError::Forbidden` if `author` may not save it.
+    pub fn insert_or_update(&mut self, store: &dyn Store, author: &User) -> Result<(), SaveError> {
+        for instruction in &self.instructions {
+            instruction.validate()?;
+        }
+        if let Some(id) = validation::duplicate_instruction(self) {
+            return Err(SaveError::DuplicateInstruction(id));
+        }
+        store.save_endpoint(self, author)
     }
 }
 
-struct InstructionWrapper {
-    pub endpoint_id: Uuid,
-    pub sequence: i32,
-    pub instruction: Instruction,
+/// The result of `Endpoint::preview`
+#[derive(Debug, Serialize)]
+pub struct Preview {
+    /// The generated files by path, empty if there are any diagnostics
+    pub files: HashMap<String, String>,
+    pub diagnostics: Vec<PreviewDiagnostic>,
 }
 
-impl From<(DBCallModule, Vec<DBCallModuleParameter>)> for Instruction {
-    fn from((module, parameters): (DBCallModule, Vec<DBCallModuleParameter>)) -> Instruction {
-        Instruction::CallMethod {
-            id: module.instruction_id,
-            config: module.config,
-            method: module.method,
-            out_variable_name: module.out_variable_name,
-            arguments: parameters
-                .into_iter()
-                .sorted_by_key(|p| p.sequence)
-                .into_iter()
-                .map(Into::into)
-                .collect(),
-        }
-    }
+/// The result of `Endpoint::trace`
+#[derive(Debug, Serialize)]
+pub struct Trace {
+    /// `None` if the run failed
+    pub outcome: Option<Outcome>,
+    pub error: Option<String>,
+    /// The module calls in the order they were made, including the call that failed
+    pub calls: Vec<TraceEntry>,
+}
+
+/// A problem that prevents an endpoint from being generated
+#[derive(Debug, Serialize)]
+pub struct PreviewDiagnostic {
+    /// The instruction with the problem, or `None` if it concerns the whole endpoint
+    pub instruction: Option<Uuid>,
+    pub index: Option<usize>,
+    pub message: String,
 }
 
-impl From<DBJsonReturn> for Instruction {
-    fn from(ret: DBJsonReturn) -> Instruction {
-        Instruction::JsonReturn {
-            id: ret.instruction_id,
-            arg_type: ArgType::from_i16(ret.arg_type).unwrap(),
-            arg_type_value: ret.arg_type_value,
+impl PreviewDiagnostic {
+    fn instruction(index: usize, instruction: &Instruction, message: String) -> PreviewDiagnostic {
+        PreviewDiagnostic {
+            instruction: Some(instruction.get_id()),
+            index: Some(index),
+            message,
         }
     }
-}
 
-enum_from_primitive! {
-#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
-enum InstructionType {
-    CallMethod = 1,
-    JsonReturn = 2,
-}
+    fn endpoint(message: String) -> PreviewDiagnostic {
+        PreviewDiagnostic {
+            instruction: None,
+            index: None,
+            message,
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// An instruction of an endpoint. Stored as JSON, so adding a variant does not need a migration.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Instruction {
     CallMethod {
         id: Uuid,
@@ -276,64 +491,24 @@ pub enum Instruction {
 }
 
 impl Instruction {
-    fn load(
-        conn: &PgConnection,
-        key: InstructionType,
-        values: &[DBInstruction],
-    ) -> QueryResult<Vec<InstructionWrapper>> {
-        let ids = values.iter().map(|v| v.id).collect::<Vec<_>>();
-        match key {
-            InstructionType::CallMethod => {
-                let methods = schema::instruction_call_module::table
-                    .filter(schema::instruction_call_module::dsl::instruction_id.eq_any(&ids))
-                    .get_results::<DBCallModule>(&*conn)?;
-                let method_parameters = DBCallModuleParameter::belonging_to(&methods)
-                    .get_results::<DBCallModuleParameter>(&*conn)?
-                    .grouped_by(&methods);
-                // let methods: Vec<Instruction> = methods.into_iter().zip(method_parameters.into_iter()).map(Into::into).collect();
-                let mut result = Vec::with_capacity(methods.len());
-                for (method, parameters) in methods.into_iter().zip(method_parameters.into_iter()) {
-                    let instruction = values
-                        .iter()
-                        .find(|v| v.id == method.instruction_id)
-                        .unwrap();
-                    result.push(InstructionWrapper {
-                        instruction: (method, parameters).into(),
-                        sequence: instruction.sequence,
-                        endpoint_id: instruction.endpoint,
-                    })
-                }
-                Ok(result)
-            }
-            InstructionType::JsonReturn => {
-                let instructions = schema::instruction_json_return::table
-                    .filter(schema::instruction_json_return::dsl::instruction_id.eq_any(&ids))
-                    .get_results::<DBJsonReturn>(&*conn)?;
-                let mut result = Vec::with_capacity(instructions.len());
-                for instruction in instructions {
-                    let definition = values
-                        .iter()
-                        .find(|v| v.id == instruction.instruction_id)
-                        .unwrap();
-                    result.push(InstructionWrapper {
-                        instruction: instruction.into(),
-                        sequence: definition.sequence,
-                        endpoint_id: definition.endpoint,
-                    });
+    /// Check that every argument can be parsed as its `ArgType`
+    pub fn validate(&self) -> Result<(), InvalidArgument> {
+        match self {
+            Instruction::CallMethod { arguments, .. } => {
+                for argument in arguments {
+                    argument.arg_type.parse(&argument.arg_type_value)?;
                 }
-                Ok(result)
+                Ok(())
             }
+            Instruction::JsonReturn {
+                arg_type,
+                arg_type_value,
+                ..
+            } => arg_type.parse(arg_type_value).map(|_| ()),
         }
     }
 
-    fn get_type(&self) -> i16 {
-        match self {
-            Instruction::CallMethod { .. } => InstructionType::CallMethod as i16,
-            Instruction::JsonReturn { .. } => InstructionType::JsonReturn as i16,
-        }
-    }
-
-    fn get_id(&self) -> Uuid {
+    pub fn get_id(&self) -> Uuid {
         match self {
             Instruction::CallMethod { id, .. } | Instruction::JsonReturn { id, .. } => *id,
         }
@@ -344,117 +519,6 @@ impl Instruction {
             _ => None,
         }
     }
-    fn set_id(&mut self, new_id: Uuid) {
-        match self {
-            Instruction::CallMethod { id, .. } | Instruction::JsonReturn { id, .. } => *id = new_id,
-        }
-    }
-    fn insert_or_update(
-        &mut self,
-        endpoint: Uuid,
-        sequence: i32,
-        conn: &PgConnection,
-    ) -> QueryResult<()> {
-        let mut id = self.get_id();
-        {
-            let type_ = self.get_type();
-            let instruction = DBInstruction {
-                id,
-                type_,
-                endpoint,
-                sequence,
-            };
-            id = ::diesel::insert_into(schema::instruction::table)
-                .values(&instruction)
-                .on_conflict(schema::instruction::dsl::id)
-                .do_update()
-                .set(&instruction)
-                .returning(schema::instruction::dsl::id)
-                .get_result(conn)?;
-            self.set_id(id);
-        }
-        match self {
-            Instruction::CallMethod {
-                id,
-                config,
-                method,
-                out_variable_name,
-                arguments,
-            } => {
-                let module = DBCallModule {
-                    instruction_id: *id,
-                    config: *config,
-                    method: method.clone(),
-                    out_variable_name: out_variable_name.clone(),
-                };
-                ::diesel::delete(
-                    schema::instruction_json_return::table.filter(
-                        schema::instruction_json_return::dsl::instruction_id.eq(id.clone()),
-                    ),
-                ).execute(conn)?;
-                ::diesel::insert_into(schema::instruction_call_module::table)
-                    .values(&module)
-                    .on_conflict(schema::instruction_call_module::dsl::instruction_id)
-                    .do_update()
-                    .set(&module)
-                    .execute(conn)?;
-                for (index, arg) in arguments.iter().enumerate() {
-                    let param = DBCallModuleParameter {
-                        instruction_call_module_id: *id,
-                        sequence: index as i16,
-                        name: arg.name.clone(),
-                        arg_type: arg.arg_type as i16,
-                        arg_type_value: arg.arg_type_value.clone(),
-                    };
-                    ::diesel::insert_into(schema::instruction_call_module_parameter::table)
-                        .values(&param)
-                        .on_conflict((schema::instruction_call_module_parameter::dsl::instruction_call_module_id, schema::instruction_call_module_parameter::dsl::sequence))
-                        .do_update()
-                        .set(&param)
-                        .execute(conn)?;
-                }
-                ::diesel::delete(
-                    schema::instruction_call_module_parameter::table.filter(
-                        schema::instruction_call_module_parameter::dsl::instruction_call_module_id
-                            .eq(id.clone())
-                            .and(
-                                schema::instruction_call_module_parameter::dsl::sequence
-                                    .ge(arguments.len() as i16),
-                            ),
-                    ),
-                ).execute(conn)?;
-            }
-            Instruction::JsonReturn {
-                id,
-                arg_type,
-                arg_type_value,
-            } => {
-                let db = DBJsonReturn {
-                    instruction_id: *id,
-                    arg_type: *arg_type as i16,
-                    arg_type_value: arg_type_value.clone(),
-                };
-                ::diesel::delete(
-                    schema::instruction_call_module_parameter::table.filter(
-                        schema::instruction_call_module_parameter::dsl::instruction_call_module_id
-                            .eq(id.clone()),
-                    ),
-                ).execute(conn)?;
-                ::diesel::delete(
-                    schema::instruction_call_module::table.filter(
-                        schema::instruction_call_module::dsl::instruction_id.eq(id.clone()),
-                    ),
-                ).execute(conn)?;
-                ::diesel::insert_into(schema::instruction_json_return::table)
-                    .values(&db)
-                    .on_conflict(schema::instruction_json_return::dsl::instruction_id)
-                    .do_update()
-                    .set(&db)
-                    .execute(conn)?;
-            }
-        }
-        Ok(())
-    }
 }
 
 trait Convert<T> {
@@ -470,14 +534,11 @@ impl<'a> Convert<(&'a Instruction, &'a Vec<(Uuid, Rc<web_api_generator::Config>)
                 arg_type,
                 arg_type_value,
                 ..
-            } => web_api_generator::Instruction::Json(match arg_type {
-                ArgType::String => {
-                    web_api_generator::InstructionParameter::String(arg_type_value.clone())
-                }
-                ArgType::Parameter => {
-                    web_api_generator::InstructionParameter::Variable(arg_type_value.clone())
-                }
-            }),
+            } => web_api_generator::Instruction::Json(
+                arg_type
+                    .parse(arg_type_value)
+                    .expect("Instruction arguments are validated before generating"),
+            ),
             Instruction::CallMethod {
                 config,
                 method,
@@ -495,44 +556,3 @@ impl<'a> Convert<(&'a Instruction, &'a Vec<(Uuid, Rc<web_api_generator::Config>)
         }
     }
 }
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CallMethodArgument {
-    pub name: String,
-    pub arg_type: ArgType,
-    pub arg_type_value: String,
-}
-
-impl<'a> From<&'a CallMethodArgument> for (String, web_api_generator::InstructionParameter) {
-    fn from(m: &'a CallMethodArgument) -> (String, web_api_generator::InstructionParameter) {
-        (
-            m.name.clone(),
-            match m.arg_type {
-                ArgType::String => {
-                    web_api_generator::InstructionParameter::String(m.arg_type_value.clone())
-                }
-                ArgType::Parameter => {
-                    web_api_generator::InstructionParameter::Variable(m.arg_type_value.clone())
-                }
-            },
-        )
-    }
-}
-
-impl From<DBCallModuleParameter> for CallMethodArgument {
-    fn from(param: DBCallModuleParameter) -> CallMethodArgument {
-        CallMethodArgument {
-            name: param.name,
-            arg_type: ArgType::from_i16(param.arg_type).unwrap(),
-            arg_type_value: param.arg_type_value,
-        }
-    }
-}
-
-enum_from_primitive! {
-#[derive(Debug, Hash, PartialEq, Eq, Serialize, Deserialize, Clone, Copy)]
-pub enum ArgType {
-    Parameter = 1,
-    String = 2,
-}
-}
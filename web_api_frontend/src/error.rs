@@ -0,0 +1,150 @@
+use actix_web::http::StatusCode;
+use actix_web::{HttpResponse, ResponseError};
+use diesel::result::Error as DieselError;
+use endpoint::{InvalidArgument, InvalidConfigPath, SaveError};
+use failure::{self, Fail};
+use project::ProjectError;
+use rust_functional::InterpretError;
+use std::fmt;
+use std::io;
+use store::StoreError;
+use uuid;
+use validation::BrokenEndpoint;
+use web_api_generator::BuilderError;
+
+/// An error returned by the API, rendered as `{"error": kind, "message": message}`
+#[derive(Debug)]
+pub enum ApiError {
+    BadRequest(String),
+    /// The request has no valid session
+    Unauthorized(String),
+    /// The logged in user may not do this
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    Internal(String),
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    error: &'static str,
+    message: &'a str,
+}
+
+impl ApiError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            ApiError::BadRequest(_) => "bad_request",
+            ApiError::Unauthorized(_) => "unauthorized",
+            ApiError::Forbidden(_) => "forbidden",
+            ApiError::NotFound(_) => "not_found",
+            ApiError::Conflict(_) => "conflict",
+            ApiError::Internal(_) => "internal",
+        }
+    }
+
+    fn message(&self) -> &str {
+        match self {
+            ApiError::BadRequest(m)
+            | ApiError::Unauthorized(m)
+            | ApiError::Forbidden(m)
+            | ApiError::NotFound(m)
+            | ApiError::Conflict(m)
+            | ApiError::Internal(m) => m,
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}: {}", self.kind(), self.message())
+    }
+}
+
+impl Fail for ApiError {}
+
+impl ResponseError for ApiError {
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status()).json(ErrorBody {
+            error: self.kind(),
+            message: self.message(),
+        })
+    }
+}
+
+impl From<StoreError> for ApiError {
+    fn from(e: StoreError) -> ApiError {
+        match e {
+            StoreError::Conflict(message) => ApiError::Conflict(message),
+            StoreError::Internal(message) => ApiError::Internal(message),
+        }
+    }
+}
+
+impl From<SaveError> for ApiError {
+    fn from(e: SaveError) -> ApiError {
+        match e {
+            SaveError::Conflict { .. } => ApiError::Conflict(e.to_string()),
+            SaveError::Forbidden(_) => ApiError::Forbidden(e.to_string()),
+            SaveError::Invalid(_) | SaveError::DuplicateInstruction(_) => {
+                ApiError::BadRequest(e.to_string())
+            }
+            SaveError::Store(e) => e.into(),
+        }
+    }
+}
+
+impl From<uuid::ParseError> for ApiError {
+    fn from(e: uuid::ParseError) -> ApiError {
+        ApiError::BadRequest(e.to_string())
+    }
+}
+
+impl From<io::Error> for ApiError {
+    fn from(e: io::Error) -> ApiError {
+        ApiError::Internal(e.to_string())
+    }
+}
+
+impl From<failure::Error> for ApiError {
+    fn from(e: failure::Error) -> ApiError {
+        let e = match e.downcast::<StoreError>() {
+            Ok(e) => return e.into(),
+            Err(e) => e,
+        };
+        // Returned by the transactions of the diesel stores
+        let e = match e.downcast::<DieselError>() {
+            Ok(e) => return StoreError::from(e).into(),
+            Err(e) => e,
+        };
+        let e = match e.downcast::<SaveError>() {
+            Ok(e) => return e.into(),
+            Err(e) => e,
+        };
+        // The modules could not be copied into an archive
+        if let Some(BuilderError::Io(_)) = e.downcast_ref::<BuilderError>() {
+            return ApiError::Internal(e.to_string());
+        }
+        if e.downcast_ref::<BuilderError>().is_some()
+            || e.downcast_ref::<BrokenEndpoint>().is_some()
+            || e.downcast_ref::<ProjectError>().is_some()
+            || e.downcast_ref::<InvalidArgument>().is_some()
+            || e.downcast_ref::<InvalidConfigPath>().is_some()
+            || e.downcast_ref::<InterpretError>().is_some()
+        {
+            return ApiError::BadRequest(e.to_string());
+        }
+        ApiError::Internal(e.to_string())
+    }
+}
@@ -0,0 +1,141 @@
+//! Change events, sent to the browsers as server-sent events so that people editing at the same
+//! time see each other's saves
+
+use bytes::Bytes;
+use endpoint::{Config, Endpoint};
+use futures::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use serde_json;
+use std::sync::{Arc, Mutex, MutexGuard};
+use user::User;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// An endpoint was created or changed. A client that has an older `version` loaded
+    /// can no longer save it.
+    EndpointSaved {
+        id: Uuid,
+        name: String,
+        version: i32,
+        /// The name of the user who saved it
+        actor: String,
+    },
+    EndpointDeleted { id: Uuid, actor: String },
+    /// A config was created or changed, and its module.json was read again
+    ConfigSaved { id: Uuid, name: String, actor: String },
+    ConfigDeleted { id: Uuid, actor: String },
+}
+
+impl Event {
+    pub fn endpoint_saved(endpoint: &Endpoint, actor: &User) -> Event {
+        Event::EndpointSaved {
+            id: endpoint.id,
+            name: endpoint.name.clone(),
+            version: endpoint.version,
+            actor: actor.name.clone(),
+        }
+    }
+
+    pub fn config_saved(config: &Config, actor: &User) -> Event {
+        Event::ConfigSaved {
+            id: config.id,
+            name: config.name.clone(),
+            actor: actor.name.clone(),
+        }
+    }
+
+    /// The event as a `text/event-stream` message
+    pub fn to_message(&self) -> Bytes {
+        let data = serde_json::to_string(self).expect("Event is always serializable");
+        Bytes::from(format!("data: {}\n\n", data))
+    }
+}
+
+/// Sends events to every subscriber, shared between all workers
+#[derive(Clone, Default)]
+pub struct Events {
+    subscribers: Arc<Mutex<Vec<UnboundedSender<Event>>>>,
+}
+
+impl Events {
+    /// Receive every event published from now on
+    pub fn subscribe(&self) -> UnboundedReceiver<Event> {
+        let (sender, receiver) = mpsc::unbounded();
+        self.lock().push(sender);
+        receiver
+    }
+
+    /// Send the event to every subscriber, and forget the subscribers that disconnected
+    pub fn publish(&self, event: Event) {
+        self.lock()
+            .retain(|subscriber| subscriber.unbounded_send(event.clone()).is_ok());
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<UnboundedSender<Event>>> {
+        // A panic while sending cannot leave the list of subscribers inconsistent
+        self.subscribers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{Future, Stream};
+    use user::Role;
+
+    #[test]
+    fn disconnected_subscribers_are_forgotten() {
+        let events = Events::default();
+        let receiver = events.subscribe();
+        drop(events.subscribe());
+        assert_eq!(2, events.lock().len());
+
+        let alice = User {
+            id: Uuid::new_v4(),
+            name: "alice".to_string(),
+            role: Role::Write,
+            password_hash: String::new(),
+        };
+        let endpoint = Endpoint {
+            id: Uuid::new_v4(),
+            name: "add".to_string(),
+            description: "Add two numbers".to_string(),
+            url: "/add".to_string(),
+            instructions: Vec::new(),
+            version: 1,
+            updated_at: None,
+            owner: Some(alice.id),
+        };
+        let event = Event::endpoint_saved(&endpoint, &alice);
+        events.clone().publish(event.clone());
+        assert_eq!(1, events.lock().len());
+
+        let (received, receiver) = receiver.into_future().wait().map_err(|(e, _)| e).unwrap();
+        assert_eq!(Some(event), received);
+        drop(receiver);
+        events.publish(Event::EndpointDeleted {
+            id: endpoint.id,
+            actor: "alice".to_string(),
+        });
+        assert!(events.lock().is_empty());
+    }
+
+    #[test]
+    fn events_are_tagged_messages() {
+        let id = Uuid::nil();
+        let event = Event::ConfigDeleted {
+            id,
+            actor: "alice".to_string(),
+        };
+        assert_eq!(
+            format!(
+                "data: {{\"type\":\"config_deleted\",\"id\":\"{}\",\"actor\":\"alice\"}}\n\n",
+                id
+            ),
+            String::from_utf8(event.to_message().to_vec()).unwrap()
+        );
+    }
+}
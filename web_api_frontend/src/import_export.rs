@@ -0,0 +1,237 @@
+use endpoint::{Config, ConfigForm, Endpoint, Instruction};
+use failure::Error;
+use project::{self, Module, Project, ProjectEndpoint};
+use std::collections::HashMap;
+use store::Store;
+use user::User;
+use uuid::Uuid;
+use validation::{self, BrokenEndpoint};
+
+/// Export the given endpoints, or all endpoints if `ids` is `None`, with the configs they call.
+/// Endpoints and modules are sorted by name, so exporting the same data gives the same document.
+pub fn export(store: &dyn Store, ids: Option<&[Uuid]>) -> Result<Project, Error> {
+    let mut endpoints = store.endpoints()?;
+    if let Some(ids) = ids {
+        endpoints.retain(|e| ids.contains(&e.id));
+    }
+    endpoints.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let config_ids = endpoints
+        .iter()
+        .flat_map(|e| e.config_ids())
+        .collect::<Vec<_>>();
+    let mut configs = store.configs()?;
+    configs.retain(|c| config_ids.contains(&c.id));
+    configs.sort_by(|a, b| a.name.cmp(&b.name));
+    let names = configs
+        .iter()
+        .map(|c| (c.id, c.name.clone()))
+        .collect::<HashMap<_, _>>();
+
+    let mut result = Vec::with_capacity(endpoints.len());
+    for endpoint in endpoints {
+        let mut instructions = Vec::with_capacity(endpoint.instructions.len());
+        for instruction in &endpoint.instructions {
+            instructions.push(to_project(instruction, &names)?);
+        }
+        result.push(ProjectEndpoint {
+            id: endpoint.id,
+            name: endpoint.name,
+            description: endpoint.description,
+            url: endpoint.url,
+            instructions,
+        });
+    }
+
+    Ok(Project {
+        version: project::VERSION,
+        modules: configs
+            .into_iter()
+            .map(|c| Module {
+                name: c.name,
+                path: c.path,
+            })
+            .collect(),
+        endpoints: result,
+    })
+}
+
+fn to_project(
+    instruction: &Instruction,
+    names: &HashMap<Uuid, String>,
+) -> Result<project::Instruction, Error> {
+    Ok(match instruction.clone() {
+        Instruction::CallMethod {
+            id,
+            config,
+            method,
+            out_variable_name,
+            arguments,
+        } => project::Instruction::CallMethod {
+            id,
+            module: names
+                .get(&config)
+                .ok_or_else(|| format_err!("Config {} not found", config))?
+                .clone(),
+            method,
+            out_variable_name,
+            arguments,
+        },
+        Instruction::JsonReturn {
+            id,
+            arg_type,
+            arg_type_value,
+        } => project::Instruction::JsonReturn {
+            id,
+            arg_type,
+            arg_type_value,
+        },
+    })
+}
+
+fn from_project(instruction: project::Instruction, ids: &HashMap<String, Uuid>) -> Instruction {
+    match instruction {
+        project::Instruction::CallMethod {
+            id,
+            module,
+            method,
+            out_variable_name,
+            arguments,
+        } => Instruction::CallMethod {
+            id,
+            config: ids[&module],
+            method,
+            out_variable_name,
+            arguments,
+        },
+        project::Instruction::JsonReturn {
+            id,
+            arg_type,
+            arg_type_value,
+        } => Instruction::JsonReturn {
+            id,
+            arg_type,
+            arg_type_value,
+        },
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportResult {
+    /// The modules that had no config with the same name, and were added
+    pub created_configs: Vec<String>,
+    pub endpoints: Vec<ImportedEndpoint>,
+    /// Imported endpoints that do not match the module configs of this instance
+    pub broken: Vec<BrokenEndpoint>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportedEndpoint {
+    pub id: Uuid,
+    pub name: String,
+    pub version: i32,
+    pub status: ImportStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportStatus {
+    Created,
+    Updated,
+    /// The endpoint is identical to the stored one, so no revision was created
+    Unchanged,
+}
+
+/// Store the modules and endpoints of a project in a single transaction.
+/// Modules are matched to configs by name, endpoints by id and otherwise by name.
+pub fn import(store: &dyn Store, project: Project, author: &User) -> Result<ImportResult, Error> {
+    project.validate()?;
+    let mut result = None;
+    store.transaction(&mut |store| {
+        result = Some(import_into(store, project.clone(), author)?);
+        Ok(())
+    })?;
+    Ok(result.expect("The import succeeded"))
+}
+
+fn import_into(store: &dyn Store, project: Project, author: &User) -> Result<ImportResult, Error> {
+    let mut created_configs = Vec::new();
+    let mut ids = HashMap::with_capacity(project.modules.len());
+    for module in project.modules {
+        let id = match store.config_by_name(&module.name)? {
+            Some(config) => config.id,
+            None => {
+                let form = ConfigForm {
+                    name: module.name.clone(),
+                    path: module.path,
+                };
+                created_configs.push(module.name.clone());
+                Config::insert_or_update(store, None, form, author)?.id
+            }
+        };
+        ids.insert(module.name, id);
+    }
+
+    let mut endpoints = Vec::with_capacity(project.endpoints.len());
+    let mut imported = Vec::with_capacity(project.endpoints.len());
+    for project_endpoint in project.endpoints {
+        let existing = match store.endpoint(project_endpoint.id)? {
+            Some(endpoint) => Some(endpoint),
+            None => store.endpoint_by_name(&project_endpoint.name)?,
+        };
+        let mut endpoint = Endpoint {
+            id: project_endpoint.id,
+            name: project_endpoint.name,
+            description: project_endpoint.description,
+            url: project_endpoint.url,
+            instructions: project_endpoint
+                .instructions
+                .into_iter()
+                .map(|i| from_project(i, &ids))
+                .collect(),
+            version: 0,
+            updated_at: None,
+            owner: None,
+        };
+        let status = match existing {
+            Some(ref existing)
+                if existing.name == endpoint.name
+                    && existing.description == endpoint.description
+                    && existing.url == endpoint.url
+                    && existing.instructions == endpoint.instructions =>
+            {
+                endpoint.id = existing.id;
+                endpoint.version = existing.version;
+                ImportStatus::Unchanged
+            }
+            Some(ref existing) => {
+                endpoint.id = existing.id;
+                endpoint.version = existing.version;
+                endpoint.insert_or_update(store, author)?;
+                ImportStatus::Updated
+            }
+            None => {
+                endpoint.insert_or_update(store, author)?;
+                ImportStatus::Created
+            }
+        };
+        imported.push(ImportedEndpoint {
+            id: endpoint.id,
+            name: endpoint.name.clone(),
+            version: endpoint.version,
+            status,
+        });
+        endpoints.push(endpoint);
+    }
+
+    let config_ids = ids.values().cloned().collect::<Vec<_>>();
+    let configs = store.module_configs(&config_ids)?;
+    Ok(ImportResult {
+        created_configs,
+        endpoints: imported,
+        broken: endpoints
+            .iter()
+            .filter_map(|e| validation::check(e, &configs))
+            .collect(),
+    })
+}
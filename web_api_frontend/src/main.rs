@@ -1,130 +1,174 @@
-#![feature(test)]
-
 extern crate actix_web;
+#[cfg(any(feature = "adder", test))]
+extern crate adder;
+extern crate build_runner;
+extern crate bytes;
+extern crate chrono;
+extern crate cookie;
 #[macro_use]
 extern crate diesel;
 extern crate dotenv;
+extern crate env_logger;
+extern crate futures;
 extern crate itertools;
+extern crate project;
 extern crate r2d2;
+extern crate ring;
 extern crate rust_functional;
 extern crate uuid;
 extern crate web_api_generator;
 #[macro_use]
 extern crate serde_derive;
+#[macro_use]
 extern crate failure;
 extern crate serde;
 extern crate serde_json;
-#[cfg(test)]
-extern crate test;
-#[macro_use]
-extern crate enum_primitive;
+extern crate time;
+extern crate toml;
 
+pub mod api;
+pub mod audit;
+pub mod auth;
+pub mod build;
 pub mod endpoint;
+pub mod error;
+pub mod events;
+pub mod import_export;
 pub mod models;
+pub mod revision;
 pub mod schema;
+pub mod service;
+pub mod settings;
+pub mod store;
+pub mod user;
+pub mod validation;
+
+#[cfg(test)]
+mod tests;
 
 use actix_web::fs::{NamedFile, StaticFiles};
-use actix_web::{server, App, HttpRequest, Json, Result};
-use diesel::pg::PgConnection;
-use diesel::r2d2::ConnectionManager;
-use r2d2::{Error, Pool};
+use actix_web::middleware::Logger;
+use actix_web::{server, App};
+use cookie::Key;
+use events::Events;
+use rust_functional::Interpreter;
+use settings::Settings;
 use std::env;
+use std::path::PathBuf;
+use std::process;
+use store::{SharedStore, Store, StoreError};
+use user::{Role, User};
 
 pub struct AppState {
-    pub db: DbConnection,
+    pub store: SharedStore,
+    pub interpreter: Interpreter,
+    /// Encrypts the session cookies, see `auth`
+    pub key: Key,
+    pub events: Events,
+    /// Where the crates of builds are kept, see `Settings::output_dir`
+    pub output_dir: Option<PathBuf>,
 }
 
 pub struct StateProvider {
-    db: DbConnection,
+    store: SharedStore,
+    interpreter: Interpreter,
+    key: Key,
+    events: Events,
+    output_dir: Option<PathBuf>,
+}
+
+/// An interpreter for the modules enabled as features, which have a `dispatch` module.
+/// Endpoints calling other modules can only be run if their methods have a mock.
+#[cfg_attr(not(feature = "adder"), allow(unused_mut))]
+pub fn interpreter() -> Interpreter {
+    let mut interpreter = Interpreter::default();
+    #[cfg(feature = "adder")]
+    interpreter.register("adder", adder::dispatch::call);
+    interpreter
 }
 
 impl StateProvider {
-    pub fn new() -> Result<StateProvider, Error> {
-        let db = establish_connection()?;
-        Ok(StateProvider { db })
+    /// Open the store given by `settings.database_url`, e.g. `postgres://...`,
+    /// `sqlite://frontend.db` or `memory:`
+    pub fn new(settings: &Settings) -> Result<StateProvider, StoreError> {
+        let store = store::open(&settings.database_url, settings.pool_size)?;
+        create_admin(&*store)?;
+        Ok(StateProvider {
+            store,
+            interpreter: interpreter(),
+            key: settings.session_key.clone(),
+            events: Events::default(),
+            output_dir: settings.output_dir.clone(),
+        })
     }
 
     pub fn create_state(&self) -> AppState {
         AppState {
-            db: self.db.clone(),
+            store: self.store.clone(),
+            interpreter: self.interpreter.clone(),
+            key: self.key.clone(),
+            events: self.events.clone(),
+            output_dir: self.output_dir.clone(),
         }
     }
 }
 
-#[derive(Clone)]
-pub struct DbConnection {
-    pub(crate) conn: Pool<ConnectionManager<PgConnection>>,
-}
-
-pub fn establish_connection() -> Result<DbConnection, Error> {
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    Ok(DbConnection {
-        conn: Pool::new(ConnectionManager::new(database_url))?,
-    })
-}
-
-fn generate(req: HttpRequest<AppState>) -> Result<String, failure::Error> {
-    let conn = req.state().db.conn.get()?;
-    let id = match req.match_info().get("id").map(uuid::Uuid::parse_str) {
-        Some(Ok(id)) => id,
-        _ => return Ok(String::new()),
-    };
-    let endpoint = match endpoint::Endpoint::load_one(&conn, id)? {
-        Some(e) => e,
-        None => return Ok(String::new()),
-    };
-    let output = endpoint.generate(&conn)?;
-    let mut result = String::new();
-    for (key, value) in output {
-        result += &format!("== {}\n{}\n\n", key, value);
+/// Create an admin named `admin` with the password in `ADMIN_PASSWORD` if there are no users yet,
+/// who can then add the other users
+fn create_admin(store: &dyn Store) -> Result<(), StoreError> {
+    if !store.users()?.is_empty() {
+        return Ok(());
+    }
+    match env::var("ADMIN_PASSWORD") {
+        Ok(password) => store.save_user(&User::new("admin".to_string(), &password, Role::Admin)),
+        Err(_) => {
+            eprintln!("There are no users, set ADMIN_PASSWORD to create an admin");
+            Ok(())
+        }
     }
-    Ok(result)
-}
-
-fn index(_: HttpRequest<AppState>) -> Result<NamedFile> {
-    Ok(NamedFile::open("frontend/index.html")?)
-}
-
-fn get_endpoints(req: HttpRequest<AppState>) -> Result<Json<endpoint::Endpoints>, failure::Error> {
-    let endpoint = endpoint::Endpoints::load(&*(req.state().db.conn.get()?))?;
-    Ok(Json(endpoint))
 }
 
-fn set_endpoints(
-    (req, form): (HttpRequest<AppState>, Json<endpoint::Endpoint>),
-) -> Result<Json<endpoint::Endpoint>, failure::Error> {
-    let mut endpoint = form.into_inner();
-    let conn = match req.state().db.conn.get() {
-        Ok(c) => c,
+fn main() {
+    // The settings can be in a config file instead
+    dotenv::dotenv().ok();
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        println!("{}", settings::USAGE);
+        return;
+    }
+    let settings = match Settings::load(&args, |name| env::var(name).ok()) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, settings::USAGE);
+            process::exit(2);
+        }
+    };
+    env_logger::Builder::new()
+        .parse_filters(&settings.log_level)
+        .init();
+    let state_provider = match StateProvider::new(&settings) {
+        Ok(state_provider) => state_provider,
         Err(e) => {
-            println!("{:?}", e);
-            return Err(e.into());
+            eprintln!("error: Could not open the store: {}", e);
+            process::exit(1);
         }
     };
-    match endpoint.insert_or_update(&*conn) {
-        Ok(()) => {},
+    let static_root = settings.static_root.clone();
+    let modules_dir = settings.modules_dir.clone();
+    let server = server::new(move || {
+        let index = static_root.join("index.html");
+        let app = App::with_state(state_provider.create_state())
+            .middleware(Logger::default())
+            .resource("/", move |r| r.get().f(move |_| NamedFile::open(&index)))
+            .handler("/dist", StaticFiles::new(static_root.join("dist")))
+            .handler("/node_modules", StaticFiles::new(modules_dir.clone()));
+        api::register(app)
+    });
+    match server.bind(&settings.bind) {
+        Ok(server) => server.run(),
         Err(e) => {
-            println!("{:?}", e);
-            return Err(e.into());
+            eprintln!("error: Could not listen on {}: {}", settings.bind, e);
+            process::exit(1);
         }
     }
-    Ok(Json(endpoint))
-}
-
-fn main() {
-    dotenv::dotenv().expect("Could not load .env file");
-    let state_provider = StateProvider::new().unwrap();
-    server::new(move || {
-        App::with_state(state_provider.create_state())
-            .resource("/", |r| r.get().f(index))
-            .handler("/dist", StaticFiles::new("frontend/dist"))
-            .handler("/node_modules", StaticFiles::new("frontend/node_modules"))
-            .resource("/api/endpoints", |r| {
-                r.get().f(get_endpoints);
-                r.post().with(set_endpoints);
-            })
-            .resource("/api/generate/{id}", |r| r.get().f(generate))
-    }).bind("127.0.0.1:8000")
-        .unwrap()
-        .run();
 }
@@ -1,10 +1,11 @@
+use chrono::NaiveDateTime;
 use schema::{
-    config, endpoint, instruction, instruction_call_module, instruction_call_module_parameter,
-    instruction_json_return,
+    app_user, audit, config, endpoint, endpoint_revision, instruction, service, service_endpoint,
 };
+use serde_json::Value;
 use uuid::Uuid;
 
-#[derive(Identifiable, Queryable, Debug)]
+#[derive(Identifiable, Queryable, Debug, Clone, Insertable, AsChangeset)]
 #[table_name = "config"]
 #[repr(C)]
 pub struct Config {
@@ -13,13 +14,47 @@ pub struct Config {
     pub path: String,
 }
 
-#[derive(Identifiable, Queryable, Debug, Insertable, AsChangeset)]
+#[derive(Identifiable, Queryable, Debug)]
 #[table_name = "endpoint"]
 pub struct Endpoint {
     pub id: Uuid,
     pub name: String,
     pub description: String,
     pub url: String,
+    pub version: i32,
+    pub updated_at: NaiveDateTime,
+    pub owner: Option<Uuid>,
+}
+
+/// The user-editable columns of an `Endpoint`, used for inserts and updates
+#[derive(Debug, Insertable, AsChangeset)]
+#[table_name = "endpoint"]
+pub struct EndpointChangeset {
+    pub id: Uuid,
+    pub name: String,
+    pub description: String,
+    pub url: String,
+}
+
+#[derive(Identifiable, Queryable, Associations, Debug)]
+#[table_name = "endpoint_revision"]
+#[belongs_to(Endpoint, foreign_key = "endpoint")]
+pub struct EndpointRevision {
+    pub id: Uuid,
+    pub endpoint: Uuid,
+    pub version: i32,
+    pub author: String,
+    pub created_at: NaiveDateTime,
+    pub snapshot: Value,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "endpoint_revision"]
+pub struct NewEndpointRevision {
+    pub endpoint: Uuid,
+    pub version: i32,
+    pub author: String,
+    pub snapshot: Value,
 }
 
 #[derive(Identifiable, Queryable, Associations, Debug, Insertable, AsChangeset)]
@@ -28,39 +63,61 @@ pub struct Endpoint {
 pub struct Instruction {
     pub id: Uuid,
     pub endpoint: Uuid,
-    pub type_: i16,
     pub sequence: i32,
+    /// The serialized `endpoint::Instruction`
+    pub payload: Value,
 }
 
-#[derive(Identifiable, Queryable, Associations, Debug, Insertable, AsChangeset)]
-#[table_name = "instruction_call_module"]
-#[belongs_to(Instruction, foreign_key = "instruction_id")]
-#[primary_key(instruction_id)]
-pub struct CallModule {
-    pub instruction_id: Uuid,
-    pub config: Uuid,
-    pub method: String,
-    pub out_variable_name: String,
+#[derive(Identifiable, Queryable, Debug, Insertable, AsChangeset)]
+#[table_name = "service"]
+pub struct Service {
+    pub id: Uuid,
+    pub name: String,
+    pub description: String,
+    pub backend: String,
 }
 
-#[derive(Identifiable, Queryable, Associations, Debug, Insertable, AsChangeset)]
-#[table_name = "instruction_call_module_parameter"]
-#[belongs_to(CallModule, foreign_key = "instruction_call_module_id")]
-#[primary_key(instruction_call_module_id, sequence)]
-pub struct CallModuleParameter {
-    pub instruction_call_module_id: Uuid,
-    pub sequence: i16,
+#[derive(Identifiable, Queryable, Associations, Debug, Insertable)]
+#[table_name = "service_endpoint"]
+#[belongs_to(Service, foreign_key = "service")]
+#[primary_key(service, endpoint)]
+pub struct ServiceEndpoint {
+    pub service: Uuid,
+    pub endpoint: Uuid,
+    pub prefix: String,
+}
+
+#[derive(Identifiable, Queryable, Debug, Insertable, AsChangeset)]
+#[table_name = "app_user"]
+pub struct User {
+    pub id: Uuid,
     pub name: String,
-    pub arg_type: i16,
-    pub arg_type_value: String,
+    pub password_hash: String,
+    /// The name of a `user::Role`
+    pub role: String,
 }
 
-#[derive(Identifiable, Queryable, Associations, Debug, Insertable, AsChangeset)]
-#[table_name = "instruction_json_return"]
-#[belongs_to(Instruction, foreign_key = "instruction_id")]
-#[primary_key(instruction_id)]
-pub struct JsonReturn {
-    pub instruction_id: Uuid,
-    pub arg_type: i16,
-    pub arg_type_value: String,
+#[derive(Identifiable, Queryable, Debug)]
+#[table_name = "audit"]
+pub struct AuditEntry {
+    pub id: i64,
+    pub created_at: NaiveDateTime,
+    pub actor: String,
+    /// The name of an `audit::Action`
+    pub action: String,
+    pub endpoint: Option<Uuid>,
+    pub config: Option<Uuid>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "audit"]
+pub struct NewAuditEntry {
+    pub actor: String,
+    pub action: String,
+    pub endpoint: Option<Uuid>,
+    pub config: Option<Uuid>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
 }
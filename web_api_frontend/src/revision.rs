@@ -0,0 +1,302 @@
+use chrono::NaiveDateTime;
+use endpoint::{Endpoint, Instruction};
+use models::EndpointRevision as DBEndpointRevision;
+use std::collections::{HashMap, HashSet};
+use store::{StoreError, StoreResult};
+use uuid::Uuid;
+
+/// An immutable snapshot of an endpoint, stored every time the endpoint is saved
+#[derive(Debug, Clone, Serialize)]
+pub struct Revision {
+    pub endpoint: Uuid,
+    pub version: i32,
+    pub author: String,
+    pub created_at: NaiveDateTime,
+    pub snapshot: Endpoint,
+}
+
+/// A revision without its snapshot, used to list the history of an endpoint
+#[derive(Debug, Serialize)]
+pub struct RevisionSummary {
+    pub version: i32,
+    pub author: String,
+    pub created_at: NaiveDateTime,
+}
+
+impl Revision {
+    /// Parse the snapshot of a stored revision
+    pub fn load(revision: DBEndpointRevision) -> StoreResult<Revision> {
+        let (endpoint, version) = (revision.endpoint, revision.version);
+        let snapshot = ::serde_json::from_value(revision.snapshot).map_err(|e| {
+            StoreError::Internal(format!(
+                "Revision {} of endpoint {} is not a valid endpoint: {}",
+                version, endpoint, e
+            ))
+        })?;
+        Ok(Revision {
+            endpoint,
+            version,
+            author: revision.author,
+            created_at: revision.created_at,
+            snapshot,
+        })
+    }
+
+    /// Get an endpoint that, when saved, restores this revision on top of the current version
+    pub fn restore(self, current_version: i32) -> Endpoint {
+        let mut endpoint = self.snapshot;
+        endpoint.version = current_version;
+        endpoint
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RevisionDiff<'a> {
+    pub from: i32,
+    pub to: i32,
+    pub fields: Vec<FieldChange<'a>>,
+    pub instructions: Vec<InstructionChange<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldChange<'a> {
+    pub field: &'static str,
+    pub before: &'a str,
+    pub after: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum InstructionChange<'a> {
+    Added {
+        index: usize,
+        instruction: &'a Instruction,
+    },
+    Removed {
+        index: usize,
+        instruction: &'a Instruction,
+    },
+    Modified {
+        id: Uuid,
+        before: &'a Instruction,
+        after: &'a Instruction,
+    },
+    /// The instruction is not part of the longest sequence of instructions that keeps its order
+    /// between the revisions, see `unmoved`
+    Moved { id: Uuid, from: usize, to: usize },
+}
+
+fn position(instructions: &[Instruction], id: Uuid) -> Option<usize> {
+    instructions.iter().position(|i| i.get_id() == id)
+}
+
+/// The ids in the longest common subsequence of `old` and `new`. Moving one instruction leaves
+/// all others in it, so only the moved instruction is reported.
+fn unmoved(old: &[Uuid], new: &[Uuid]) -> HashSet<Uuid> {
+    // `lengths[i][j]` is the length of the longest common subsequence of `old[i..]` and `new[j..]`
+    let mut lengths = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i] == new[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut result = HashSet::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            result.insert(old[i]);
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    result
+}
+
+/// Compare two revisions of the same endpoint. Instructions are matched by their id.
+pub fn diff<'a>(from: &'a Revision, to: &'a Revision) -> RevisionDiff<'a> {
+    let (old, new) = (&from.snapshot, &to.snapshot);
+
+    let mut fields = Vec::new();
+    for &(field, before, after) in &[
+        ("name", &old.name, &new.name),
+        ("description", &old.description, &new.description),
+        ("url", &old.url, &new.url),
+    ] {
+        if before != after {
+            fields.push(FieldChange {
+                field,
+                before,
+                after,
+            });
+        }
+    }
+
+    let mut instructions = Vec::new();
+    for (index, instruction) in old.instructions.iter().enumerate() {
+        if position(&new.instructions, instruction.get_id()).is_none() {
+            instructions.push(InstructionChange::Removed { index, instruction });
+        }
+    }
+    for (index, instruction) in new.instructions.iter().enumerate() {
+        if position(&old.instructions, instruction.get_id()).is_none() {
+            instructions.push(InstructionChange::Added { index, instruction });
+        }
+    }
+
+    let common_old = old
+        .instructions
+        .iter()
+        .enumerate()
+        .filter(|(_, i)| position(&new.instructions, i.get_id()).is_some())
+        .map(|(index, i)| (i.get_id(), (index, i)))
+        .collect::<Vec<_>>();
+    let common_new = new
+        .instructions
+        .iter()
+        .enumerate()
+        .filter(|(_, i)| position(&old.instructions, i.get_id()).is_some())
+        .collect::<Vec<_>>();
+    let unmoved = unmoved(
+        &common_old.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+        &common_new.iter().map(|(_, i)| i.get_id()).collect::<Vec<_>>(),
+    );
+    let common_old = common_old.into_iter().collect::<HashMap<_, _>>();
+    for (to, after) in common_new {
+        let id = after.get_id();
+        let (from, before) = common_old[&id];
+        if before != after {
+            instructions.push(InstructionChange::Modified { id, before, after });
+        }
+        if !unmoved.contains(&id) {
+            instructions.push(InstructionChange::Moved { id, from, to });
+        }
+    }
+
+    RevisionDiff {
+        from: from.version,
+        to: to.version,
+        fields,
+        instructions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use endpoint::ArgType;
+
+    fn instruction(value: &str) -> Instruction {
+        Instruction::JsonReturn {
+            id: Uuid::new_v4(),
+            arg_type: ArgType::Number,
+            arg_type_value: value.to_string(),
+        }
+    }
+
+    fn revision(version: i32, instructions: Vec<Instruction>) -> Revision {
+        Revision {
+            endpoint: Uuid::nil(),
+            version,
+            author: "alice".to_string(),
+            created_at: Utc::now().naive_utc(),
+            snapshot: Endpoint {
+                id: Uuid::nil(),
+                name: "add".to_string(),
+                description: "Add two numbers".to_string(),
+                url: "/add".to_string(),
+                instructions,
+                version,
+                updated_at: None,
+                owner: None,
+            },
+        }
+    }
+
+    /// The changes as (kind, id, index before, index after)
+    fn changes(diff: &RevisionDiff) -> Vec<(&'static str, Uuid, Option<usize>, Option<usize>)> {
+        diff.instructions
+            .iter()
+            .map(|change| match change {
+                InstructionChange::Added { index, instruction } => {
+                    ("added", instruction.get_id(), None, Some(*index))
+                }
+                InstructionChange::Removed { index, instruction } => {
+                    ("removed", instruction.get_id(), Some(*index), None)
+                }
+                InstructionChange::Modified { id, .. } => ("modified", *id, None, None),
+                InstructionChange::Moved { id, from, to } => ("moved", *id, Some(*from), Some(*to)),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn moving_one_instruction_moves_only_it() {
+        let instructions = (0..5).map(|i| instruction(&i.to_string())).collect::<Vec<_>>();
+        let ids = instructions.iter().map(|i| i.get_id()).collect::<Vec<_>>();
+        let from = revision(1, instructions.clone());
+        let mut moved = instructions.clone();
+        let first = moved.remove(0);
+        moved.insert(3, first);
+        let to = revision(2, moved);
+
+        let diff = diff(&from, &to);
+        assert!(diff.fields.is_empty());
+        assert_eq!(vec![("moved", ids[0], Some(0), Some(3))], changes(&diff));
+        assert_eq!(
+            vec![("moved", ids[0], Some(3), Some(0))],
+            changes(&super::diff(&to, &from))
+        );
+        assert!(super::diff(&from, &from).instructions.is_empty());
+    }
+
+    #[test]
+    fn added_removed_and_modified_instructions_are_not_moves() {
+        let instructions = (0..3).map(|i| instruction(&i.to_string())).collect::<Vec<_>>();
+        let ids = instructions.iter().map(|i| i.get_id()).collect::<Vec<_>>();
+        let from = revision(1, instructions.clone());
+        let added = instruction("3");
+        let mut modified = instructions[2].clone();
+        if let Instruction::JsonReturn {
+            ref mut arg_type_value,
+            ..
+        } = modified
+        {
+            *arg_type_value = "4".to_string();
+        }
+        let mut to = revision(2, vec![added.clone(), instructions[1].clone(), modified]);
+        to.snapshot.url = "/sum".to_string();
+
+        let diff = diff(&from, &to);
+        assert_eq!(1, diff.fields.len());
+        let field = &diff.fields[0];
+        assert_eq!(("url", "/add", "/sum"), (field.field, field.before, field.after));
+        assert_eq!(
+            vec![
+                ("removed", ids[0], Some(0), None),
+                ("added", added.get_id(), None, Some(0)),
+                ("modified", ids[2], None, None),
+            ],
+            changes(&diff)
+        );
+    }
+
+    #[test]
+    fn swapped_instructions_move_one_of_them() {
+        let instructions = (0..2).map(|i| instruction(&i.to_string())).collect::<Vec<_>>();
+        let from = revision(1, instructions.clone());
+        let to = revision(2, vec![instructions[1].clone(), instructions[0].clone()]);
+        let changes = changes(&diff(&from, &to));
+        assert_eq!(1, changes.len());
+        assert_eq!("moved", changes[0].0);
+    }
+}
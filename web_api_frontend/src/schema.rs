@@ -1,3 +1,25 @@
+table! {
+    app_user (id) {
+        id -> Uuid,
+        name -> Text,
+        password_hash -> Text,
+        role -> Text,
+    }
+}
+
+table! {
+    audit (id) {
+        id -> Int8,
+        created_at -> Timestamp,
+        actor -> Text,
+        action -> Text,
+        endpoint -> Nullable<Uuid>,
+        config -> Nullable<Uuid>,
+        before -> Nullable<Jsonb>,
+        after -> Nullable<Jsonb>,
+    }
+}
+
 table! {
     config (id) {
         id -> Uuid,
@@ -12,57 +34,62 @@ table! {
         name -> Text,
         description -> Text,
         url -> Text,
+        version -> Int4,
+        updated_at -> Timestamp,
+        owner -> Nullable<Uuid>,
     }
 }
 
 table! {
-    instruction (id) {
+    endpoint_revision (id) {
         id -> Uuid,
         endpoint -> Uuid,
-        #[sql_name = "type"]
-        type_ -> Int2,
-        sequence -> Int4,
+        version -> Int4,
+        author -> Text,
+        created_at -> Timestamp,
+        snapshot -> Jsonb,
     }
 }
 
 table! {
-    instruction_call_module (instruction_id) {
-        instruction_id -> Uuid,
-        config -> Uuid,
-        method -> Text,
-        out_variable_name -> Text,
+    instruction (id) {
+        id -> Uuid,
+        endpoint -> Uuid,
+        sequence -> Int4,
+        payload -> Jsonb,
     }
 }
 
 table! {
-    instruction_call_module_parameter (instruction_call_module_id, sequence) {
-        instruction_call_module_id -> Uuid,
-        sequence -> Int2,
+    service (id) {
+        id -> Uuid,
         name -> Text,
-        arg_type -> Int2,
-        arg_type_value -> Text,
+        description -> Text,
+        backend -> Text,
     }
 }
 
 table! {
-    instruction_json_return (instruction_id) {
-        instruction_id -> Uuid,
-        arg_type -> Int2,
-        arg_type_value -> Text,
+    service_endpoint (service, endpoint) {
+        service -> Uuid,
+        endpoint -> Uuid,
+        prefix -> Text,
     }
 }
 
+joinable!(endpoint -> app_user (owner));
+joinable!(endpoint_revision -> endpoint (endpoint));
 joinable!(instruction -> endpoint (endpoint));
-joinable!(instruction_call_module -> config (config));
-joinable!(instruction_call_module -> instruction (instruction_id));
-joinable!(instruction_call_module_parameter -> instruction_call_module (instruction_call_module_id));
-joinable!(instruction_json_return -> instruction (instruction_id));
+joinable!(service_endpoint -> endpoint (endpoint));
+joinable!(service_endpoint -> service (service));
 
 allow_tables_to_appear_in_same_query!(
+    app_user,
+    audit,
     config,
     endpoint,
+    endpoint_revision,
     instruction,
-    instruction_call_module,
-    instruction_call_module_parameter,
-    instruction_json_return,
+    service,
+    service_endpoint,
 );
@@ -0,0 +1,94 @@
+use failure::Error;
+use itertools::Itertools;
+use models::{Service as DBService, ServiceEndpoint as DBServiceEndpoint};
+use std::collections::HashMap;
+use store::Store;
+use uuid::Uuid;
+use validation;
+use web_api_generator;
+
+/// A group of endpoints that are generated into a single crate
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Service {
+    pub id: Uuid,
+    pub name: String,
+    pub description: String,
+    /// The name of the `web_api_generator::Backend` this service is generated for
+    #[serde(default = "default_backend")]
+    pub backend: String,
+    pub endpoints: Vec<ServiceEndpoint>,
+}
+
+fn default_backend() -> String {
+    "actix".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceEndpoint {
+    pub endpoint: Uuid,
+    /// The url prefix of the scope this endpoint is registered in, e.g. `/api/users`
+    pub prefix: String,
+}
+
+impl From<(DBService, Vec<DBServiceEndpoint>)> for Service {
+    fn from((service, endpoints): (DBService, Vec<DBServiceEndpoint>)) -> Service {
+        Service {
+            id: service.id,
+            name: service.name,
+            description: service.description,
+            backend: service.backend,
+            endpoints: endpoints
+                .into_iter()
+                .map(|e| ServiceEndpoint {
+                    endpoint: e.endpoint,
+                    prefix: e.prefix,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Service {
+    /// Generate a single crate exposing all endpoints of this service, grouped in scopes by their prefix
+    pub fn generate(&self, store: &dyn Store) -> Result<HashMap<String, String>, Error> {
+        let mut endpoints = Vec::with_capacity(self.endpoints.len());
+        for service_endpoint in &self.endpoints {
+            let endpoint = store
+                .endpoint(service_endpoint.endpoint)?
+                .ok_or_else(|| format_err!("Endpoint {} not found", service_endpoint.endpoint))?;
+            endpoints.push((service_endpoint.prefix.as_str(), endpoint));
+        }
+
+        let config_ids = endpoints
+            .iter()
+            .flat_map(|(_, e)| e.config_ids())
+            .unique()
+            .collect::<Vec<_>>();
+        let configs = store.module_configs(&config_ids)?;
+        for (_, endpoint) in &endpoints {
+            if let Some(broken) = validation::check(endpoint, &configs) {
+                return Err(broken.into());
+            }
+        }
+
+        let backend = web_api_generator::backend_by_name(&self.backend).ok_or_else(|| {
+            format_err!(
+                "Unknown backend {:?}, expected one of {:?}",
+                self.backend,
+                web_api_generator::backend_names()
+            )
+        })?;
+
+        let mut builder = web_api_generator::Builder::default();
+        builder.set_backend(backend);
+        for config in &configs {
+            builder.add_module(config.1.clone());
+        }
+        for (prefix, endpoint) in &endpoints {
+            let mut scope = web_api_generator::Scope::new(*prefix);
+            scope.add_endpoint(endpoint.to_generator(&configs));
+            builder.add_scope(scope);
+        }
+        Ok(builder.build()?)
+    }
+}
@@ -0,0 +1,543 @@
+//! Settings of the server, read from a TOML file, the environment and the command line flags,
+//! where each overrides the one before
+
+use cookie::Key;
+use std::error::Error as StdError;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::net::ToSocketAddrs;
+use std::path::{Path, PathBuf};
+use toml;
+
+pub const USAGE: &str = "Usage: web_api_frontend [options]
+
+Options:
+    --config <file>         The TOML file to read the settings from, defaults to
+                            CONFIG_FILE or web_api_frontend.toml if it exists
+    --database-url <url>    postgres://..., sqlite://<path> or memory:
+    --bind <address>        The address to listen on, defaults to 127.0.0.1:8000
+    --static-root <dir>     The directory with index.html and dist, defaults to frontend
+    --modules-dir <dir>     The node_modules served at /node_modules, defaults to
+                            <static root>/node_modules
+    --pool-size <n>         The number of Postgres connections, defaults to 10
+    --output-dir <dir>      Keep the crates generated for builds in this directory,
+                            instead of in a temporary directory
+    --log-level <filter>    e.g. warn or warn,actix_web=info, defaults to info
+
+Every option can also be set in the config file, e.g. `pool_size = 4`, or in the environment,
+e.g. POOL_SIZE=4. Flags override the environment, which overrides the config file.
+Relative paths in the config file are relative to the directory of the config file.
+SESSION_KEY, which needs at least 32 bytes, and ADMIN_PASSWORD are only read from the environment,
+which includes .env.";
+
+const DEFAULT_FILE: &str = "web_api_frontend.toml";
+
+/// Each setting with the environment variable and the flag that set it
+const SETTINGS: &[(&str, &str, &str)] = &[
+    ("database_url", "DATABASE_URL", "--database-url"),
+    ("bind", "BIND_ADDRESS", "--bind"),
+    ("static_root", "STATIC_ROOT", "--static-root"),
+    ("modules_dir", "MODULES_DIR", "--modules-dir"),
+    ("pool_size", "POOL_SIZE", "--pool-size"),
+    ("output_dir", "OUTPUT_DIR", "--output-dir"),
+    ("log_level", "LOG_LEVEL", "--log-level"),
+];
+
+const LOG_LEVELS: &[&str] = &["off", "error", "warn", "info", "debug", "trace"];
+
+/// The minimum length of `SESSION_KEY`, below which `Key::from_master` panics
+const MIN_KEY_LENGTH: usize = 32;
+
+#[derive(Clone)]
+pub struct Settings {
+    /// See `store::open`
+    pub database_url: String,
+    pub bind: String,
+    /// Contains `index.html` and the `dist` directory
+    pub static_root: PathBuf,
+    /// The `node_modules` directory of the frontend
+    pub modules_dir: PathBuf,
+    /// The maximum number of connections to Postgres
+    pub pool_size: u32,
+    /// Where the crates of builds are kept, `None` to use temporary directories
+    pub output_dir: Option<PathBuf>,
+    /// An `env_logger` filter
+    pub log_level: String,
+    /// Encrypts the session cookies, see `auth`. Changing it ends every session.
+    pub session_key: Key,
+}
+
+/// Leaves out the session key, so it cannot end up in a log
+impl fmt::Debug for Settings {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Settings")
+            .field("database_url", &self.database_url)
+            .field("bind", &self.bind)
+            .field("static_root", &self.static_root)
+            .field("modules_dir", &self.modules_dir)
+            .field("pool_size", &self.pool_size)
+            .field("output_dir", &self.output_dir)
+            .field("log_level", &self.log_level)
+            .finish()
+    }
+}
+
+impl Settings {
+    /// Load and validate the settings, given the command line arguments without the program name
+    /// and a function that looks up environment variables
+    pub fn load<F>(args: &[String], var: F) -> Result<Settings, SettingsError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let (file, flags) = Layer::from_args(args)?;
+        let default = Path::new(DEFAULT_FILE);
+        let file = match file.or_else(|| var("CONFIG_FILE").map(PathBuf::from)) {
+            Some(path) => Layer::from_file(&path)?,
+            None if default.is_file() => Layer::from_file(default)?,
+            None => Layer::default(),
+        };
+        let session_key = session_key(&var)?;
+        file.merge(Layer::from_env(var)?)
+            .merge(flags)
+            .validate(session_key)
+    }
+}
+
+/// The session key from `SESSION_KEY`, which is only read from the environment.
+/// `main` loads `.env` into the environment first, so it is usually set there.
+fn session_key<F: Fn(&str) -> Option<String>>(var: F) -> Result<Key, SettingsError> {
+    let secret = var("SESSION_KEY")
+        .filter(|secret| !secret.is_empty())
+        .ok_or(SettingsError::Missing("session_key"))?;
+    if secret.len() < MIN_KEY_LENGTH {
+        return Err(SettingsError::Invalid(
+            "session_key",
+            format!(
+                "needs at least {} bytes, but has {}",
+                MIN_KEY_LENGTH,
+                secret.len()
+            ),
+        ));
+    }
+    Ok(Key::from_master(secret.as_bytes()))
+}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    /// The config file could not be read
+    Read(PathBuf, io::Error),
+    /// The config file is not valid TOML, or has an unknown setting or a value of the wrong type
+    Parse(PathBuf, toml::de::Error),
+    /// The command line has an unknown flag, or a flag without its value
+    Usage(String),
+    /// A required setting is not set
+    Missing(&'static str),
+    /// A setting has an invalid value
+    Invalid(&'static str, String),
+}
+
+/// How to change a setting, e.g. "bind in the config file, BIND_ADDRESS or --bind"
+fn sources(setting: &str) -> String {
+    match SETTINGS.iter().find(|&&(name, _, _)| name == setting) {
+        Some(&(_, var, flag)) => format!("{} in the config file, {} or {}", setting, var, flag),
+        // Secrets like the session key are only read from the environment
+        None => format!("{} in the environment or .env", setting.to_uppercase()),
+    }
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SettingsError::Read(path, e) => {
+                write!(fmt, "Could not read config file {}: {}", path.display(), e)
+            }
+            SettingsError::Parse(path, e) => {
+                write!(fmt, "Invalid config file {}: {}", path.display(), e)
+            }
+            SettingsError::Usage(message) => write!(fmt, "{}", message),
+            SettingsError::Missing(setting) => {
+                write!(fmt, "Missing {}, set {}", setting, sources(setting))
+            }
+            SettingsError::Invalid(setting, message) => write!(
+                fmt,
+                "Invalid {}: {} (set by {})",
+                setting,
+                message,
+                sources(setting)
+            ),
+        }
+    }
+}
+
+impl StdError for SettingsError {
+    fn description(&self) -> &str {
+        match self {
+            SettingsError::Read(..) => "Could not read config file",
+            SettingsError::Parse(..) => "Invalid config file",
+            SettingsError::Usage(_) => "Invalid arguments",
+            SettingsError::Missing(_) => "Missing setting",
+            SettingsError::Invalid(..) => "Invalid setting",
+        }
+    }
+}
+
+/// The settings from one source, which do not all have to be set
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Layer {
+    database_url: Option<String>,
+    bind: Option<String>,
+    static_root: Option<PathBuf>,
+    modules_dir: Option<PathBuf>,
+    pool_size: Option<u32>,
+    output_dir: Option<PathBuf>,
+    log_level: Option<String>,
+}
+
+impl Layer {
+    fn from_file(path: &Path) -> Result<Layer, SettingsError> {
+        let mut contents = String::new();
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut contents))
+            .map_err(|e| SettingsError::Read(path.to_owned(), e))?;
+        let layer: Layer =
+            toml::from_str(&contents).map_err(|e| SettingsError::Parse(path.to_owned(), e))?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let relative = |path: Option<PathBuf>| path.map(|path| dir.join(path));
+        Ok(Layer {
+            static_root: relative(layer.static_root),
+            modules_dir: relative(layer.modules_dir),
+            output_dir: relative(layer.output_dir),
+            ..layer
+        })
+    }
+
+    fn from_env<F: Fn(&str) -> Option<String>>(var: F) -> Result<Layer, SettingsError> {
+        let mut layer = Layer::default();
+        for &(setting, name, _) in SETTINGS {
+            if let Some(value) = var(name) {
+                layer.set(setting, value)?;
+            }
+        }
+        Ok(layer)
+    }
+
+    /// The settings given as flags, and the config file given with `--config`
+    fn from_args(args: &[String]) -> Result<(Option<PathBuf>, Layer), SettingsError> {
+        let mut layer = Layer::default();
+        let mut file = None;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let value = args
+                .next()
+                .cloned()
+                .ok_or_else(|| SettingsError::Usage(format!("Missing value for {}", arg)));
+            if arg == "--config" {
+                file = Some(PathBuf::from(value?));
+                continue;
+            }
+            match SETTINGS.iter().find(|&&(_, _, flag)| flag == arg) {
+                Some(&(setting, _, _)) => layer.set(setting, value?)?,
+                None if arg.starts_with("--") => {
+                    return Err(SettingsError::Usage(format!("Unknown option {}", arg)))
+                }
+                None => {
+                    return Err(SettingsError::Usage(format!(
+                        "Unexpected argument {:?}",
+                        arg
+                    )))
+                }
+            }
+        }
+        Ok((file, layer))
+    }
+
+    fn set(&mut self, setting: &'static str, value: String) -> Result<(), SettingsError> {
+        match setting {
+            "database_url" => self.database_url = Some(value),
+            "bind" => self.bind = Some(value),
+            "static_root" => self.static_root = Some(PathBuf::from(value)),
+            "modules_dir" => self.modules_dir = Some(PathBuf::from(value)),
+            "pool_size" => {
+                let size = value.parse().map_err(|_| {
+                    SettingsError::Invalid(setting, format!("{:?} is not a number", value))
+                })?;
+                self.pool_size = Some(size);
+            }
+            "output_dir" => self.output_dir = Some(PathBuf::from(value)),
+            "log_level" => self.log_level = Some(value),
+            _ => unreachable!("Unknown setting {}", setting),
+        }
+        Ok(())
+    }
+
+    /// Use the settings of `other` where they are set
+    fn merge(self, other: Layer) -> Layer {
+        Layer {
+            database_url: other.database_url.or(self.database_url),
+            bind: other.bind.or(self.bind),
+            static_root: other.static_root.or(self.static_root),
+            modules_dir: other.modules_dir.or(self.modules_dir),
+            pool_size: other.pool_size.or(self.pool_size),
+            output_dir: other.output_dir.or(self.output_dir),
+            log_level: other.log_level.or(self.log_level),
+        }
+    }
+
+    /// Fill in the defaults and check the settings. Creates the output dir if it does not exist.
+    fn validate(self, session_key: Key) -> Result<Settings, SettingsError> {
+        let database_url = self
+            .database_url
+            .filter(|url| !url.is_empty())
+            .ok_or(SettingsError::Missing("database_url"))?;
+
+        let bind = self.bind.unwrap_or_else(|| "127.0.0.1:8000".to_string());
+        if let Err(e) = bind.to_socket_addrs() {
+            return Err(SettingsError::Invalid(
+                "bind",
+                format!("{:?} is not an address like 127.0.0.1:8000: {}", bind, e),
+            ));
+        }
+
+        let static_root = self
+            .static_root
+            .unwrap_or_else(|| PathBuf::from("frontend"));
+        if !static_root.join("index.html").is_file() {
+            return Err(SettingsError::Invalid(
+                "static_root",
+                format!("{} does not contain index.html", static_root.display()),
+            ));
+        }
+
+        let modules_dir = self
+            .modules_dir
+            .unwrap_or_else(|| static_root.join("node_modules"));
+        if !modules_dir.is_dir() {
+            return Err(SettingsError::Invalid(
+                "modules_dir",
+                format!(
+                    "{} is not a directory, run `npm install` in the frontend",
+                    modules_dir.display()
+                ),
+            ));
+        }
+
+        let pool_size = self.pool_size.unwrap_or(10);
+        if pool_size == 0 {
+            return Err(SettingsError::Invalid(
+                "pool_size",
+                "needs at least 1 connection".to_string(),
+            ));
+        }
+
+        if let Some(dir) = &self.output_dir {
+            fs::create_dir_all(dir).map_err(|e| {
+                SettingsError::Invalid(
+                    "output_dir",
+                    format!("could not create {}: {}", dir.display(), e),
+                )
+            })?;
+        }
+
+        let log_level = self.log_level.unwrap_or_else(|| "info".to_string());
+        check_log_level(&log_level).map_err(|e| SettingsError::Invalid("log_level", e))?;
+
+        Ok(Settings {
+            database_url,
+            bind,
+            static_root,
+            modules_dir,
+            pool_size,
+            output_dir: self.output_dir,
+            log_level,
+            session_key,
+        })
+    }
+}
+
+/// Check the levels of an `env_logger` filter. Its comma separated directives are a level,
+/// a module that logs at every level, or `module=level`, optionally followed by `/<regex>`.
+fn check_log_level(filter: &str) -> Result<(), String> {
+    let directives = filter.split('/').next().unwrap_or("");
+    for directive in directives.split(',') {
+        let level = match directive.split_once('=') {
+            Some((_, level)) => level.trim(),
+            // A level, or a module name
+            None => continue,
+        };
+        if !LOG_LEVELS.contains(&level.to_lowercase().as_str()) {
+            return Err(format!(
+                "{:?} is not a level, expected one of {}",
+                level,
+                LOG_LEVELS.join(", ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::env;
+    use uuid::Uuid;
+
+    const KEY: &str = "0123456789abcdef0123456789abcdef";
+
+    /// A new directory with the `index.html` and `node_modules` the defaults point to
+    fn static_root() -> PathBuf {
+        let dir = env::temp_dir().join(format!("web_api_frontend_settings_{}", Uuid::new_v4()));
+        fs::create_dir_all(dir.join("node_modules")).unwrap();
+        fs::write(dir.join("index.html"), "").unwrap();
+        dir
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    /// An environment with only the given variables and a valid `SESSION_KEY`, unless it is given
+    fn vars(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let mut vars = vars
+            .iter()
+            .map(|&(name, value)| (name.to_string(), value.to_string()))
+            .collect::<HashMap<_, _>>();
+        vars.entry("SESSION_KEY".to_string())
+            .or_insert_with(|| KEY.to_string());
+        move |name| vars.get(name).cloned()
+    }
+
+    fn load(flags: &[&str], env: &[(&str, &str)]) -> Result<Settings, SettingsError> {
+        Settings::load(&args(flags), vars(env))
+    }
+
+    #[test]
+    fn flags_override_the_environment_which_overrides_the_file() {
+        let root = static_root();
+        let file = root.join("settings.toml");
+        let toml = "database_url = \"memory:\"\nbind = \"127.0.0.1:9000\"\n\
+                    static_root = \".\"\npool_size = 2\nlog_level = \"warn\"\n";
+        fs::write(&file, toml).unwrap();
+        let settings = load(
+            &["--config", file.to_str().unwrap(), "--log-level", "debug"],
+            &[("POOL_SIZE", "3"), ("LOG_LEVEL", "error")],
+        )
+        .unwrap();
+        assert_eq!("memory:", settings.database_url);
+        assert_eq!("127.0.0.1:9000", settings.bind);
+        // Relative to the directory of the config file
+        assert_eq!(root.join("."), settings.static_root);
+        assert_eq!(root.join("./node_modules"), settings.modules_dir);
+        assert_eq!(3, settings.pool_size);
+        assert_eq!("debug", settings.log_level);
+        assert_eq!(None, settings.output_dir);
+
+        let config_file = file.to_str().unwrap();
+        let settings = load(
+            &[],
+            &[("CONFIG_FILE", config_file), ("BIND_ADDRESS", "[::1]:80")],
+        );
+        assert_eq!("[::1]:80", settings.unwrap().bind);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn defaults_fill_in_what_is_not_set() {
+        let root = static_root();
+        let output_dir = root.join("builds");
+        let settings = load(
+            &["--static-root", root.to_str().unwrap()],
+            &[
+                ("DATABASE_URL", "memory:"),
+                ("OUTPUT_DIR", output_dir.to_str().unwrap()),
+            ],
+        )
+        .unwrap();
+        assert_eq!("127.0.0.1:8000", settings.bind);
+        assert_eq!(root.join("node_modules"), settings.modules_dir);
+        assert_eq!(10, settings.pool_size);
+        assert_eq!("info", settings.log_level);
+        assert!(output_dir.is_dir());
+        assert!(!format!("{:?}", settings).contains("session_key"));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn session_keys_need_32_bytes() {
+        let root = static_root();
+        let flags = [
+            "--database-url",
+            "memory:",
+            "--static-root",
+            root.to_str().unwrap(),
+        ];
+        assert!(load(&flags, &[("SESSION_KEY", KEY)]).is_ok());
+        match load(&flags, &[("SESSION_KEY", &KEY[1..])]) {
+            Err(SettingsError::Invalid("session_key", message)) => {
+                assert_eq!("needs at least 32 bytes, but has 31", message)
+            }
+            result => panic!("Expected an invalid session key, got {:?}", result),
+        }
+        match load(&flags, &[("SESSION_KEY", "")]) {
+            Err(error @ SettingsError::Missing("session_key")) => assert_eq!(
+                "Missing session_key, set SESSION_KEY in the environment or .env",
+                error.to_string()
+            ),
+            result => panic!("Expected a missing session key, got {:?}", result),
+        }
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn arguments_are_flags_with_values() {
+        let usage = |flags: &[&str]| match load(flags, &[]) {
+            Err(SettingsError::Usage(message)) => message,
+            result => panic!("Expected a usage error, got {:?}", result),
+        };
+        assert_eq!("Unknown option --port", usage(&["--port", "80"]));
+        assert_eq!("Missing value for --bind", usage(&["--bind"]));
+        assert_eq!("Unexpected argument \"memory:\"", usage(&["memory:"]));
+        match load(&["--pool-size", "many"], &[]) {
+            Err(SettingsError::Invalid("pool_size", _)) => {}
+            result => panic!("Expected an invalid pool size, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn log_levels_are_env_logger_filters() {
+        let filters = [
+            "info",
+            "WARN",
+            "actix_web",
+            "web_api_frontend,actix_web=warn",
+            "warn,web_api_frontend::api=debug",
+            "info/GET",
+            "warn,",
+        ];
+        for filter in &filters {
+            assert_eq!(Ok(()), check_log_level(filter), "{}", filter);
+        }
+        for filter in &["actix_web=loud", "info,actix_web=", "=warn=x"] {
+            assert!(check_log_level(filter).is_err(), "{}", filter);
+        }
+
+        let root = static_root();
+        let flags = [
+            "--static-root",
+            root.to_str().unwrap(),
+            "--log-level",
+            "actix_web=loud",
+        ];
+        match load(&flags, &[("DATABASE_URL", "memory:")]) {
+            Err(error @ SettingsError::Invalid("log_level", _)) => assert_eq!(
+                "Invalid log_level: \"loud\" is not a level, expected one of off, error, warn, \
+                 info, debug, trace (set by log_level in the config file, LOG_LEVEL or --log-level)",
+                error.to_string()
+            ),
+            result => panic!("Expected an invalid log level, got {:?}", result),
+        }
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,338 @@
+use audit::{AuditEntry, AuditFilter, Change};
+use super::{check_owner, Store, StoreError, StoreResult};
+use chrono::Utc;
+use endpoint::{Config, Endpoint, SaveError};
+use failure::Error;
+use models::Config as DbConfig;
+use revision::{Revision, RevisionSummary};
+use service::Service;
+use std::cmp::Reverse;
+use std::sync::{Mutex, MutexGuard};
+use user::User;
+use uuid::Uuid;
+
+/// A store that keeps everything in memory, for tests and for trying out the frontend.
+/// It enforces the same unique names as the Postgres schema.
+#[derive(Default)]
+pub struct MemoryStore {
+    data: Mutex<Data>,
+}
+
+#[derive(Clone, Default)]
+struct Data {
+    configs: Vec<DbConfig>,
+    endpoints: Vec<Endpoint>,
+    revisions: Vec<Revision>,
+    services: Vec<Service>,
+    users: Vec<User>,
+    audit: Vec<AuditEntry>,
+}
+
+impl MemoryStore {
+    fn lock(&self) -> StoreResult<MutexGuard<'_, Data>> {
+        self.data
+            .lock()
+            .map_err(|_| StoreError::Internal("The store was poisoned by a panic".to_string()))
+    }
+}
+
+fn unique<'a, I>(items: I, id: Uuid, field: &str, value: &str) -> StoreResult<()>
+where
+    I: IntoIterator<Item = (Uuid, &'a str)>,
+{
+    if items.into_iter().any(|(other, v)| other != id && v == value) {
+        return Err(StoreError::Conflict(format!(
+            "The {} {:?} is already in use",
+            field, value
+        )));
+    }
+    Ok(())
+}
+
+impl Data {
+    fn record(&mut self, change: Change) {
+        let id = self.audit.len() as i64 + 1;
+        self.audit.push(AuditEntry::new(id, Utc::now().naive_utc(), change));
+    }
+
+    fn save_endpoint(&mut self, endpoint: &mut Endpoint, author: &User) -> Result<(), SaveError> {
+        let index = self.endpoints.iter().position(|e| e.id == endpoint.id);
+        let before = index.map(|i| self.endpoints[i].clone());
+        check_owner(author, endpoint.id, before.as_ref().map(|e| e.owner))?;
+        let version = match index {
+            Some(index) if self.endpoints[index].version != endpoint.version => {
+                return Err(SaveError::Conflict {
+                    id: endpoint.id,
+                    expected: endpoint.version,
+                    actual: self.endpoints[index].version,
+                })
+            }
+            Some(index) => self.endpoints[index].version + 1,
+            None => 1,
+        };
+        let others = || self.endpoints.iter().filter(|e| e.id != endpoint.id);
+        unique(others().map(|e| (e.id, &e.name[..])), endpoint.id, "name", &endpoint.name)?;
+        unique(
+            others().map(|e| (e.id, &e.description[..])),
+            endpoint.id,
+            "description",
+            &endpoint.description,
+        )?;
+        unique(others().map(|e| (e.id, &e.url[..])), endpoint.id, "url", &endpoint.url)?;
+
+        let now = Utc::now().naive_utc();
+        endpoint.version = version;
+        endpoint.updated_at = Some(now);
+        endpoint.owner = match index {
+            Some(index) => self.endpoints[index].owner,
+            None => Some(author.id),
+        };
+        match index {
+            Some(index) => self.endpoints[index] = endpoint.clone(),
+            None => self.endpoints.push(endpoint.clone()),
+        }
+        self.revisions.push(Revision {
+            endpoint: endpoint.id,
+            version,
+            author: author.name.clone(),
+            created_at: now,
+            snapshot: endpoint.clone(),
+        });
+        self.record(Change::endpoint(author, before.as_ref(), Some(&*endpoint)));
+        Ok(())
+    }
+}
+
+impl Store for MemoryStore {
+    fn configs(&self) -> StoreResult<Vec<Config>> {
+        // Loading the module.json files happens without the lock
+        let rows = self.lock()?.configs.clone();
+        let mut configs = rows
+            .into_iter()
+            .map(Config::load)
+            .collect::<StoreResult<Vec<_>>>()?;
+        configs.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(configs)
+    }
+
+    fn config(&self, id: Uuid) -> StoreResult<Option<Config>> {
+        let row = self.lock()?.configs.iter().find(|c| c.id == id).cloned();
+        row.map(Config::load).transpose()
+    }
+
+    fn config_by_name(&self, name: &str) -> StoreResult<Option<Config>> {
+        let row = self.lock()?.configs.iter().find(|c| c.name == name).cloned();
+        row.map(Config::load).transpose()
+    }
+
+    fn save_config(&self, config: &Config, actor: &User) -> StoreResult<()> {
+        let mut data = self.lock()?;
+        unique(
+            data.configs.iter().map(|c| (c.id, &c.name[..])),
+            config.id,
+            "name",
+            &config.name,
+        )?;
+        let row = DbConfig {
+            id: config.id,
+            name: config.name.clone(),
+            path: config.path.clone(),
+        };
+        let before = match data.configs.iter().position(|c| c.id == config.id) {
+            Some(index) => Some(::std::mem::replace(&mut data.configs[index], row.clone())),
+            None => {
+                data.configs.push(row.clone());
+                None
+            }
+        };
+        data.record(Change::config(actor, before.as_ref(), Some(&row)));
+        Ok(())
+    }
+
+    fn delete_config(&self, id: Uuid, actor: &User) -> StoreResult<bool> {
+        let mut data = self.lock()?;
+        if let Some(endpoint) = data.endpoints.iter().find(|e| e.config_ids().contains(&id)) {
+            return Err(StoreError::Conflict(format!(
+                "Config {} is still called by endpoint {:?}",
+                id, endpoint.name
+            )));
+        }
+        match data.configs.iter().position(|c| c.id == id) {
+            Some(index) => {
+                let before = data.configs.remove(index);
+                data.record(Change::config(actor, Some(&before), None));
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn endpoints(&self) -> StoreResult<Vec<Endpoint>> {
+        let mut endpoints = self.lock()?.endpoints.clone();
+        endpoints.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(endpoints)
+    }
+
+    fn endpoint(&self, id: Uuid) -> StoreResult<Option<Endpoint>> {
+        Ok(self.lock()?.endpoints.iter().find(|e| e.id == id).cloned())
+    }
+
+    fn endpoint_by_name(&self, name: &str) -> StoreResult<Option<Endpoint>> {
+        Ok(self.lock()?.endpoints.iter().find(|e| e.name == name).cloned())
+    }
+
+    fn save_endpoint(&self, endpoint: &mut Endpoint, author: &User) -> Result<(), SaveError> {
+        self.lock()?.save_endpoint(endpoint, author)
+    }
+
+    /// Also removes the endpoint from its services and its revisions, like the cascades in Postgres
+    fn delete_endpoint(&self, id: Uuid, actor: &User) -> StoreResult<bool> {
+        let mut data = self.lock()?;
+        let before = match data.endpoints.iter().position(|e| e.id == id) {
+            Some(index) => data.endpoints.remove(index),
+            None => return Ok(false),
+        };
+        data.revisions.retain(|r| r.endpoint != id);
+        for service in &mut data.services {
+            service.endpoints.retain(|e| e.endpoint != id);
+        }
+        data.record(Change::endpoint(actor, Some(&before), None));
+        Ok(true)
+    }
+
+    fn revisions(&self, endpoint: Uuid) -> StoreResult<Vec<RevisionSummary>> {
+        let data = self.lock()?;
+        let mut revisions = data
+            .revisions
+            .iter()
+            .filter(|r| r.endpoint == endpoint)
+            .map(|r| RevisionSummary {
+                version: r.version,
+                author: r.author.clone(),
+                created_at: r.created_at,
+            })
+            .collect::<Vec<_>>();
+        revisions.sort_by_key(|r| Reverse(r.version));
+        Ok(revisions)
+    }
+
+    fn revision(&self, endpoint: Uuid, version: i32) -> StoreResult<Option<Revision>> {
+        Ok(self
+            .lock()?
+            .revisions
+            .iter()
+            .find(|r| r.endpoint == endpoint && r.version == version)
+            .cloned())
+    }
+
+    fn users(&self) -> StoreResult<Vec<User>> {
+        let mut users = self.lock()?.users.clone();
+        users.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(users)
+    }
+
+    fn user(&self, id: Uuid) -> StoreResult<Option<User>> {
+        Ok(self.lock()?.users.iter().find(|u| u.id == id).cloned())
+    }
+
+    fn user_by_name(&self, name: &str) -> StoreResult<Option<User>> {
+        Ok(self.lock()?.users.iter().find(|u| u.name == name).cloned())
+    }
+
+    fn save_user(&self, user: &User) -> StoreResult<()> {
+        let mut data = self.lock()?;
+        unique(
+            data.users.iter().map(|u| (u.id, &u.name[..])),
+            user.id,
+            "name",
+            &user.name,
+        )?;
+        match data.users.iter().position(|u| u.id == user.id) {
+            Some(index) => data.users[index] = user.clone(),
+            None => data.users.push(user.clone()),
+        }
+        Ok(())
+    }
+
+    fn set_endpoint_owner(
+        &self,
+        endpoint: Uuid,
+        owner: Option<Uuid>,
+        actor: &User,
+    ) -> StoreResult<bool> {
+        let mut data = self.lock()?;
+        if let Some(owner) = owner {
+            if !data.users.iter().any(|u| u.id == owner) {
+                return Err(StoreError::Conflict(format!("User {} does not exist", owner)));
+            }
+        }
+        let before = match data.endpoints.iter_mut().find(|e| e.id == endpoint) {
+            Some(endpoint) => {
+                let before = endpoint.clone();
+                endpoint.owner = owner;
+                before
+            }
+            None => return Ok(false),
+        };
+        let after = Endpoint { owner, ..before.clone() };
+        data.record(Change::endpoint(actor, Some(&before), Some(&after)));
+        Ok(true)
+    }
+
+    fn audit_log(&self, filter: &AuditFilter) -> StoreResult<Vec<AuditEntry>> {
+        Ok(self
+            .lock()?
+            .audit
+            .iter()
+            .rev()
+            .filter(|e| filter.matches(e))
+            .cloned()
+            .collect())
+    }
+
+    fn services(&self) -> StoreResult<Vec<Service>> {
+        let mut services = self.lock()?.services.clone();
+        services.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(services)
+    }
+
+    fn service(&self, id: Uuid) -> StoreResult<Option<Service>> {
+        Ok(self.lock()?.services.iter().find(|s| s.id == id).cloned())
+    }
+
+    fn save_service(&self, service: &Service) -> StoreResult<()> {
+        let mut data = self.lock()?;
+        unique(
+            data.services.iter().map(|s| (s.id, &s.name[..])),
+            service.id,
+            "name",
+            &service.name,
+        )?;
+        if let Some(missing) = service
+            .endpoints
+            .iter()
+            .find(|s| !data.endpoints.iter().any(|e| e.id == s.endpoint))
+        {
+            return Err(StoreError::Conflict(format!(
+                "Endpoint {} does not exist",
+                missing.endpoint
+            )));
+        }
+        match data.services.iter().position(|s| s.id == service.id) {
+            Some(index) => data.services[index] = service.clone(),
+            None => data.services.push(service.clone()),
+        }
+        Ok(())
+    }
+
+    /// Runs `f` against a copy of the data, which replaces the data only if `f` succeeds
+    fn transaction(&self, f: &mut dyn FnMut(&dyn Store) -> Result<(), Error>) -> Result<(), Error> {
+        let mut data = self.lock()?;
+        let copy = MemoryStore {
+            data: Mutex::new(data.clone()),
+        };
+        f(&copy)?;
+        *data = copy.lock()?.clone();
+        Ok(())
+    }
+}
@@ -0,0 +1,397 @@
+mod memory;
+mod postgres;
+mod sqlite;
+
+pub use self::memory::MemoryStore;
+pub use self::postgres::PgStore;
+pub use self::sqlite::SqliteStore;
+
+use audit::{AuditEntry, AuditFilter};
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use endpoint::{Config, Endpoint, SaveError};
+use failure::Error;
+use revision::{Revision, RevisionSummary};
+use rust_functional::Config as BaseConfig;
+use service::Service;
+use std::error::Error as StdError;
+use std::fmt;
+use std::rc::Rc;
+use std::sync::Arc;
+use user::{Role, User};
+use uuid::Uuid;
+
+pub type StoreResult<T> = Result<T, StoreError>;
+
+/// Persistence of configs, endpoints with their revisions, and services.
+/// Every change to a config or endpoint is recorded in the audit log, see `audit`.
+pub trait Store {
+    fn configs(&self) -> StoreResult<Vec<Config>>;
+    fn config(&self, id: Uuid) -> StoreResult<Option<Config>>;
+    fn config_by_name(&self, name: &str) -> StoreResult<Option<Config>>;
+    /// Insert the config, or update the config with the same id
+    fn save_config(&self, config: &Config, actor: &User) -> StoreResult<()>;
+    /// Delete the config with the given id. Returns `false` if it did not exist.
+    /// Fails with `StoreError::Conflict` if an instruction still calls the config.
+    fn delete_config(&self, id: Uuid, actor: &User) -> StoreResult<bool>;
+
+    /// Load the module configs with the given ids, to be passed to a `web_api_generator::Builder`
+    fn module_configs(&self, ids: &[Uuid]) -> StoreResult<Vec<(Uuid, Rc<BaseConfig>)>> {
+        let mut result = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(config) = self.config(*id)? {
+                result.push((config.id, Rc::new(config.config)));
+            }
+        }
+        Ok(result)
+    }
+
+    fn endpoints(&self) -> StoreResult<Vec<Endpoint>>;
+    fn endpoint(&self, id: Uuid) -> StoreResult<Option<Endpoint>>;
+    fn endpoint_by_name(&self, name: &str) -> StoreResult<Option<Endpoint>>;
+    /// Save the endpoint and its instructions atomically, increment its version
+    /// and record a new revision. A new endpoint is owned by `author`.
+    /// Fails with `SaveError::Conflict` if the stored version differs from `endpoint.version`,
+    /// and with `SaveError::Forbidden` if `author` may not save it, see `check_owner`.
+    fn save_endpoint(&self, endpoint: &mut Endpoint, author: &User) -> Result<(), SaveError>;
+    /// Delete the endpoint with the given id. Returns `false` if it did not exist.
+    fn delete_endpoint(&self, id: Uuid, actor: &User) -> StoreResult<bool>;
+
+    /// List the revisions of an endpoint, newest first
+    fn revisions(&self, endpoint: Uuid) -> StoreResult<Vec<RevisionSummary>>;
+    fn revision(&self, endpoint: Uuid, version: i32) -> StoreResult<Option<Revision>>;
+
+    fn users(&self) -> StoreResult<Vec<User>>;
+    fn user(&self, id: Uuid) -> StoreResult<Option<User>>;
+    fn user_by_name(&self, name: &str) -> StoreResult<Option<User>>;
+    /// Insert the user, or update the user with the same id
+    fn save_user(&self, user: &User) -> StoreResult<()>;
+    /// Give an endpoint to another user. Returns `false` if the endpoint does not exist.
+    fn set_endpoint_owner(
+        &self,
+        endpoint: Uuid,
+        owner: Option<Uuid>,
+        actor: &User,
+    ) -> StoreResult<bool>;
+
+    /// List the audit entries that match the filter, newest first
+    fn audit_log(&self, filter: &AuditFilter) -> StoreResult<Vec<AuditEntry>>;
+
+    fn services(&self) -> StoreResult<Vec<Service>>;
+    fn service(&self, id: Uuid) -> StoreResult<Option<Service>>;
+    fn save_service(&self, service: &Service) -> StoreResult<()>;
+
+    /// Run `f` with a store that applies all its changes at once, or none if `f` fails
+    fn transaction(&self, f: &mut dyn FnMut(&dyn Store) -> Result<(), Error>) -> Result<(), Error>;
+}
+
+/// The store used when the server is started, shared between all workers
+pub type SharedStore = Arc<dyn Store + Send + Sync>;
+
+/// Open the store for the given url:
+///
+/// - `postgres://...` uses diesel with Postgres, with the schema created by the migrations
+/// - `sqlite://<path>` uses diesel with a SQLite file, creating the tables if needed
+/// - `memory:` keeps everything in memory, and loses it when the server stops
+///
+/// `pool_size` is the maximum number of connections to Postgres.
+pub fn open(url: &str, pool_size: u32) -> StoreResult<SharedStore> {
+    if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+        Ok(Arc::new(PgStore::connect(url, pool_size)?))
+    } else if let Some(path) = url.strip_prefix("sqlite://") {
+        Ok(Arc::new(SqliteStore::open(path)?))
+    } else if url == "memory:" {
+        Ok(Arc::new(MemoryStore::default()))
+    } else {
+        Err(StoreError::Internal(format!(
+            "Unknown database url {:?}, expected postgres://, sqlite:// or memory:",
+            url
+        )))
+    }
+}
+
+/// Check that `author` may save endpoint `id`, given the owner of the stored endpoint,
+/// or `None` if it does not exist yet
+fn check_owner(author: &User, id: Uuid, stored: Option<Option<Uuid>>) -> Result<(), SaveError> {
+    let allowed = match stored {
+        Some(owner) => author.can_change(owner),
+        None => author.role >= Role::Write,
+    };
+    if allowed {
+        Ok(())
+    } else {
+        Err(SaveError::Forbidden(id))
+    }
+}
+
+#[derive(Debug)]
+pub enum StoreError {
+    /// A unique or foreign key constraint was violated, e.g. by a duplicate name
+    /// or by deleting a config that is still used
+    Conflict(String),
+    Internal(String),
+}
+
+impl From<DieselError> for StoreError {
+    fn from(e: DieselError) -> StoreError {
+        match e {
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, info)
+            | DieselError::DatabaseError(DatabaseErrorKind::ForeignKeyViolation, info) => {
+                StoreError::Conflict(info.message().to_string())
+            }
+            e => StoreError::Internal(e.to_string()),
+        }
+    }
+}
+
+impl From<::r2d2::Error> for StoreError {
+    fn from(e: ::r2d2::Error) -> StoreError {
+        StoreError::Internal(e.to_string())
+    }
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StoreError::Conflict(message) | StoreError::Internal(message) => {
+                write!(fmt, "{}", message)
+            }
+        }
+    }
+}
+
+impl StdError for StoreError {
+    fn description(&self) -> &str {
+        match self {
+            StoreError::Conflict(_) => "Conflict",
+            StoreError::Internal(_) => "Store error",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use endpoint::{ArgType, Instruction};
+    use models::Config as DbConfig;
+    use service::ServiceEndpoint;
+    use std::env;
+
+    /// An in-memory store and one with a new SQLite database, which should behave the same
+    fn stores() -> Vec<(&'static str, Box<dyn Store>)> {
+        let path = env::temp_dir().join(format!("web_api_frontend_store_{}.db", Uuid::new_v4()));
+        vec![
+            ("memory", Box::new(MemoryStore::default())),
+            (
+                "sqlite",
+                Box::new(SqliteStore::open(path.to_str().unwrap()).unwrap()),
+            ),
+        ]
+    }
+
+    fn user(store: &dyn Store, name: &str, role: Role) -> User {
+        let user = User {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            role,
+            password_hash: String::new(),
+        };
+        store.save_user(&user).unwrap();
+        user
+    }
+
+    fn endpoint(name: &str, instructions: Vec<Instruction>) -> Endpoint {
+        Endpoint {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            description: name.to_string(),
+            url: format!("/{}", name),
+            instructions,
+            version: 0,
+            updated_at: None,
+            owner: None,
+        }
+    }
+
+    #[test]
+    fn urls_choose_the_store() {
+        assert!(open("memory:", 1).is_ok());
+        let path = env::temp_dir().join(format!("web_api_frontend_open_{}.db", Uuid::new_v4()));
+        let store = open(&format!("sqlite://{}", path.display()), 1).unwrap();
+        assert!(store.endpoints().unwrap().is_empty());
+        assert!(path.is_file());
+        match open("mysql://localhost", 1) {
+            Err(StoreError::Internal(message)) => assert!(message.contains("mysql://")),
+            result => panic!("Expected an error, got {:?}", result.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn owners_check_who_saves() {
+        let (alice, bob, carol) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let user = |id, role| User {
+            id,
+            name: String::new(),
+            role,
+            password_hash: String::new(),
+        };
+        let id = Uuid::new_v4();
+        assert!(check_owner(&user(alice, Role::Write), id, None).is_ok());
+        assert!(check_owner(&user(alice, Role::Write), id, Some(Some(alice))).is_ok());
+        assert!(check_owner(&user(bob, Role::Write), id, Some(Some(alice))).is_err());
+        // Endpoints saved before there were users have no owner
+        assert!(check_owner(&user(bob, Role::Write), id, Some(None)).is_err());
+        assert!(check_owner(&user(bob, Role::Admin), id, Some(None)).is_ok());
+        match check_owner(&user(carol, Role::Read), id, None) {
+            Err(SaveError::Forbidden(forbidden)) => assert_eq!(id, forbidden),
+            result => panic!("Expected Forbidden, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn endpoints_are_versioned() {
+        for (name, store) in stores() {
+            let store = &*store;
+            let alice = user(store, "alice", Role::Write);
+            let bob = user(store, "bob", Role::Write);
+            let mut saved = endpoint("add", Vec::new());
+            let mut stale = saved.clone();
+            store.save_endpoint(&mut saved, &alice).unwrap();
+            assert_eq!(1, saved.version, "{}", name);
+            assert_eq!(Some(alice.id), saved.owner);
+            assert!(saved.updated_at.is_some());
+
+            match store.save_endpoint(&mut stale, &alice) {
+                Err(SaveError::Conflict {
+                    expected, actual, ..
+                }) => assert_eq!((0, 1), (expected, actual), "{}", name),
+                result => panic!("{}: expected a conflict, got {:?}", name, result),
+            }
+            match store.save_endpoint(&mut saved.clone(), &bob) {
+                Err(SaveError::Forbidden(_)) => {}
+                result => panic!("{}: expected Forbidden, got {:?}", name, result),
+            }
+
+            saved.description = "Add two numbers".to_string();
+            store.save_endpoint(&mut saved, &alice).unwrap();
+            let versions = store
+                .revisions(saved.id)
+                .unwrap()
+                .iter()
+                .map(|r| r.version)
+                .collect::<Vec<_>>();
+            assert_eq!(vec![2, 1], versions, "{}", name);
+            let first = store.revision(saved.id, 1).unwrap().unwrap();
+            assert_eq!("add", first.snapshot.description, "{}", name);
+            assert_eq!("alice", first.author);
+            assert!(store.revision(saved.id, 3).unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn names_are_unique() {
+        for (name, store) in stores() {
+            let store = &*store;
+            let alice = user(store, "alice", Role::Write);
+            store
+                .save_endpoint(&mut endpoint("add", Vec::new()), &alice)
+                .unwrap();
+            let mut same_name = endpoint("add", Vec::new());
+            same_name.description = "Another description".to_string();
+            same_name.url = "/another".to_string();
+            match store.save_endpoint(&mut same_name, &alice) {
+                Err(SaveError::Store(StoreError::Conflict(_))) => {}
+                result => panic!("{}: expected a conflict, got {:?}", name, result),
+            }
+            assert!(store.endpoint(same_name.id).unwrap().is_none(), "{}", name);
+
+            let duplicate = User {
+                id: Uuid::new_v4(),
+                ..alice.clone()
+            };
+            match store.save_user(&duplicate) {
+                Err(StoreError::Conflict(_)) => {}
+                result => panic!("{}: expected a conflict, got {:?}", name, result),
+            }
+        }
+    }
+
+    #[test]
+    fn deleting_cascades() {
+        for (name, store) in stores() {
+            let store = &*store;
+            let alice = user(store, "alice", Role::Write);
+            let adder = Config::load(DbConfig {
+                id: Uuid::new_v4(),
+                name: "adder".to_string(),
+                path: "../modules/adder".to_string(),
+            })
+            .unwrap();
+            store.save_config(&adder, &alice).unwrap();
+            let call = Instruction::CallMethod {
+                id: Uuid::new_v4(),
+                config: adder.id,
+                method: "add".to_string(),
+                out_variable_name: "sum".to_string(),
+                arguments: Vec::new(),
+            };
+            let mut add = endpoint("add", vec![call]);
+            store.save_endpoint(&mut add, &alice).unwrap();
+            let service = Service {
+                id: Uuid::new_v4(),
+                name: "calculator".to_string(),
+                description: String::new(),
+                backend: "actix".to_string(),
+                endpoints: vec![ServiceEndpoint {
+                    endpoint: add.id,
+                    prefix: "/math".to_string(),
+                }],
+            };
+            store.save_service(&service).unwrap();
+
+            match store.delete_config(adder.id, &alice) {
+                Err(StoreError::Conflict(_)) => {}
+                result => panic!("{}: expected a conflict, got {:?}", name, result),
+            }
+            assert!(store.delete_endpoint(add.id, &alice).unwrap(), "{}", name);
+            assert!(!store.delete_endpoint(add.id, &alice).unwrap(), "{}", name);
+            assert!(store.revisions(add.id).unwrap().is_empty(), "{}", name);
+            let service = store.service(service.id).unwrap().unwrap();
+            assert!(service.endpoints.is_empty(), "{}", name);
+            assert!(store.delete_config(adder.id, &alice).unwrap(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn failed_transactions_change_nothing() {
+        for (name, store) in stores() {
+            let store = &*store;
+            let alice = user(store, "alice", Role::Write);
+            let id = Uuid::new_v4();
+            let result = store.transaction(&mut |store| {
+                let instruction = Instruction::JsonReturn {
+                    id: Uuid::new_v4(),
+                    arg_type: ArgType::Number,
+                    arg_type_value: "1".to_string(),
+                };
+                let mut saved = Endpoint {
+                    id,
+                    ..endpoint("one", vec![instruction])
+                };
+                store.save_endpoint(&mut saved, &alice)?;
+                assert!(store.endpoint(id)?.is_some());
+                Err(format_err!("Stop"))
+            });
+            assert_eq!("Stop", result.unwrap_err().to_string(), "{}", name);
+            assert!(store.endpoint(id).unwrap().is_none(), "{}", name);
+            assert!(store.revisions(id).unwrap().is_empty(), "{}", name);
+
+            store
+                .transaction(&mut |store| {
+                    store.save_endpoint(&mut endpoint("two", Vec::new()), &alice)?;
+                    Ok(())
+                })
+                .unwrap();
+            assert_eq!(1, store.endpoints().unwrap().len(), "{}", name);
+        }
+    }
+}
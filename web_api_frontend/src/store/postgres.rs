@@ -0,0 +1,743 @@
+use audit::{AuditEntry, AuditFilter, Change};
+use super::{check_owner, Store, StoreError, StoreResult};
+use chrono::NaiveDateTime;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::r2d2::{ConnectionManager, Pool};
+use diesel::result::Error as DieselError;
+use endpoint::{Config, Endpoint, Instruction, SaveError};
+use failure::Error;
+use models::{
+    AuditEntry as DBAuditEntry, Config as DbConfig, Endpoint as DBEndpoint,
+    EndpointChangeset as DBEndpointChangeset, EndpointRevision as DBEndpointRevision,
+    Instruction as DBInstruction, NewAuditEntry as DBNewAuditEntry,
+    NewEndpointRevision as DBNewEndpointRevision, Service as DBService,
+    ServiceEndpoint as DBServiceEndpoint, User as DBUser,
+};
+use revision::{Revision, RevisionSummary};
+use rust_functional::Config as BaseConfig;
+use schema;
+use service::Service;
+use std::rc::Rc;
+use user::User;
+use uuid::Uuid;
+
+/// A store in a Postgres database, with the schema created by the diesel migrations
+pub struct PgStore {
+    pool: Pool<ConnectionManager<PgConnection>>,
+}
+
+impl PgStore {
+    pub fn connect(url: &str, pool_size: u32) -> StoreResult<PgStore> {
+        Ok(PgStore {
+            pool: Pool::builder()
+                .max_size(pool_size)
+                .build(ConnectionManager::new(url))?,
+        })
+    }
+}
+
+impl Store for PgStore {
+    fn configs(&self) -> StoreResult<Vec<Config>> {
+        Queries(&*self.pool.get()?).configs()
+    }
+    fn config(&self, id: Uuid) -> StoreResult<Option<Config>> {
+        Queries(&*self.pool.get()?).config(id)
+    }
+    fn config_by_name(&self, name: &str) -> StoreResult<Option<Config>> {
+        Queries(&*self.pool.get()?).config_by_name(name)
+    }
+    fn save_config(&self, config: &Config, actor: &User) -> StoreResult<()> {
+        Queries(&*self.pool.get()?).save_config(config, actor)
+    }
+    fn delete_config(&self, id: Uuid, actor: &User) -> StoreResult<bool> {
+        Queries(&*self.pool.get()?).delete_config(id, actor)
+    }
+    fn module_configs(&self, ids: &[Uuid]) -> StoreResult<Vec<(Uuid, Rc<BaseConfig>)>> {
+        Queries(&*self.pool.get()?).module_configs(ids)
+    }
+
+    fn endpoints(&self) -> StoreResult<Vec<Endpoint>> {
+        Queries(&*self.pool.get()?).endpoints()
+    }
+    fn endpoint(&self, id: Uuid) -> StoreResult<Option<Endpoint>> {
+        Queries(&*self.pool.get()?).endpoint(id)
+    }
+    fn endpoint_by_name(&self, name: &str) -> StoreResult<Option<Endpoint>> {
+        Queries(&*self.pool.get()?).endpoint_by_name(name)
+    }
+    fn save_endpoint(&self, endpoint: &mut Endpoint, author: &User) -> Result<(), SaveError> {
+        let conn = self.pool.get().map_err(StoreError::from)?;
+        Queries(&conn).save_endpoint(endpoint, author)
+    }
+    fn delete_endpoint(&self, id: Uuid, actor: &User) -> StoreResult<bool> {
+        Queries(&*self.pool.get()?).delete_endpoint(id, actor)
+    }
+
+    fn revisions(&self, endpoint: Uuid) -> StoreResult<Vec<RevisionSummary>> {
+        Queries(&*self.pool.get()?).revisions(endpoint)
+    }
+    fn revision(&self, endpoint: Uuid, version: i32) -> StoreResult<Option<Revision>> {
+        Queries(&*self.pool.get()?).revision(endpoint, version)
+    }
+
+    fn users(&self) -> StoreResult<Vec<User>> {
+        Queries(&*self.pool.get()?).users()
+    }
+    fn user(&self, id: Uuid) -> StoreResult<Option<User>> {
+        Queries(&*self.pool.get()?).user(id)
+    }
+    fn user_by_name(&self, name: &str) -> StoreResult<Option<User>> {
+        Queries(&*self.pool.get()?).user_by_name(name)
+    }
+    fn save_user(&self, user: &User) -> StoreResult<()> {
+        Queries(&*self.pool.get()?).save_user(user)
+    }
+    fn set_endpoint_owner(
+        &self,
+        endpoint: Uuid,
+        owner: Option<Uuid>,
+        actor: &User,
+    ) -> StoreResult<bool> {
+        Queries(&*self.pool.get()?).set_endpoint_owner(endpoint, owner, actor)
+    }
+
+    fn audit_log(&self, filter: &AuditFilter) -> StoreResult<Vec<AuditEntry>> {
+        Queries(&*self.pool.get()?).audit_log(filter)
+    }
+
+    fn services(&self) -> StoreResult<Vec<Service>> {
+        Queries(&*self.pool.get()?).services()
+    }
+    fn service(&self, id: Uuid) -> StoreResult<Option<Service>> {
+        Queries(&*self.pool.get()?).service(id)
+    }
+    fn save_service(&self, service: &Service) -> StoreResult<()> {
+        Queries(&*self.pool.get()?).save_service(service)
+    }
+
+    fn transaction(&self, f: &mut dyn FnMut(&dyn Store) -> Result<(), Error>) -> Result<(), Error> {
+        let conn = self.pool.get()?;
+        Queries(&conn).transaction(f)
+    }
+}
+
+/// The queries of a `PgStore`, run on a single connection so they can share a transaction
+struct Queries<'a>(&'a PgConnection);
+
+impl<'a> Store for Queries<'a> {
+    fn configs(&self) -> StoreResult<Vec<Config>> {
+        schema::config::table
+            .order(schema::config::name)
+            .get_results::<DbConfig>(self.0)?
+            .into_iter()
+            .map(Config::load)
+            .collect()
+    }
+
+    fn config(&self, id: Uuid) -> StoreResult<Option<Config>> {
+        schema::config::table
+            .filter(schema::config::id.eq(id))
+            .get_result::<DbConfig>(self.0)
+            .optional()?
+            .map(Config::load)
+            .transpose()
+    }
+
+    fn config_by_name(&self, name: &str) -> StoreResult<Option<Config>> {
+        schema::config::table
+            .filter(schema::config::name.eq(name))
+            .get_result::<DbConfig>(self.0)
+            .optional()?
+            .map(Config::load)
+            .transpose()
+    }
+
+    fn save_config(&self, config: &Config, actor: &User) -> StoreResult<()> {
+        let db = DbConfig {
+            id: config.id,
+            name: config.name.clone(),
+            path: config.path.clone(),
+        };
+        self.0.transaction(|| {
+            let before = schema::config::table
+                .filter(schema::config::id.eq(config.id))
+                .for_update()
+                .get_result::<DbConfig>(self.0)
+                .optional()?;
+            ::diesel::insert_into(schema::config::table)
+                .values(&db)
+                .on_conflict(schema::config::dsl::id)
+                .do_update()
+                .set(&db)
+                .execute(self.0)?;
+            record(self.0, Change::config(actor, before.as_ref(), Some(&db)))
+        })
+    }
+
+    /// Calls of the config are rejected by the `config_in_use` trigger
+    fn delete_config(&self, id: Uuid, actor: &User) -> StoreResult<bool> {
+        self.0.transaction(|| {
+            let before = ::diesel::delete(schema::config::table.filter(schema::config::id.eq(id)))
+                .get_result::<DbConfig>(self.0)
+                .optional()?;
+            match before {
+                Some(before) => {
+                    record(self.0, Change::config(actor, Some(&before), None))?;
+                    Ok(true)
+                }
+                None => Ok(false),
+            }
+        })
+    }
+
+    fn module_configs(&self, ids: &[Uuid]) -> StoreResult<Vec<(Uuid, Rc<BaseConfig>)>> {
+        schema::config::table
+            .filter(schema::config::id.eq_any(ids))
+            .get_results::<DbConfig>(self.0)?
+            .into_iter()
+            .map(|c| Config::load(c).map(|c| (c.id, Rc::new(c.config))))
+            .collect()
+    }
+
+    fn endpoints(&self) -> StoreResult<Vec<Endpoint>> {
+        let endpoints: Vec<DBEndpoint> = schema::endpoint::table
+            .order(schema::endpoint::name)
+            .get_results(self.0)?;
+        let instructions = DBInstruction::belonging_to(&endpoints)
+            .order(schema::instruction::sequence)
+            .get_results::<DBInstruction>(self.0)?
+            .grouped_by(&endpoints);
+
+        let mut result = Vec::with_capacity(endpoints.len());
+        for (endpoint, instructions) in endpoints.into_iter().zip(instructions) {
+            let mut endpoint: Endpoint = endpoint.into();
+            endpoint.instructions = load_instructions(instructions)?;
+            result.push(endpoint);
+        }
+        Ok(result)
+    }
+
+    fn endpoint(&self, id: Uuid) -> StoreResult<Option<Endpoint>> {
+        let endpoint: Option<DBEndpoint> = schema::endpoint::table
+            .filter(schema::endpoint::id.eq(id))
+            .get_result(self.0)
+            .optional()?;
+        let endpoint = match endpoint {
+            Some(e) => e,
+            None => return Ok(None),
+        };
+        let instructions = DBInstruction::belonging_to(&endpoint)
+            .order(schema::instruction::sequence)
+            .get_results::<DBInstruction>(self.0)?;
+
+        let mut endpoint: Endpoint = endpoint.into();
+        endpoint.instructions = load_instructions(instructions)?;
+        Ok(Some(endpoint))
+    }
+
+    fn endpoint_by_name(&self, name: &str) -> StoreResult<Option<Endpoint>> {
+        let id = schema::endpoint::table
+            .filter(schema::endpoint::name.eq(name))
+            .select(schema::endpoint::id)
+            .get_result::<Uuid>(self.0)
+            .optional()?;
+        match id {
+            Some(id) => self.endpoint(id),
+            None => Ok(None),
+        }
+    }
+
+    fn save_endpoint(&self, endpoint: &mut Endpoint, author: &User) -> Result<(), SaveError> {
+        self.0.transaction(|| save_endpoint(self.0, endpoint, author))
+    }
+
+    fn delete_endpoint(&self, id: Uuid, actor: &User) -> StoreResult<bool> {
+        self.0.transaction(|| {
+            let before = match self.endpoint(id)? {
+                Some(endpoint) => endpoint,
+                None => return Ok(false),
+            };
+            ::diesel::delete(schema::endpoint::table.filter(schema::endpoint::id.eq(id)))
+                .execute(self.0)?;
+            record(self.0, Change::endpoint(actor, Some(&before), None))?;
+            Ok(true)
+        })
+    }
+
+    fn revisions(&self, endpoint: Uuid) -> StoreResult<Vec<RevisionSummary>> {
+        use schema::endpoint_revision::dsl;
+
+        let revisions = dsl::endpoint_revision
+            .filter(dsl::endpoint.eq(endpoint))
+            .order(dsl::version.desc())
+            .select((dsl::version, dsl::author, dsl::created_at))
+            .get_results::<(i32, String, NaiveDateTime)>(self.0)?;
+        Ok(revisions
+            .into_iter()
+            .map(|(version, author, created_at)| RevisionSummary {
+                version,
+                author,
+                created_at,
+            })
+            .collect())
+    }
+
+    fn revision(&self, endpoint: Uuid, version: i32) -> StoreResult<Option<Revision>> {
+        use schema::endpoint_revision::dsl;
+
+        dsl::endpoint_revision
+            .filter(dsl::endpoint.eq(endpoint).and(dsl::version.eq(version)))
+            .get_result::<DBEndpointRevision>(self.0)
+            .optional()?
+            .map(Revision::load)
+            .transpose()
+    }
+
+    fn users(&self) -> StoreResult<Vec<User>> {
+        schema::app_user::table
+            .order(schema::app_user::name)
+            .get_results::<DBUser>(self.0)?
+            .into_iter()
+            .map(to_user)
+            .collect()
+    }
+
+    fn user(&self, id: Uuid) -> StoreResult<Option<User>> {
+        schema::app_user::table
+            .filter(schema::app_user::id.eq(id))
+            .get_result::<DBUser>(self.0)
+            .optional()?
+            .map(to_user)
+            .transpose()
+    }
+
+    fn user_by_name(&self, name: &str) -> StoreResult<Option<User>> {
+        schema::app_user::table
+            .filter(schema::app_user::name.eq(name))
+            .get_result::<DBUser>(self.0)
+            .optional()?
+            .map(to_user)
+            .transpose()
+    }
+
+    fn save_user(&self, user: &User) -> StoreResult<()> {
+        let db = DBUser {
+            id: user.id,
+            name: user.name.clone(),
+            password_hash: user.password_hash.clone(),
+            role: user.role.as_str().to_string(),
+        };
+        ::diesel::insert_into(schema::app_user::table)
+            .values(&db)
+            .on_conflict(schema::app_user::dsl::id)
+            .do_update()
+            .set(&db)
+            .execute(self.0)?;
+        Ok(())
+    }
+
+    fn set_endpoint_owner(
+        &self,
+        endpoint: Uuid,
+        owner: Option<Uuid>,
+        actor: &User,
+    ) -> StoreResult<bool> {
+        use schema::endpoint::dsl;
+
+        self.0.transaction(|| {
+            let before = match self.endpoint(endpoint)? {
+                Some(before) => before,
+                None => return Ok(false),
+            };
+            ::diesel::update(dsl::endpoint.filter(dsl::id.eq(endpoint)))
+                .set(dsl::owner.eq(owner))
+                .execute(self.0)?;
+            let after = Endpoint {
+                owner,
+                ..before.clone()
+            };
+            record(self.0, Change::endpoint(actor, Some(&before), Some(&after)))?;
+            Ok(true)
+        })
+    }
+
+    fn audit_log(&self, filter: &AuditFilter) -> StoreResult<Vec<AuditEntry>> {
+        use schema::audit::dsl;
+
+        let mut query = dsl::audit.into_boxed();
+        if let Some(endpoint) = filter.endpoint {
+            query = query.filter(dsl::endpoint.eq(endpoint));
+        }
+        if let Some(config) = filter.config {
+            query = query.filter(dsl::config.eq(config));
+        }
+        if let Some(from) = filter.from {
+            query = query.filter(dsl::created_at.ge(from));
+        }
+        if let Some(to) = filter.to {
+            query = query.filter(dsl::created_at.lt(to));
+        }
+        query
+            .order(dsl::id.desc())
+            .get_results::<DBAuditEntry>(self.0)?
+            .into_iter()
+            .map(to_audit_entry)
+            .collect()
+    }
+
+    fn services(&self) -> StoreResult<Vec<Service>> {
+        let services = schema::service::table
+            .order(schema::service::name)
+            .get_results::<DBService>(self.0)?;
+        let endpoints = DBServiceEndpoint::belonging_to(&services)
+            .get_results::<DBServiceEndpoint>(self.0)?
+            .grouped_by(&services);
+        Ok(services.into_iter().zip(endpoints).map(Into::into).collect())
+    }
+
+    fn service(&self, id: Uuid) -> StoreResult<Option<Service>> {
+        let service: Option<DBService> = schema::service::table
+            .filter(schema::service::id.eq(id))
+            .get_result(self.0)
+            .optional()?;
+        let service = match service {
+            Some(s) => s,
+            None => return Ok(None),
+        };
+        let endpoints =
+            DBServiceEndpoint::belonging_to(&service).get_results::<DBServiceEndpoint>(self.0)?;
+        Ok(Some((service, endpoints).into()))
+    }
+
+    fn save_service(&self, service: &Service) -> StoreResult<()> {
+        let conn = self.0;
+        conn.transaction(|| {
+            let db = DBService {
+                id: service.id,
+                name: service.name.clone(),
+                description: service.description.clone(),
+                backend: service.backend.clone(),
+            };
+            ::diesel::insert_into(schema::service::table)
+                .values(&db)
+                .on_conflict(schema::service::dsl::id)
+                .do_update()
+                .set(&db)
+                .execute(conn)?;
+
+            ::diesel::delete(
+                schema::service_endpoint::table
+                    .filter(schema::service_endpoint::dsl::service.eq(service.id)),
+            ).execute(conn)?;
+
+            let endpoints = service
+                .endpoints
+                .iter()
+                .map(|e| DBServiceEndpoint {
+                    service: service.id,
+                    endpoint: e.endpoint,
+                    prefix: e.prefix.clone(),
+                })
+                .collect::<Vec<_>>();
+            ::diesel::insert_into(schema::service_endpoint::table)
+                .values(&endpoints)
+                .execute(conn)?;
+            Ok(())
+        })
+    }
+
+    fn transaction(&self, f: &mut dyn FnMut(&dyn Store) -> Result<(), Error>) -> Result<(), Error> {
+        self.0.transaction(|| f(self))
+    }
+}
+
+fn to_user(user: DBUser) -> StoreResult<User> {
+    Ok(User {
+        id: user.id,
+        name: user.name,
+        role: user.role.parse().map_err(StoreError::Internal)?,
+        password_hash: user.password_hash,
+    })
+}
+
+fn to_audit_entry(entry: DBAuditEntry) -> StoreResult<AuditEntry> {
+    Ok(AuditEntry {
+        id: entry.id,
+        created_at: entry.created_at,
+        actor: entry.actor,
+        action: entry.action.parse().map_err(StoreError::Internal)?,
+        endpoint: entry.endpoint,
+        config: entry.config,
+        before: entry.before,
+        after: entry.after,
+    })
+}
+
+fn record(conn: &PgConnection, change: Change) -> StoreResult<()> {
+    let entry = DBNewAuditEntry {
+        actor: change.actor,
+        action: change.action.as_str().to_string(),
+        endpoint: change.endpoint,
+        config: change.config,
+        before: change.before,
+        after: change.after,
+    };
+    ::diesel::insert_into(schema::audit::table)
+        .values(&entry)
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Deserialize the payloads of the given rows
+fn load_instructions(rows: Vec<DBInstruction>) -> StoreResult<Vec<Instruction>> {
+    rows.into_iter()
+        .map(|row| {
+            ::serde_json::from_value(row.payload)
+                .map_err(|e| DieselError::DeserializationError(Box::new(e)).into())
+        })
+        .collect()
+}
+
+fn save_endpoint(
+    conn: &PgConnection,
+    endpoint: &mut Endpoint,
+    author: &User,
+) -> Result<(), SaveError> {
+    use schema::endpoint::dsl;
+
+    let db = DBEndpointChangeset {
+        id: endpoint.id,
+        name: endpoint.name.clone(),
+        description: endpoint.description.clone(),
+        url: endpoint.url.clone(),
+    };
+
+    let current: Option<(i32, Option<Uuid>)> = dsl::endpoint
+        .filter(dsl::id.eq(endpoint.id))
+        .select((dsl::version, dsl::owner))
+        .for_update()
+        .get_result(conn)
+        .optional()?;
+    check_owner(author, endpoint.id, current.map(|(_, owner)| owner))?;
+    // The upsert of the instructions below would move an instruction of another endpoint
+    let ids = endpoint
+        .instructions
+        .iter()
+        .map(|i| i.get_id())
+        .collect::<Vec<_>>();
+    let foreign: Option<Uuid> = schema::instruction::table
+        .filter(
+            schema::instruction::dsl::id
+                .eq_any(&ids)
+                .and(schema::instruction::dsl::endpoint.ne(endpoint.id)),
+        )
+        .select(schema::instruction::dsl::id)
+        .first(conn)
+        .optional()?;
+    if let Some(instruction) = foreign {
+        return Err(StoreError::Conflict(format!(
+            "Instruction {} belongs to another endpoint",
+            instruction
+        ))
+        .into());
+    }
+    let before = match current {
+        Some(_) => Queries(conn).endpoint(endpoint.id)?,
+        None => None,
+    };
+
+    let (version, updated_at, owner) = match current {
+        None => ::diesel::insert_into(dsl::endpoint)
+            .values((&db, dsl::owner.eq(author.id)))
+            .returning((dsl::version, dsl::updated_at, dsl::owner))
+            .get_result(conn)?,
+        Some((actual, _)) if actual != endpoint.version => {
+            return Err(SaveError::Conflict {
+                id: endpoint.id,
+                expected: endpoint.version,
+                actual,
+            })
+        }
+        Some((actual, _)) => ::diesel::update(dsl::endpoint.filter(dsl::id.eq(endpoint.id)))
+            .set((&db, dsl::version.eq(actual + 1)))
+            .returning((dsl::version, dsl::updated_at, dsl::owner))
+            .get_result(conn)?,
+    };
+
+    endpoint.version = version;
+    endpoint.updated_at = Some(updated_at);
+    endpoint.owner = owner;
+    let id = endpoint.id;
+
+    ::diesel::sql_query("UPDATE instruction SET sequence = -1 - sequence WHERE endpoint = $1")
+        .bind::<::diesel::sql_types::Uuid, _>(id)
+        .execute(conn)?;
+
+    for (index, instruction) in endpoint.instructions.iter().enumerate() {
+        let row = DBInstruction {
+            id: instruction.get_id(),
+            endpoint: id,
+            sequence: index as i32,
+            payload: ::serde_json::to_value(instruction)
+                .expect("Instruction is always serializable"),
+        };
+        ::diesel::insert_into(schema::instruction::table)
+            .values(&row)
+            .on_conflict(schema::instruction::dsl::id)
+            .do_update()
+            .set(&row)
+            .execute(conn)?;
+    }
+
+    ::diesel::delete(
+        schema::instruction::table.filter(
+            schema::instruction::dsl::endpoint
+                .eq(id)
+                .and(schema::instruction::dsl::sequence.lt(0)),
+        ),
+    ).execute(conn)?;
+
+    let revision = DBNewEndpointRevision {
+        endpoint: id,
+        version: endpoint.version,
+        author: author.name.clone(),
+        snapshot: ::serde_json::to_value(&*endpoint).expect("Endpoint is always serializable"),
+    };
+    ::diesel::insert_into(schema::endpoint_revision::table)
+        .values(&revision)
+        .execute(conn)?;
+    record(conn, Change::endpoint(author, before.as_ref(), Some(&*endpoint)))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use endpoint::{ArgType, CallMethodArgument};
+    use std::env;
+    use user::Role;
+
+    fn user(name: &str) -> User {
+        User {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            role: Role::Write,
+            password_hash: String::new(),
+        }
+    }
+
+    fn endpoint(name: &str, instructions: Vec<Instruction>) -> Endpoint {
+        Endpoint {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            description: name.to_string(),
+            url: format!("/{}", name),
+            instructions,
+            version: 0,
+            updated_at: None,
+            owner: None,
+        }
+    }
+
+    /// Run `f` on the database in `TEST_DATABASE_URL`, which needs the migrations applied, in a
+    /// transaction that is rolled back. The tests that call it run with `cargo test -- --ignored`.
+    fn with_database<F: FnOnce(&Queries)>(f: F) {
+        let url = env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL is not set");
+        let conn = PgConnection::establish(&url).unwrap();
+        conn.test_transaction::<_, DieselError, _>(|| {
+            f(&Queries(&conn));
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn instructions_cannot_be_taken_from_another_endpoint() {
+        with_database(|store| {
+            let (alice, bob) = (user("alice"), user("bob"));
+            store.save_user(&alice).unwrap();
+            store.save_user(&bob).unwrap();
+            let instruction = Instruction::JsonReturn {
+                id: Uuid::new_v4(),
+                arg_type: ArgType::Number,
+                arg_type_value: "1".to_string(),
+            };
+            let mut mine = endpoint("mine", vec![instruction.clone()]);
+            store.save_endpoint(&mut mine, &alice).unwrap();
+
+            let mut theirs = endpoint("theirs", vec![instruction.clone()]);
+            match store.save_endpoint(&mut theirs, &bob) {
+                Err(SaveError::Store(StoreError::Conflict(_))) => {}
+                result => panic!("Expected a conflict, got {:?}", result),
+            }
+            assert!(store.endpoint(theirs.id).unwrap().is_none());
+            let loaded = store.endpoint(mine.id).unwrap().unwrap();
+            assert_eq!(vec![instruction], loaded.instructions);
+            assert!(store.revisions(theirs.id).unwrap().is_empty());
+
+            // Saving the instructions with their own endpoint still updates them
+            store.save_endpoint(&mut mine, &alice).unwrap();
+            assert_eq!(2, mine.version);
+        });
+    }
+
+    #[test]
+    #[ignore]
+    fn instructions_are_stored_as_their_payload() {
+        with_database(|store| {
+            let alice = user("alice");
+            store.save_user(&alice).unwrap();
+            let adder = Config::load(DbConfig {
+                id: Uuid::new_v4(),
+                name: "adder".to_string(),
+                path: "../modules/adder".to_string(),
+            })
+            .unwrap();
+            store.save_config(&adder, &alice).unwrap();
+            let call = Instruction::CallMethod {
+                id: Uuid::new_v4(),
+                config: adder.id,
+                method: "add".to_string(),
+                out_variable_name: "out".to_string(),
+                arguments: vec![
+                    CallMethodArgument {
+                        name: "a".to_string(),
+                        arg_type: ArgType::Number,
+                        arg_type_value: "1".to_string(),
+                    },
+                    CallMethodArgument {
+                        name: "b".to_string(),
+                        arg_type: ArgType::Float,
+                        arg_type_value: "2.5".to_string(),
+                    },
+                ],
+            };
+            let json = Instruction::JsonReturn {
+                id: Uuid::new_v4(),
+                arg_type: ArgType::Parameter,
+                arg_type_value: "out".to_string(),
+            };
+            let mut endpoint = endpoint("add", vec![call.clone(), json.clone()]);
+            store.save_endpoint(&mut endpoint, &alice).unwrap();
+            let loaded = store.endpoint(endpoint.id).unwrap().unwrap();
+            assert_eq!(vec![call.clone(), json.clone()], loaded.instructions);
+
+            endpoint.instructions = vec![json.clone(), call.clone()];
+            store.save_endpoint(&mut endpoint, &alice).unwrap();
+            let loaded = store.endpoint(endpoint.id).unwrap().unwrap();
+            assert_eq!(vec![json.clone(), call], loaded.instructions);
+
+            // The `config_in_use` trigger looks into the payloads
+            match store.delete_config(adder.id, &alice) {
+                Err(StoreError::Conflict(_)) => {}
+                result => panic!("Expected a conflict, got {:?}", result),
+            }
+            endpoint.instructions = vec![json.clone()];
+            store.save_endpoint(&mut endpoint, &alice).unwrap();
+            let loaded = store.endpoint(endpoint.id).unwrap().unwrap();
+            assert_eq!(vec![json], loaded.instructions);
+            assert!(store.delete_config(adder.id, &alice).unwrap());
+        });
+    }
+}
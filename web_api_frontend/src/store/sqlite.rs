@@ -0,0 +1,820 @@
+use audit::{AuditEntry, AuditFilter, Change};
+use super::{check_owner, Store, StoreError, StoreResult};
+use chrono::{NaiveDateTime, Utc};
+use diesel::connection::SimpleConnection;
+use diesel::prelude::*;
+use diesel::r2d2::{self, ConnectionManager, CustomizeConnection, Pool};
+use diesel::sqlite::SqliteConnection;
+use endpoint::{Config, Endpoint, SaveError};
+use failure::Error;
+use models::Config as DbConfig;
+use revision::{Revision, RevisionSummary};
+use serde_json::Value;
+use service::{Service, ServiceEndpoint};
+use user::User;
+use uuid::Uuid;
+
+/// The tables of a SQLite store. Ids are stored as text and JSON values as serialized text.
+mod tables {
+    table! {
+        app_user (id) {
+            id -> Text,
+            name -> Text,
+            password_hash -> Text,
+            role -> Text,
+        }
+    }
+
+    table! {
+        audit (id) {
+            id -> BigInt,
+            created_at -> Timestamp,
+            actor -> Text,
+            action -> Text,
+            endpoint -> Nullable<Text>,
+            config -> Nullable<Text>,
+            before -> Nullable<Text>,
+            after -> Nullable<Text>,
+        }
+    }
+
+    table! {
+        config (id) {
+            id -> Text,
+            name -> Text,
+            path -> Text,
+        }
+    }
+
+    table! {
+        endpoint (id) {
+            id -> Text,
+            name -> Text,
+            description -> Text,
+            url -> Text,
+            version -> Integer,
+            updated_at -> Timestamp,
+            instructions -> Text,
+            owner -> Nullable<Text>,
+        }
+    }
+
+    table! {
+        endpoint_revision (endpoint, version) {
+            endpoint -> Text,
+            version -> Integer,
+            author -> Text,
+            created_at -> Timestamp,
+            snapshot -> Text,
+        }
+    }
+
+    table! {
+        service (id) {
+            id -> Text,
+            name -> Text,
+            description -> Text,
+            backend -> Text,
+        }
+    }
+
+    table! {
+        service_endpoint (service, endpoint) {
+            service -> Text,
+            endpoint -> Text,
+            prefix -> Text,
+        }
+    }
+}
+
+use self::tables::{app_user, audit, config, endpoint, endpoint_revision, service, service_endpoint};
+
+/// Created when the store is opened, with the same constraints as the Postgres migrations
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS app_user (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL,
+    role TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS audit (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    created_at TIMESTAMP NOT NULL,
+    actor TEXT NOT NULL,
+    action TEXT NOT NULL CHECK (action IN ('create', 'update', 'delete')),
+    endpoint TEXT,
+    config TEXT,
+    before TEXT,
+    after TEXT,
+
+    CHECK ((endpoint IS NULL) <> (config IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS audit_endpoint ON audit (endpoint);
+CREATE INDEX IF NOT EXISTS audit_created_at ON audit (created_at);
+
+CREATE TABLE IF NOT EXISTS config (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    path TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS endpoint (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT NOT NULL UNIQUE,
+    url TEXT NOT NULL UNIQUE,
+    version INTEGER NOT NULL,
+    updated_at TIMESTAMP NOT NULL,
+    instructions TEXT NOT NULL,
+    owner TEXT REFERENCES app_user(id) ON DELETE SET NULL
+);
+
+CREATE TABLE IF NOT EXISTS endpoint_revision (
+    endpoint TEXT NOT NULL REFERENCES endpoint(id) ON DELETE CASCADE,
+    version INTEGER NOT NULL,
+    author TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    snapshot TEXT NOT NULL,
+
+    PRIMARY KEY (endpoint, version)
+);
+
+CREATE TABLE IF NOT EXISTS service (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT NOT NULL,
+    backend TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS service_endpoint (
+    service TEXT NOT NULL REFERENCES service(id) ON DELETE CASCADE,
+    endpoint TEXT NOT NULL REFERENCES endpoint(id) ON DELETE CASCADE,
+    prefix TEXT NOT NULL,
+
+    PRIMARY KEY (service, endpoint)
+);
+";
+
+/// Columns added to `SCHEMA` after its tables were first created, as `(table, column, type)`.
+/// They are added when an older database is opened.
+const ADDED_COLUMNS: &[(&str, &str, &str)] = &[(
+    "endpoint",
+    "owner",
+    "TEXT REFERENCES app_user(id) ON DELETE SET NULL",
+)];
+
+type EndpointRow = (
+    String,
+    String,
+    String,
+    String,
+    i32,
+    NaiveDateTime,
+    String,
+    Option<String>,
+);
+type UserRow = (String, String, String, String);
+type RevisionRow = (String, i32, String, NaiveDateTime, String);
+type AuditRow = (
+    i64,
+    NaiveDateTime,
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+);
+
+/// A store in a single SQLite file, for running the frontend without a database server
+pub struct SqliteStore {
+    pool: Pool<ConnectionManager<SqliteConnection>>,
+}
+
+/// SQLite only enforces foreign keys when asked to, for every connection
+#[derive(Debug)]
+struct ForeignKeys;
+
+impl CustomizeConnection<SqliteConnection, r2d2::Error> for ForeignKeys {
+    fn on_acquire(&self, conn: &mut SqliteConnection) -> Result<(), r2d2::Error> {
+        conn.batch_execute("PRAGMA foreign_keys = ON")
+            .map_err(r2d2::Error::QueryError)
+    }
+}
+
+impl SqliteStore {
+    /// Open the database at `path`, creating it and its tables if they do not exist
+    pub fn open(path: &str) -> StoreResult<SqliteStore> {
+        // SQLite allows a single writer, so connections would only wait for each other
+        let pool = Pool::builder()
+            .max_size(1)
+            .connection_customizer(Box::new(ForeignKeys))
+            .build(ConnectionManager::new(path))?;
+        let conn = pool.get()?;
+        conn.batch_execute(SCHEMA)?;
+        for (table, column, column_type) in ADDED_COLUMNS {
+            let query = format!("SELECT {} FROM {} LIMIT 0", column, table);
+            if conn.batch_execute(&query).is_err() {
+                conn.batch_execute(&format!(
+                    "ALTER TABLE {} ADD COLUMN {} {}",
+                    table, column, column_type
+                ))?;
+            }
+        }
+        Ok(SqliteStore { pool })
+    }
+}
+
+impl Store for SqliteStore {
+    fn configs(&self) -> StoreResult<Vec<Config>> {
+        Queries(&*self.pool.get()?).configs()
+    }
+    fn config(&self, id: Uuid) -> StoreResult<Option<Config>> {
+        Queries(&*self.pool.get()?).config(id)
+    }
+    fn config_by_name(&self, name: &str) -> StoreResult<Option<Config>> {
+        Queries(&*self.pool.get()?).config_by_name(name)
+    }
+    fn save_config(&self, config: &Config, actor: &User) -> StoreResult<()> {
+        Queries(&*self.pool.get()?).save_config(config, actor)
+    }
+    fn delete_config(&self, id: Uuid, actor: &User) -> StoreResult<bool> {
+        Queries(&*self.pool.get()?).delete_config(id, actor)
+    }
+
+    fn endpoints(&self) -> StoreResult<Vec<Endpoint>> {
+        Queries(&*self.pool.get()?).endpoints()
+    }
+    fn endpoint(&self, id: Uuid) -> StoreResult<Option<Endpoint>> {
+        Queries(&*self.pool.get()?).endpoint(id)
+    }
+    fn endpoint_by_name(&self, name: &str) -> StoreResult<Option<Endpoint>> {
+        Queries(&*self.pool.get()?).endpoint_by_name(name)
+    }
+    fn save_endpoint(&self, endpoint: &mut Endpoint, author: &User) -> Result<(), SaveError> {
+        let conn = self.pool.get().map_err(StoreError::from)?;
+        Queries(&conn).save_endpoint(endpoint, author)
+    }
+    fn delete_endpoint(&self, id: Uuid, actor: &User) -> StoreResult<bool> {
+        Queries(&*self.pool.get()?).delete_endpoint(id, actor)
+    }
+
+    fn revisions(&self, endpoint: Uuid) -> StoreResult<Vec<RevisionSummary>> {
+        Queries(&*self.pool.get()?).revisions(endpoint)
+    }
+    fn revision(&self, endpoint: Uuid, version: i32) -> StoreResult<Option<Revision>> {
+        Queries(&*self.pool.get()?).revision(endpoint, version)
+    }
+
+    fn users(&self) -> StoreResult<Vec<User>> {
+        Queries(&*self.pool.get()?).users()
+    }
+    fn user(&self, id: Uuid) -> StoreResult<Option<User>> {
+        Queries(&*self.pool.get()?).user(id)
+    }
+    fn user_by_name(&self, name: &str) -> StoreResult<Option<User>> {
+        Queries(&*self.pool.get()?).user_by_name(name)
+    }
+    fn save_user(&self, user: &User) -> StoreResult<()> {
+        Queries(&*self.pool.get()?).save_user(user)
+    }
+    fn set_endpoint_owner(
+        &self,
+        endpoint: Uuid,
+        owner: Option<Uuid>,
+        actor: &User,
+    ) -> StoreResult<bool> {
+        Queries(&*self.pool.get()?).set_endpoint_owner(endpoint, owner, actor)
+    }
+
+    fn audit_log(&self, filter: &AuditFilter) -> StoreResult<Vec<AuditEntry>> {
+        Queries(&*self.pool.get()?).audit_log(filter)
+    }
+
+    fn services(&self) -> StoreResult<Vec<Service>> {
+        Queries(&*self.pool.get()?).services()
+    }
+    fn service(&self, id: Uuid) -> StoreResult<Option<Service>> {
+        Queries(&*self.pool.get()?).service(id)
+    }
+    fn save_service(&self, service: &Service) -> StoreResult<()> {
+        Queries(&*self.pool.get()?).save_service(service)
+    }
+
+    fn transaction(&self, f: &mut dyn FnMut(&dyn Store) -> Result<(), Error>) -> Result<(), Error> {
+        let conn = self.pool.get()?;
+        Queries(&conn).transaction(f)
+    }
+}
+
+/// The queries of a `SqliteStore`, run on a single connection so they can share a transaction
+struct Queries<'a>(&'a SqliteConnection);
+
+fn parse_id(id: &str) -> StoreResult<Uuid> {
+    Uuid::parse_str(id).map_err(|e| StoreError::Internal(format!("Invalid id {:?}: {}", id, e)))
+}
+
+fn from_json<T: ::serde::de::DeserializeOwned>(json: &str) -> StoreResult<T> {
+    ::serde_json::from_str(json).map_err(|e| StoreError::Internal(e.to_string()))
+}
+
+fn to_db_config((id, name, path): (String, String, String)) -> StoreResult<DbConfig> {
+    Ok(DbConfig {
+        id: parse_id(&id)?,
+        name,
+        path,
+    })
+}
+
+fn to_config(row: (String, String, String)) -> StoreResult<Config> {
+    Config::load(to_db_config(row)?)
+}
+
+fn parse_optional_id(id: Option<String>) -> StoreResult<Option<Uuid>> {
+    id.map(|id| parse_id(&id)).transpose()
+}
+
+fn to_audit_entry(
+    (id, created_at, actor, action, endpoint, config, before, after): AuditRow,
+) -> StoreResult<AuditEntry> {
+    let json = |value: Option<String>| value.map(|value| from_json(&value)).transpose();
+    Ok(AuditEntry {
+        id,
+        created_at,
+        actor,
+        action: action.parse().map_err(StoreError::Internal)?,
+        endpoint: parse_optional_id(endpoint)?,
+        config: parse_optional_id(config)?,
+        before: json(before)?,
+        after: json(after)?,
+    })
+}
+
+fn to_endpoint(
+    (id, name, description, url, version, updated_at, instructions, owner): EndpointRow,
+) -> StoreResult<Endpoint> {
+    Ok(Endpoint {
+        id: parse_id(&id)?,
+        name,
+        description,
+        url,
+        instructions: from_json(&instructions)?,
+        version,
+        updated_at: Some(updated_at),
+        owner: parse_optional_id(owner)?,
+    })
+}
+
+fn to_user((id, name, password_hash, role): UserRow) -> StoreResult<User> {
+    Ok(User {
+        id: parse_id(&id)?,
+        name,
+        role: role.parse().map_err(StoreError::Internal)?,
+        password_hash,
+    })
+}
+
+impl<'a> Queries<'a> {
+    fn service_endpoints(&self, service: &str) -> StoreResult<Vec<ServiceEndpoint>> {
+        service_endpoint::table
+            .filter(service_endpoint::service.eq(service))
+            .select((service_endpoint::endpoint, service_endpoint::prefix))
+            .get_results::<(String, String)>(self.0)?
+            .into_iter()
+            .map(|(endpoint, prefix)| {
+                Ok(ServiceEndpoint {
+                    endpoint: parse_id(&endpoint)?,
+                    prefix,
+                })
+            })
+            .collect()
+    }
+
+    fn to_service(
+        &self,
+        (id, name, description, backend): (String, String, String, String),
+    ) -> StoreResult<Service> {
+        Ok(Service {
+            id: parse_id(&id)?,
+            endpoints: self.service_endpoints(&id)?,
+            name,
+            description,
+            backend,
+        })
+    }
+
+    fn record(&self, change: Change) -> StoreResult<()> {
+        let json = |value: Option<Value>| value.map(|value| value.to_string());
+        ::diesel::insert_into(audit::table)
+            .values((
+                audit::created_at.eq(Utc::now().naive_utc()),
+                audit::actor.eq(change.actor),
+                audit::action.eq(change.action.as_str()),
+                audit::endpoint.eq(change.endpoint.map(|id| id.to_string())),
+                audit::config.eq(change.config.map(|id| id.to_string())),
+                audit::before.eq(json(change.before)),
+                audit::after.eq(json(change.after)),
+            ))
+            .execute(self.0)?;
+        Ok(())
+    }
+
+    fn db_config(&self, id: &str) -> StoreResult<Option<DbConfig>> {
+        config::table
+            .filter(config::id.eq(id))
+            .get_result(self.0)
+            .optional()?
+            .map(to_db_config)
+            .transpose()
+    }
+
+    fn save(&self, endpoint: &mut Endpoint, author: &User) -> Result<(), SaveError> {
+        let id = endpoint.id.to_string();
+        let current = endpoint::table
+            .filter(endpoint::id.eq(&id))
+            .select((endpoint::version, endpoint::owner))
+            .get_result::<(i32, Option<String>)>(self.0)
+            .optional()?;
+        let (current, owner) = match current {
+            Some((version, owner)) => (Some(version), Some(parse_optional_id(owner)?)),
+            None => (None, None),
+        };
+        check_owner(author, endpoint.id, owner)?;
+        let before = match current {
+            Some(_) => self.endpoint(endpoint.id)?,
+            None => None,
+        };
+        let version = match current {
+            Some(actual) if actual != endpoint.version => {
+                return Err(SaveError::Conflict {
+                    id: endpoint.id,
+                    expected: endpoint.version,
+                    actual,
+                })
+            }
+            Some(actual) => actual + 1,
+            None => 1,
+        };
+        let updated_at = Utc::now().naive_utc();
+        let instructions = ::serde_json::to_string(&endpoint.instructions)
+            .expect("Instruction is always serializable");
+        let values = (
+            endpoint::name.eq(&endpoint.name),
+            endpoint::description.eq(&endpoint.description),
+            endpoint::url.eq(&endpoint.url),
+            endpoint::version.eq(version),
+            endpoint::updated_at.eq(updated_at),
+            endpoint::instructions.eq(&instructions),
+        );
+        if current.is_some() {
+            ::diesel::update(endpoint::table.filter(endpoint::id.eq(&id)))
+                .set(values)
+                .execute(self.0)?;
+        } else {
+            ::diesel::insert_into(endpoint::table)
+                .values((
+                    endpoint::id.eq(&id),
+                    endpoint::owner.eq(author.id.to_string()),
+                    values,
+                ))
+                .execute(self.0)?;
+        }
+
+        endpoint.version = version;
+        endpoint.updated_at = Some(updated_at);
+        endpoint.owner = owner.unwrap_or(Some(author.id));
+        let snapshot =
+            ::serde_json::to_string(&*endpoint).expect("Endpoint is always serializable");
+        ::diesel::insert_into(endpoint_revision::table)
+            .values((
+                endpoint_revision::endpoint.eq(&id),
+                endpoint_revision::version.eq(version),
+                endpoint_revision::author.eq(&author.name),
+                endpoint_revision::created_at.eq(updated_at),
+                endpoint_revision::snapshot.eq(&snapshot),
+            ))
+            .execute(self.0)?;
+        self.record(Change::endpoint(author, before.as_ref(), Some(&*endpoint)))?;
+        Ok(())
+    }
+}
+
+impl<'a> Store for Queries<'a> {
+    fn configs(&self) -> StoreResult<Vec<Config>> {
+        config::table
+            .order(config::name)
+            .get_results(self.0)?
+            .into_iter()
+            .map(to_config)
+            .collect()
+    }
+
+    fn config(&self, id: Uuid) -> StoreResult<Option<Config>> {
+        config::table
+            .filter(config::id.eq(id.to_string()))
+            .get_result(self.0)
+            .optional()?
+            .map(to_config)
+            .transpose()
+    }
+
+    fn config_by_name(&self, name: &str) -> StoreResult<Option<Config>> {
+        config::table
+            .filter(config::name.eq(name))
+            .get_result(self.0)
+            .optional()?
+            .map(to_config)
+            .transpose()
+    }
+
+    fn save_config(&self, c: &Config, actor: &User) -> StoreResult<()> {
+        let id = c.id.to_string();
+        self.0.transaction(|| {
+            let before = self.db_config(&id)?;
+            if before.is_some() {
+                ::diesel::update(config::table.filter(config::id.eq(&id)))
+                    .set((config::name.eq(&c.name), config::path.eq(&c.path)))
+                    .execute(self.0)?;
+            } else {
+                ::diesel::insert_into(config::table)
+                    .values((
+                        config::id.eq(&id),
+                        config::name.eq(&c.name),
+                        config::path.eq(&c.path),
+                    ))
+                    .execute(self.0)?;
+            }
+            let after = DbConfig {
+                id: c.id,
+                name: c.name.clone(),
+                path: c.path.clone(),
+            };
+            self.record(Change::config(actor, before.as_ref(), Some(&after)))
+        })
+    }
+
+    fn delete_config(&self, id: Uuid, actor: &User) -> StoreResult<bool> {
+        self.0.transaction(|| {
+            if let Some(endpoint) = self
+                .endpoints()?
+                .into_iter()
+                .find(|e| e.config_ids().contains(&id))
+            {
+                return Err(StoreError::Conflict(format!(
+                    "Config {} is still called by endpoint {:?}",
+                    id, endpoint.name
+                )));
+            }
+            let before = match self.db_config(&id.to_string())? {
+                Some(before) => before,
+                None => return Ok(false),
+            };
+            ::diesel::delete(config::table.filter(config::id.eq(id.to_string())))
+                .execute(self.0)?;
+            self.record(Change::config(actor, Some(&before), None))?;
+            Ok(true)
+        })
+    }
+
+    fn endpoints(&self) -> StoreResult<Vec<Endpoint>> {
+        endpoint::table
+            .order(endpoint::name)
+            .get_results::<EndpointRow>(self.0)?
+            .into_iter()
+            .map(to_endpoint)
+            .collect()
+    }
+
+    fn endpoint(&self, id: Uuid) -> StoreResult<Option<Endpoint>> {
+        endpoint::table
+            .filter(endpoint::id.eq(id.to_string()))
+            .get_result::<EndpointRow>(self.0)
+            .optional()?
+            .map(to_endpoint)
+            .transpose()
+    }
+
+    fn endpoint_by_name(&self, name: &str) -> StoreResult<Option<Endpoint>> {
+        endpoint::table
+            .filter(endpoint::name.eq(name))
+            .get_result::<EndpointRow>(self.0)
+            .optional()?
+            .map(to_endpoint)
+            .transpose()
+    }
+
+    fn save_endpoint(&self, endpoint: &mut Endpoint, author: &User) -> Result<(), SaveError> {
+        self.0.transaction(|| self.save(endpoint, author))
+    }
+
+    fn delete_endpoint(&self, id: Uuid, actor: &User) -> StoreResult<bool> {
+        self.0.transaction(|| {
+            let before = match self.endpoint(id)? {
+                Some(before) => before,
+                None => return Ok(false),
+            };
+            ::diesel::delete(endpoint::table.filter(endpoint::id.eq(id.to_string())))
+                .execute(self.0)?;
+            self.record(Change::endpoint(actor, Some(&before), None))?;
+            Ok(true)
+        })
+    }
+
+    fn revisions(&self, endpoint: Uuid) -> StoreResult<Vec<RevisionSummary>> {
+        let revisions = endpoint_revision::table
+            .filter(endpoint_revision::endpoint.eq(endpoint.to_string()))
+            .order(endpoint_revision::version.desc())
+            .select((
+                endpoint_revision::version,
+                endpoint_revision::author,
+                endpoint_revision::created_at,
+            ))
+            .get_results::<(i32, String, NaiveDateTime)>(self.0)?;
+        Ok(revisions
+            .into_iter()
+            .map(|(version, author, created_at)| RevisionSummary {
+                version,
+                author,
+                created_at,
+            })
+            .collect())
+    }
+
+    fn revision(&self, endpoint: Uuid, version: i32) -> StoreResult<Option<Revision>> {
+        let row = endpoint_revision::table
+            .filter(endpoint_revision::endpoint.eq(endpoint.to_string()))
+            .filter(endpoint_revision::version.eq(version))
+            .get_result::<RevisionRow>(self.0)
+            .optional()?;
+        match row {
+            Some((_, version, author, created_at, snapshot)) => Ok(Some(Revision {
+                endpoint,
+                version,
+                author,
+                created_at,
+                snapshot: from_json(&snapshot)?,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    fn users(&self) -> StoreResult<Vec<User>> {
+        app_user::table
+            .order(app_user::name)
+            .get_results(self.0)?
+            .into_iter()
+            .map(to_user)
+            .collect()
+    }
+
+    fn user(&self, id: Uuid) -> StoreResult<Option<User>> {
+        app_user::table
+            .filter(app_user::id.eq(id.to_string()))
+            .get_result(self.0)
+            .optional()?
+            .map(to_user)
+            .transpose()
+    }
+
+    fn user_by_name(&self, name: &str) -> StoreResult<Option<User>> {
+        app_user::table
+            .filter(app_user::name.eq(name))
+            .get_result(self.0)
+            .optional()?
+            .map(to_user)
+            .transpose()
+    }
+
+    fn save_user(&self, u: &User) -> StoreResult<()> {
+        let id = u.id.to_string();
+        let values = (
+            app_user::name.eq(&u.name),
+            app_user::password_hash.eq(&u.password_hash),
+            app_user::role.eq(u.role.as_str()),
+        );
+        let exists = app_user::table
+            .filter(app_user::id.eq(&id))
+            .select(app_user::id)
+            .get_result::<String>(self.0)
+            .optional()?
+            .is_some();
+        if exists {
+            ::diesel::update(app_user::table.filter(app_user::id.eq(&id)))
+                .set(values)
+                .execute(self.0)?;
+        } else {
+            ::diesel::insert_into(app_user::table)
+                .values((app_user::id.eq(&id), values))
+                .execute(self.0)?;
+        }
+        Ok(())
+    }
+
+    fn set_endpoint_owner(
+        &self,
+        id: Uuid,
+        owner: Option<Uuid>,
+        actor: &User,
+    ) -> StoreResult<bool> {
+        self.0.transaction(|| {
+            let before = match self.endpoint(id)? {
+                Some(before) => before,
+                None => return Ok(false),
+            };
+            ::diesel::update(endpoint::table.filter(endpoint::id.eq(id.to_string())))
+                .set(endpoint::owner.eq(owner.map(|o| o.to_string())))
+                .execute(self.0)?;
+            let after = Endpoint {
+                owner,
+                ..before.clone()
+            };
+            self.record(Change::endpoint(actor, Some(&before), Some(&after)))?;
+            Ok(true)
+        })
+    }
+
+    fn audit_log(&self, filter: &AuditFilter) -> StoreResult<Vec<AuditEntry>> {
+        let mut query = audit::table.into_boxed();
+        if let Some(endpoint) = filter.endpoint {
+            query = query.filter(audit::endpoint.eq(endpoint.to_string()));
+        }
+        if let Some(config) = filter.config {
+            query = query.filter(audit::config.eq(config.to_string()));
+        }
+        if let Some(from) = filter.from {
+            query = query.filter(audit::created_at.ge(from));
+        }
+        if let Some(to) = filter.to {
+            query = query.filter(audit::created_at.lt(to));
+        }
+        query
+            .order(audit::id.desc())
+            .get_results::<AuditRow>(self.0)?
+            .into_iter()
+            .map(to_audit_entry)
+            .collect()
+    }
+
+    fn services(&self) -> StoreResult<Vec<Service>> {
+        service::table
+            .order(service::name)
+            .get_results(self.0)?
+            .into_iter()
+            .map(|row| self.to_service(row))
+            .collect()
+    }
+
+    fn service(&self, id: Uuid) -> StoreResult<Option<Service>> {
+        service::table
+            .filter(service::id.eq(id.to_string()))
+            .get_result(self.0)
+            .optional()?
+            .map(|row| self.to_service(row))
+            .transpose()
+    }
+
+    fn save_service(&self, s: &Service) -> StoreResult<()> {
+        let id = s.id.to_string();
+        self.0.transaction(|| {
+            let values = (
+                service::name.eq(&s.name),
+                service::description.eq(&s.description),
+                service::backend.eq(&s.backend),
+            );
+            let exists = service::table
+                .filter(service::id.eq(&id))
+                .select(service::id)
+                .get_result::<String>(self.0)
+                .optional()?
+                .is_some();
+            if exists {
+                ::diesel::update(service::table.filter(service::id.eq(&id)))
+                    .set(values)
+                    .execute(self.0)?;
+            } else {
+                ::diesel::insert_into(service::table)
+                    .values((service::id.eq(&id), values))
+                    .execute(self.0)?;
+            }
+
+            ::diesel::delete(service_endpoint::table.filter(service_endpoint::service.eq(&id)))
+                .execute(self.0)?;
+            for e in &s.endpoints {
+                ::diesel::insert_into(service_endpoint::table)
+                    .values((
+                        service_endpoint::service.eq(&id),
+                        service_endpoint::endpoint.eq(e.endpoint.to_string()),
+                        service_endpoint::prefix.eq(&e.prefix),
+                    ))
+                    .execute(self.0)?;
+            }
+            Ok(())
+        })
+    }
+
+    fn transaction(&self, f: &mut dyn FnMut(&dyn Store) -> Result<(), Error>) -> Result<(), Error> {
+        self.0.transaction(|| f(self))
+    }
+}
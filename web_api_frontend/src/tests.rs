@@ -0,0 +1,1347 @@
+//! Tests of the API, against an actix server with a new store for every test
+
+use actix_web::http::{HeaderMap, Method, StatusCode};
+use actix_web::test::TestServer;
+use actix_web::{App, HttpMessage};
+use adder;
+use api;
+use cookie::Key;
+use endpoint::{ArgType, CallMethodArgument, ConfigForm, Endpoint, Instruction};
+use events::{Event, Events};
+use futures::{Future, Stream};
+use rust_functional::Interpreter;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{self, Value};
+use service::{Service, ServiceEndpoint};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use store::{MemoryStore, SharedStore, SqliteStore};
+use user::{hash_password, Role, User};
+use uuid::Uuid;
+use AppState;
+
+struct Api {
+    server: TestServer,
+    /// The `Cookie` header that is sent with every request, set by the responses like a browser
+    session: Option<String>,
+    /// The events published by the server
+    events: Events,
+}
+
+/// The interpreter of the server, with the adder module whether its feature is enabled or not
+fn interpreter() -> Interpreter {
+    let mut interpreter = ::interpreter();
+    interpreter.register("adder", adder::dispatch::call);
+    interpreter
+}
+
+/// A config as the API returns it. Its module is left out, because a `rust_functional::Config`
+/// is read from the format of a module.json, not from the JSON the API writes.
+#[derive(Deserialize)]
+struct Config {
+    id: Uuid,
+    name: String,
+}
+
+#[derive(Serialize)]
+struct InsertInstruction<'a> {
+    /// Appends the instruction if `None`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    index: Option<usize>,
+    instruction: &'a Instruction,
+}
+
+#[derive(Serialize)]
+struct Login<'a> {
+    name: &'a str,
+    password: &'a str,
+}
+
+#[derive(Serialize)]
+struct RunEndpoint {
+    endpoint: Endpoint,
+    inputs: HashMap<String, Value>,
+}
+
+impl Api {
+    /// A server with an in-memory store, see `with_store`
+    fn new() -> Api {
+        Api::with_store(Arc::new(MemoryStore::default()))
+    }
+
+    /// A server with a new SQLite database in the temporary directory, to test the diesel queries
+    fn sqlite() -> Api {
+        let path = env::temp_dir().join(format!("web_api_frontend_{}.db", Uuid::new_v4()));
+        Api::with_store(Arc::new(SqliteStore::open(path.to_str().unwrap()).unwrap()))
+    }
+
+    /// A server with the users `alice` and `bob` who can write, `carol` who can read and `admin`,
+    /// all with the password `secret`. Logged in as `alice`.
+    fn with_store(store: SharedStore) -> Api {
+        Api::start(store, None)
+    }
+
+    /// A server that keeps the crates it builds in `output_dir`. actix-web is patched with an
+    /// empty crate there, so builds fail within a second with diagnostics instead of compiling it.
+    fn building(output_dir: &Path) -> Api {
+        let stub = output_dir.join("actix_web_stub");
+        fs::create_dir_all(stub.join("src")).unwrap();
+        fs::create_dir_all(output_dir.join(".cargo")).unwrap();
+        let manifest = "[package]\nname = \"actix-web\"\nversion = \"0.6.14\"\nauthors = [\"\"]\n";
+        fs::write(stub.join("Cargo.toml"), manifest).unwrap();
+        fs::write(stub.join("src/lib.rs"), "").unwrap();
+        let config = format!("[patch.crates-io]\nactix-web = {{ path = {:?} }}\n", stub);
+        fs::write(output_dir.join(".cargo/config.toml"), config).unwrap();
+        Api::start(Arc::new(MemoryStore::default()), Some(output_dir.to_path_buf()))
+    }
+
+    fn start(store: SharedStore, output_dir: Option<PathBuf>) -> Api {
+        // Hashing is slow on purpose, so the users share a hash
+        let password_hash = hash_password("secret");
+        let users = [
+            ("alice", Role::Write),
+            ("bob", Role::Write),
+            ("carol", Role::Read),
+            ("admin", Role::Admin),
+        ];
+        for &(name, role) in &users {
+            let user = User {
+                id: Uuid::new_v4(),
+                name: name.to_string(),
+                role,
+                password_hash: password_hash.clone(),
+            };
+            store.save_user(&user).unwrap();
+        }
+        let key = Key::generate();
+        let events = Events::default();
+        let server_events = events.clone();
+        let server = TestServer::with_factory(move || {
+            api::register(App::with_state(AppState {
+                store: store.clone(),
+                interpreter: interpreter(),
+                key: key.clone(),
+                events: server_events.clone(),
+                output_dir: output_dir.clone(),
+            }))
+        });
+        let mut api = Api {
+            server,
+            session: None,
+            events,
+        };
+        api.login("alice");
+        api
+    }
+
+    fn login(&mut self, name: &str) -> User {
+        let login = Login {
+            name,
+            password: "secret",
+        };
+        self.json(Method::POST, "/api/login", Some(login))
+    }
+
+    /// Send a request with the current session, returning the status and the body
+    fn send<T: Serialize>(
+        &mut self,
+        method: Method,
+        path: &str,
+        body: Option<T>,
+    ) -> (StatusCode, Vec<u8>) {
+        let mut request = self.server.client(method, path);
+        if let Some(session) = &self.session {
+            request.header("Cookie", session.as_str());
+        }
+        let request = match body {
+            Some(body) => request.json(body),
+            None => request.finish(),
+        }.unwrap();
+        let response = self.server.execute(request.send()).unwrap();
+        if let Some(cookie) = response.headers().get("Set-Cookie") {
+            // Only keep `name=value`, an empty value removes the cookie
+            let cookie = cookie.to_str().unwrap().split(';').next().unwrap();
+            self.session = match cookie.split_once('=') {
+                Some((_, "")) | None => None,
+                Some(_) => Some(cookie.to_string()),
+            };
+        }
+        let status = response.status();
+        let body = self.server.execute(response.body()).unwrap();
+        (status, body.to_vec())
+    }
+
+    /// Send a request that must succeed, and parse the JSON response
+    fn json<T: Serialize, R: DeserializeOwned>(
+        &mut self,
+        method: Method,
+        path: &str,
+        body: Option<T>,
+    ) -> R {
+        let (status, body) = self.send(method, path, body);
+        assert!(
+            status.is_success(),
+            "{} {}",
+            status,
+            String::from_utf8_lossy(&body)
+        );
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    /// Send a request that must fail, and return the `error` of the response
+    fn error<T: Serialize>(
+        &mut self,
+        method: Method,
+        path: &str,
+        body: Option<T>,
+    ) -> (StatusCode, String) {
+        let (status, body) = self.send(method, path, body);
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        (status, body["error"].as_str().unwrap().to_string())
+    }
+
+    /// Send a request with a body that is not JSON, returning the headers of the response as well
+    fn raw(
+        &mut self,
+        method: Method,
+        path: &str,
+        content_type: &str,
+        body: Vec<u8>,
+    ) -> (StatusCode, HeaderMap, Vec<u8>) {
+        let mut request = self.server.client(method, path);
+        if let Some(session) = &self.session {
+            request.header("Cookie", session.as_str());
+        }
+        let request = request.content_type(content_type).body(body).unwrap();
+        let response = self.server.execute(request.send()).unwrap();
+        let (status, headers) = (response.status(), response.headers().clone());
+        let body = self.server.execute(response.body()).unwrap();
+        (status, headers, body.to_vec())
+    }
+
+    fn get<R: DeserializeOwned>(&mut self, path: &str) -> R {
+        self.json(Method::GET, path, None::<()>)
+    }
+
+    fn generate(&mut self, endpoint: &Endpoint) -> String {
+        let path = format!("/api/generate/{}", endpoint.id);
+        let (status, body) = self.send(Method::GET, &path, None::<()>);
+        assert_eq!(StatusCode::OK, status);
+        String::from_utf8(body).unwrap()
+    }
+
+    fn add_adder(&mut self) -> Config {
+        let form = ConfigForm {
+            name: "adder".to_string(),
+            path: "../modules/adder".to_string(),
+        };
+        self.json(Method::POST, "/api/configs", Some(form))
+    }
+
+    fn create_endpoint(&mut self, instructions: Vec<Instruction>) -> Endpoint {
+        let endpoint = new_endpoint("/add", instructions);
+        self.json(Method::POST, "/api/endpoints", Some(endpoint))
+    }
+}
+
+/// A server with an in-memory store and one with SQLite, for tests that cover the diesel queries
+fn stores() -> Vec<(&'static str, Api)> {
+    vec![("memory", Api::new()), ("sqlite", Api::sqlite())]
+}
+
+/// An endpoint that has not been saved yet
+fn new_endpoint(url: &str, instructions: Vec<Instruction>) -> Endpoint {
+    Endpoint {
+        id: Uuid::new_v4(),
+        name: "add".to_string(),
+        description: "Add two numbers".to_string(),
+        url: url.to_string(),
+        instructions,
+        version: 0,
+        updated_at: None,
+        owner: None,
+    }
+}
+
+/// A service that has not been saved yet, with the endpoints under their prefixes
+fn new_service(endpoints: Vec<(&Endpoint, &str)>) -> Service {
+    Service {
+        id: Uuid::new_v4(),
+        name: "calculator".to_string(),
+        description: "Calculates things".to_string(),
+        backend: "actix".to_string(),
+        endpoints: endpoints
+            .into_iter()
+            .map(|(endpoint, prefix)| ServiceEndpoint {
+                endpoint: endpoint.id,
+                prefix: prefix.to_string(),
+            })
+            .collect(),
+    }
+}
+
+fn number(name: &str, value: &str) -> CallMethodArgument {
+    CallMethodArgument {
+        name: name.to_string(),
+        arg_type: ArgType::Number,
+        arg_type_value: value.to_string(),
+    }
+}
+
+fn parameter(name: &str, variable: &str) -> CallMethodArgument {
+    CallMethodArgument {
+        name: name.to_string(),
+        arg_type: ArgType::Parameter,
+        arg_type_value: variable.to_string(),
+    }
+}
+
+fn add(config: &Config, out: &str, a: &str, b: &str) -> Instruction {
+    Instruction::CallMethod {
+        id: Uuid::new_v4(),
+        config: config.id,
+        method: "add".to_string(),
+        out_variable_name: out.to_string(),
+        arguments: vec![number("a", a), number("b", b)],
+    }
+}
+
+/// Add 1 to the variable, for endpoints with the url `/add/{x}`
+fn add_one(config: &Config, out: &str, variable: &str) -> Instruction {
+    Instruction::CallMethod {
+        id: Uuid::new_v4(),
+        config: config.id,
+        method: "add".to_string(),
+        out_variable_name: out.to_string(),
+        arguments: vec![number("a", "1"), parameter("b", variable)],
+    }
+}
+
+fn json_return(variable: &str) -> Instruction {
+    Instruction::JsonReturn {
+        id: Uuid::new_v4(),
+        arg_type: ArgType::Parameter,
+        arg_type_value: variable.to_string(),
+    }
+}
+
+fn position(source: &str, text: &str) -> usize {
+    source
+        .find(text)
+        .unwrap_or_else(|| panic!("{:?} not found in\n{}", text, source))
+}
+
+#[test]
+fn create_and_generate() {
+    let mut api = Api::new();
+    let adder = api.add_adder();
+    let endpoint = api.create_endpoint(vec![add(&adder, "out", "1", "2"), json_return("out")]);
+    assert_eq!(1, endpoint.version);
+
+    let endpoints: Value = api.get("/api/endpoints");
+    assert_eq!(1, endpoints["configs"].as_array().unwrap().len());
+    assert_eq!(1, endpoints["endpoints"].as_array().unwrap().len());
+    let loaded: Endpoint = api.get(&format!("/api/endpoints/{}", endpoint.id));
+    assert_eq!(endpoint.instructions, loaded.instructions);
+
+    let source = api.generate(&endpoint);
+    assert!(source.contains("== src/lib.rs"));
+    assert!(source.contains(".route(\"/add\", actix_web::http::Method::GET, add)"));
+    let call = position(&source, "let out = adder::add(1, 2);");
+    assert!(call < position(&source, "actix_web::Json(out)"));
+}
+
+/// The versions and authors of the revisions of an endpoint, newest first
+fn revisions(api: &mut Api, endpoint: &Endpoint) -> Vec<(i64, String)> {
+    let revisions: Value = api.get(&format!("/api/endpoints/{}/revisions", endpoint.id));
+    revisions
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| (r["version"].as_i64().unwrap(), r["author"].as_str().unwrap().to_string()))
+        .collect()
+}
+
+#[test]
+fn update_requires_the_current_version() {
+    for (name, mut api) in stores() {
+        let adder = api.add_adder();
+        let mut endpoint =
+            api.create_endpoint(vec![add(&adder, "out", "1", "2"), json_return("out")]);
+        let path = format!("/api/endpoints/{}", endpoint.id);
+
+        let stale = endpoint.clone();
+        endpoint.instructions[0] = add(&adder, "out", "3", "4");
+        let endpoint: Endpoint = api.json(Method::PUT, &path, Some(&endpoint));
+        assert_eq!(2, endpoint.version, "{}", name);
+        assert!(api.generate(&endpoint).contains("let out = adder::add(3, 4);"));
+
+        let (status, error) = api.error(Method::PUT, &path, Some(&stale));
+        assert_eq!(StatusCode::CONFLICT, status, "{}", name);
+        assert_eq!("conflict", error);
+
+        let loaded: Endpoint = api.get(&path);
+        assert_eq!(endpoint.instructions, loaded.instructions, "{}", name);
+        let alice = "alice".to_string();
+        assert_eq!(vec![(2, alice.clone()), (1, alice)], revisions(&mut api, &endpoint));
+    }
+}
+
+#[test]
+fn diff_and_restore_revisions() {
+    for (name, mut api) in stores() {
+        let adder = api.add_adder();
+        let first = api.create_endpoint(vec![
+            add(&adder, "a", "1", "2"),
+            add(&adder, "b", "3", "4"),
+            json_return("a"),
+        ]);
+        let path = format!("/api/endpoints/{}", first.id);
+        let mut second = first.clone();
+        second.description = "Add more numbers".to_string();
+        second.instructions.swap(0, 1);
+        let second: Endpoint = api.json(Method::PUT, &path, Some(&second));
+
+        let diff: Value = api.get(&format!("{}/revisions/1/diff/2", path));
+        assert_eq!(1, diff["fields"].as_array().unwrap().len(), "{}", name);
+        assert_eq!("description", diff["fields"][0]["field"]);
+        let changes = diff["instructions"].as_array().unwrap();
+        assert_eq!(1, changes.len(), "{}", name);
+        assert_eq!("moved", changes[0]["change"]);
+
+        let restore = format!("{}/revisions/1/restore", path);
+        api.login("carol");
+        let (status, error) = api.error(Method::POST, &restore, None::<()>);
+        assert_eq!(StatusCode::FORBIDDEN, status, "{}", name);
+        assert_eq!("forbidden", error);
+        api.login("alice");
+        let restored: Endpoint = api.json(Method::POST, &restore, None::<()>);
+        assert_eq!(3, restored.version, "{}", name);
+        assert_eq!(first.description, restored.description);
+        assert_eq!(first.instructions, restored.instructions);
+        let loaded: Endpoint = api.get(&path);
+        assert_eq!(first.instructions, loaded.instructions, "{}", name);
+        let diff: Value = api.get(&format!("{}/revisions/1/diff/3", path));
+        assert!(diff["fields"].as_array().unwrap().is_empty(), "{}", name);
+        assert!(diff["instructions"].as_array().unwrap().is_empty(), "{}", name);
+        assert_eq!(second.version + 1, restored.version);
+
+        let missing = format!("{}/revisions/9/restore", path);
+        let (status, error) = api.error(Method::POST, &missing, None::<()>);
+        assert_eq!(StatusCode::NOT_FOUND, status, "{}", name);
+        assert_eq!("not_found", error);
+    }
+}
+
+#[test]
+fn deleting_an_endpoint_removes_its_revisions_and_routes() {
+    for (name, mut api) in stores() {
+        let adder = api.add_adder();
+        let endpoint = api.create_endpoint(vec![add(&adder, "out", "1", "2"), json_return("out")]);
+        let path = format!("/api/endpoints/{}", endpoint.id);
+        let _: Endpoint = api.json(Method::PUT, &path, Some(&endpoint));
+        let service = new_service(vec![(&endpoint, "/api")]);
+        let _: Service = api.json(Method::POST, "/api/services", Some(&service));
+
+        let (status, _) = api.send(Method::DELETE, &path, None::<()>);
+        assert_eq!(StatusCode::NO_CONTENT, status, "{}", name);
+        let services: Vec<Service> = api.get("/api/services");
+        assert!(services[0].endpoints.is_empty(), "{}", name);
+
+        // Created again with the same id, the endpoint starts without the old revisions
+        let endpoint = Endpoint {
+            version: 0,
+            ..endpoint
+        };
+        let endpoint: Endpoint = api.json(Method::POST, "/api/endpoints", Some(&endpoint));
+        assert_eq!(1, endpoint.version, "{}", name);
+        assert_eq!(vec![(1, "alice".to_string())], revisions(&mut api, &endpoint));
+    }
+}
+
+#[test]
+fn names_are_unique() {
+    for (name, mut api) in stores() {
+        let adder = api.add_adder();
+        let form = ConfigForm {
+            name: "adder".to_string(),
+            path: "../modules/adder".to_string(),
+        };
+        let (status, error) = api.error(Method::POST, "/api/configs", Some(form));
+        assert_eq!(StatusCode::CONFLICT, status, "{}", name);
+        assert_eq!("conflict", error);
+
+        api.create_endpoint(vec![add(&adder, "out", "1", "2"), json_return("out")]);
+        let mut endpoint = new_endpoint("/subtract", Vec::new());
+        endpoint.description = "Subtract two numbers".to_string();
+        let (status, error) = api.error(Method::POST, "/api/endpoints", Some(&endpoint));
+        assert_eq!(StatusCode::CONFLICT, status, "{}", name);
+        assert_eq!("conflict", error);
+        let endpoints: Value = api.get("/api/endpoints");
+        assert_eq!(1, endpoints["endpoints"].as_array().unwrap().len(), "{}", name);
+
+        api.login("admin");
+        let form = vec![("name", "alice"), ("role", "Read"), ("password", "secret")]
+            .into_iter()
+            .collect::<HashMap<_, _>>();
+        let (status, error) = api.error(Method::POST, "/api/users", Some(&form));
+        assert_eq!(StatusCode::CONFLICT, status, "{}", name);
+        assert_eq!("conflict", error);
+    }
+}
+
+#[test]
+fn reorder_instructions() {
+    let mut api = Api::new();
+    let adder = api.add_adder();
+    let endpoint = api.create_endpoint(vec![
+        add(&adder, "first", "1", "2"),
+        add(&adder, "second", "3", "4"),
+        json_return("second"),
+    ]);
+    let source = api.generate(&endpoint);
+    assert!(position(&source, "adder::add(1, 2)") < position(&source, "adder::add(3, 4)"));
+
+    let ids = endpoint
+        .instructions
+        .iter()
+        .map(|i| i.get_id())
+        .collect::<Vec<_>>();
+    let path = format!("/api/endpoints/{}/instructions", endpoint.id);
+    let order = vec![ids[1], ids[0], ids[2]];
+    let reordered: Endpoint = api.json(Method::PUT, &path, Some(&order));
+    assert_eq!(2, reordered.version);
+    let loaded: Endpoint = api.get(&format!("/api/endpoints/{}", endpoint.id));
+    let loaded_ids = loaded
+        .instructions
+        .iter()
+        .map(|i| i.get_id())
+        .collect::<Vec<_>>();
+    assert_eq!(order, loaded_ids);
+
+    let source = api.generate(&loaded);
+    assert!(position(&source, "adder::add(3, 4)") < position(&source, "adder::add(1, 2)"));
+
+    let (status, _) = api.error(Method::PUT, &path, Some(&ids[..2]));
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+}
+
+#[test]
+fn insert_and_delete_instructions() {
+    let mut api = Api::new();
+    let adder = api.add_adder();
+    let endpoint = api.create_endpoint(vec![add(&adder, "out", "1", "2"), json_return("out")]);
+    let path = format!("/api/endpoints/{}/instructions", endpoint.id);
+
+    let instruction = add(&adder, "unused", "5", "6");
+    let body = InsertInstruction {
+        index: Some(0),
+        instruction: &instruction,
+    };
+    let endpoint: Endpoint = api.json(Method::POST, &path, Some(body));
+    assert_eq!(3, endpoint.instructions.len());
+    assert_eq!(instruction, endpoint.instructions[0]);
+    let source = api.generate(&endpoint);
+    assert!(position(&source, "adder::add(5, 6)") < position(&source, "adder::add(1, 2)"));
+
+    let instruction = format!("{}/{}", path, instruction.get_id());
+    let endpoint: Endpoint = api.json(Method::DELETE, &instruction, None::<()>);
+    assert_eq!(2, endpoint.instructions.len());
+    assert!(!api.generate(&endpoint).contains("adder::add(5, 6)"));
+
+    let (status, error) = api.error(Method::DELETE, &instruction, None::<()>);
+    assert_eq!(StatusCode::NOT_FOUND, status);
+    assert_eq!("not_found", error);
+}
+
+#[test]
+fn instructions_are_appended_without_an_index() {
+    let mut api = Api::new();
+    let adder = api.add_adder();
+    let endpoint = api.create_endpoint(vec![add(&adder, "out", "1", "2")]);
+    let path = format!("/api/endpoints/{}/instructions", endpoint.id);
+    let instruction = json_return("out");
+    let body = InsertInstruction {
+        index: None,
+        instruction: &instruction,
+    };
+    let endpoint: Endpoint = api.json(Method::POST, &path, Some(body));
+    assert_eq!(2, endpoint.instructions.len());
+    assert_eq!(instruction, endpoint.instructions[1]);
+    // An index past the end appends as well
+    let instruction = add(&adder, "unused", "5", "6");
+    let body = InsertInstruction {
+        index: Some(10),
+        instruction: &instruction,
+    };
+    let endpoint: Endpoint = api.json(Method::POST, &path, Some(body));
+    assert_eq!(3, endpoint.instructions.len());
+    assert_eq!(instruction, endpoint.instructions[2]);
+
+    let path = format!("/api/endpoints/{}/instructions", Uuid::new_v4());
+    let instruction = json_return("out");
+    let body = InsertInstruction {
+        index: None,
+        instruction: &instruction,
+    };
+    let (status, error) = api.error(Method::POST, &path, Some(body));
+    assert_eq!(StatusCode::NOT_FOUND, status);
+    assert_eq!("not_found", error);
+}
+
+#[test]
+fn configs_are_resources() {
+    let mut api = Api::new();
+    let adder = api.add_adder();
+    let path = format!("/api/configs/{}", adder.id);
+    let loaded: Config = api.get(&path);
+    assert_eq!("adder", loaded.name);
+
+    let form = ConfigForm {
+        name: "calculator".to_string(),
+        path: "../modules/adder".to_string(),
+    };
+    let renamed: Config = api.json(Method::PUT, &path, Some(form));
+    assert_eq!(adder.id, renamed.id);
+    let configs: Vec<Config> = api.get("/api/configs");
+    assert_eq!(1, configs.len());
+    assert_eq!("calculator", configs[0].name);
+
+    let (status, _) = api.send(Method::DELETE, &path, None::<()>);
+    assert_eq!(StatusCode::NO_CONTENT, status);
+    for method in [Method::GET, Method::DELETE] {
+        let (status, error) = api.error(method, &path, None::<()>);
+        assert_eq!(StatusCode::NOT_FOUND, status);
+        assert_eq!("not_found", error);
+    }
+    let path = format!("/api/endpoints/{}", Uuid::new_v4());
+    let (status, error) = api.error(Method::GET, &path, None::<()>);
+    assert_eq!(StatusCode::NOT_FOUND, status);
+    assert_eq!("not_found", error);
+}
+
+#[test]
+fn builds_stream_their_diagnostics() {
+    let output_dir = env::temp_dir().join(format!("web_api_frontend_builds_{}", Uuid::new_v4()));
+    let mut api = Api::building(&output_dir);
+    let adder = api.add_adder();
+    let endpoint = api.create_endpoint(vec![add(&adder, "out", "1", "2"), json_return("out")]);
+    let path = format!("/api/endpoints/{}/build", endpoint.id);
+
+    let (status, error) = api.error(Method::POST, &format!("{}?command=run", path), None::<()>);
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    assert_eq!("bad_request", error);
+    api.login("carol");
+    let (status, _) = api.error(Method::POST, &path, None::<()>);
+    assert_eq!(StatusCode::FORBIDDEN, status);
+    api.login("alice");
+
+    let (status, body) = api.send(Method::POST, &path, None::<()>);
+    assert_eq!(StatusCode::OK, status);
+    let events = String::from_utf8(body)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    let (finished, diagnostics) = events.split_last().unwrap();
+    assert_eq!("finished", finished["type"], "{:?}", events);
+    assert_eq!(false, finished["success"]);
+    assert_eq!(false, finished["timed_out"]);
+    assert!(diagnostics.iter().all(|e| e["type"] == "diagnostic"));
+    // `actix_web::Json` does not exist in the empty crate
+    let json = diagnostics
+        .iter()
+        .find(|e| e["instruction"] == 1)
+        .unwrap_or_else(|| panic!("No diagnostic of the JsonReturn in {:?}", events));
+    assert_eq!(endpoint.id.to_string(), json["endpoint"]);
+    assert_eq!("error", json["diagnostic"]["level"]);
+
+    // The build has a target directory of its own
+    assert!(output_dir.join(endpoint.id.to_string()).join("target").is_dir());
+    fs::remove_dir_all(&output_dir).unwrap();
+}
+
+#[test]
+fn audit_log_records_changes() {
+    let mut api = Api::new();
+    let adder = api.add_adder();
+    let endpoint = api.create_endpoint(vec![add(&adder, "out", "1", "2"), json_return("out")]);
+    let instruction = add(&adder, "unused", "5", "6");
+    let body = InsertInstruction {
+        index: Some(0),
+        instruction: &instruction,
+    };
+    let path = format!("/api/endpoints/{}/instructions", endpoint.id);
+    let _: Endpoint = api.json(Method::POST, &path, Some(body));
+    api.login("admin");
+    let path = format!("/api/endpoints/{}", endpoint.id);
+    let (status, _) = api.send(Method::DELETE, &path, None::<()>);
+    assert_eq!(StatusCode::NO_CONTENT, status);
+
+    let log: Vec<Value> = api.get(&format!("/api/audit?endpoint={}", endpoint.id));
+    let changes = log
+        .iter()
+        .map(|e| (e["action"].as_str().unwrap(), e["actor"].as_str().unwrap()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec![("delete", "admin"), ("update", "alice"), ("create", "alice")],
+        changes
+    );
+    assert!(log[0]["after"].is_null());
+    assert_eq!(2, log[1]["before"]["instructions"].as_array().unwrap().len());
+    assert_eq!(3, log[1]["after"]["instructions"].as_array().unwrap().len());
+    assert!(log[2]["before"].is_null());
+
+    let all: Vec<Value> = api.get("/api/audit");
+    assert_eq!(4, all.len());
+    assert_eq!(adder.id.to_string(), all[3]["config"]);
+    let deleted_at = log[0]["created_at"].as_str().unwrap();
+    let before: Vec<Value> = api.get(&format!("/api/audit?to={}", deleted_at));
+    assert_eq!(3, before.len());
+    let after: Vec<Value> = api.get(&format!("/api/audit?from={}", deleted_at));
+    assert_eq!(1, after.len());
+
+    let (status, _) = api.error(Method::GET, "/api/audit?from=yesterday", None::<()>);
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+}
+
+#[test]
+fn changes_are_published() {
+    let mut api = Api::new();
+    let events = api.events.subscribe();
+    let alice: User = api.get("/api/me");
+    let adder = api.add_adder();
+    let endpoint = api.create_endpoint(vec![add(&adder, "out", "1", "2"), json_return("out")]);
+    let path = format!("/api/endpoints/{}", endpoint.id);
+    let updated: Endpoint = api.json(Method::PUT, &path, Some(&endpoint));
+    // A save that fails is not published
+    let (status, _) = api.error(Method::PUT, &path, Some(&endpoint));
+    assert_eq!(StatusCode::CONFLICT, status);
+    let (status, _) = api.send(Method::DELETE, &path, None::<()>);
+    assert_eq!(StatusCode::NO_CONTENT, status);
+
+    let events = events.take(4).collect().wait().unwrap();
+    assert_eq!(
+        vec![
+            Event::ConfigSaved {
+                id: adder.id,
+                name: adder.name.clone(),
+                actor: "alice".to_string(),
+            },
+            Event::endpoint_saved(&endpoint, &alice),
+            Event::endpoint_saved(&updated, &alice),
+            Event::EndpointDeleted {
+                id: endpoint.id,
+                actor: "alice".to_string(),
+            },
+        ],
+        events
+    );
+    assert_eq!(2, updated.version);
+}
+
+#[test]
+fn invalid_arguments_are_not_saved() {
+    let mut api = Api::new();
+    let adder = api.add_adder();
+    let endpoint = new_endpoint("/add", vec![add(&adder, "out", "one", "2"), json_return("out")]);
+    let (status, error) = api.error(Method::POST, "/api/endpoints", Some(&endpoint));
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    assert_eq!("bad_request", error);
+
+    let endpoints: Value = api.get("/api/endpoints");
+    assert!(endpoints["endpoints"].as_array().unwrap().is_empty());
+    let generate = format!("/api/generate/{}", endpoint.id);
+    let (status, _) = api.send(Method::GET, &generate, None::<()>);
+    assert_eq!(StatusCode::NOT_FOUND, status);
+}
+
+#[test]
+fn numbers_and_floats_are_kept() {
+    for (name, mut api) in stores() {
+        let adder = api.add_adder();
+        let float = Instruction::JsonReturn {
+            id: Uuid::new_v4(),
+            arg_type: ArgType::Float,
+            arg_type_value: "2.5".to_string(),
+        };
+        let endpoint = api.create_endpoint(vec![add(&adder, "out", "-1", " 2"), float]);
+        let loaded: Endpoint = api.get(&format!("/api/endpoints/{}", endpoint.id));
+        assert_eq!(endpoint.instructions, loaded.instructions, "{}", name);
+        let source = api.generate(&loaded);
+        assert!(source.contains("let out = adder::add(-1, 2);"), "{}", name);
+        assert!(source.contains("actix_web::Json(2.5f32)"), "{}", name);
+    }
+}
+
+#[test]
+fn instruction_ids_are_unique() {
+    let mut api = Api::new();
+    let adder = api.add_adder();
+    let mut endpoint = api.create_endpoint(vec![add(&adder, "out", "1", "2"), json_return("out")]);
+    let path = format!("/api/endpoints/{}", endpoint.id);
+    let copy = endpoint.instructions[0].clone();
+    endpoint.instructions.insert(1, copy);
+    let (status, error) = api.error(Method::PUT, &path, Some(&endpoint));
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    assert_eq!("bad_request", error);
+
+    let loaded: Endpoint = api.get(&path);
+    assert_eq!(1, loaded.version);
+    assert_eq!(2, loaded.instructions.len());
+}
+
+#[test]
+fn services_group_routes_in_scopes() {
+    let mut api = Api::new();
+    let adder = api.add_adder();
+    let sum = api.create_endpoint(vec![add(&adder, "out", "1", "2"), json_return("out")]);
+    let one = Endpoint {
+        name: "Add one".to_string(),
+        description: "Add one to x".to_string(),
+        ..new_endpoint("/add/{x}", vec![add_one(&adder, "out", "x"), json_return("out")])
+    };
+    let one: Endpoint = api.json(Method::POST, "/api/endpoints", Some(&one));
+
+    let service = new_service(vec![(&sum, "/api"), (&one, "/api")]);
+    let _: Service = api.json(Method::POST, "/api/services", Some(&service));
+    let path = format!("/api/services/{}/generate", service.id);
+    let (status, body) = api.send(Method::GET, &path, None::<()>);
+    assert_eq!(StatusCode::OK, status);
+    let source = String::from_utf8(body).unwrap();
+    assert_eq!(1, source.matches(".scope(\"/api\"").count());
+    let scope = position(&source, ".scope(\"/api\"");
+    assert!(scope < position(&source, ".route(\"/add\", actix_web::http::Method::GET, add)"));
+    assert!(scope < position(&source, "Method::GET, add_one)"));
+    assert!(source.contains("\"/api/add/{x}\""));
+
+    // `/v1` + `/add` is the route of an endpoint outside of any scope
+    let root = Endpoint {
+        name: "add v1".to_string(),
+        description: "Add two numbers, the first version".to_string(),
+        ..new_endpoint("/v1/add", vec![add(&adder, "out", "1", "2"), json_return("out")])
+    };
+    let root: Endpoint = api.json(Method::POST, "/api/endpoints", Some(&root));
+    let service = Service {
+        name: "versions".to_string(),
+        ..new_service(vec![(&sum, "/v1"), (&root, "")])
+    };
+    let _: Service = api.json(Method::POST, "/api/services", Some(&service));
+    let path = format!("/api/services/{}/generate", service.id);
+    let (status, error) = api.error(Method::GET, &path, None::<()>);
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    assert_eq!("bad_request", error);
+}
+
+#[test]
+fn services_choose_their_backend() {
+    let mut api = Api::new();
+    let adder = api.add_adder();
+    let endpoint = api.create_endpoint(vec![add(&adder, "out", "1", "2"), json_return("out")]);
+    let service = Service {
+        backend: "cgi".to_string(),
+        ..new_service(vec![(&endpoint, "/api")])
+    };
+    let _: Service = api.json(Method::POST, "/api/services", Some(&service));
+    let services: Vec<Service> = api.get("/api/services");
+    assert_eq!("cgi", services[0].backend);
+    let path = format!("/api/services/{}/generate", service.id);
+    let (status, body) = api.send(Method::GET, &path, None::<()>);
+    assert_eq!(StatusCode::OK, status);
+    let source = String::from_utf8(body).unwrap();
+    assert!(source.contains("pub fn handle(path: &str) -> (u16, String) {"));
+    assert!(source.contains("match_route(\"/api/add\", path)"));
+
+    let service = Service {
+        backend: "rocket".to_string(),
+        ..new_service(vec![(&endpoint, "/api")])
+    };
+    let (status, error) = api.error(Method::POST, "/api/services", Some(&service));
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    assert_eq!("bad_request", error);
+    assert_eq!(1, api.get::<Vec<Service>>("/api/services").len());
+}
+
+#[test]
+fn failed_service_saves_change_nothing() {
+    for (name, mut api) in stores() {
+        let adder = api.add_adder();
+        let endpoint = api.create_endpoint(vec![add(&adder, "out", "1", "2"), json_return("out")]);
+        let mut service = new_service(vec![(&endpoint, "/api")]);
+        let _: Service = api.json(Method::POST, "/api/services", Some(&service));
+
+        service.name = "renamed".to_string();
+        service.endpoints[0].endpoint = Uuid::new_v4();
+        let (status, error) = api.error(Method::POST, "/api/services", Some(&service));
+        assert_eq!(StatusCode::CONFLICT, status, "{}", name);
+        assert_eq!("conflict", error);
+
+        let services: Vec<Service> = api.get("/api/services");
+        assert_eq!(1, services.len(), "{}", name);
+        assert_eq!("calculator", services[0].name);
+        assert_eq!(endpoint.id, services[0].endpoints[0].endpoint);
+        let path = format!("/api/services/{}/generate", service.id);
+        let (status, _) = api.send(Method::GET, &path, None::<()>);
+        assert_eq!(StatusCode::OK, status, "{}", name);
+    }
+}
+
+#[test]
+fn a_missing_module_is_an_error() {
+    for (name, mut api) in stores() {
+        let dir = env::temp_dir().join(format!("web_api_frontend_module_{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let module = dir.join("module.json");
+        fs::copy("../modules/adder/module.json", &module).unwrap();
+        let form = ConfigForm {
+            name: "moved".to_string(),
+            path: dir.to_str().unwrap().to_string(),
+        };
+        let config: Config = api.json(Method::POST, "/api/configs", Some(form));
+
+        fs::remove_file(&module).unwrap();
+        let (status, error) = api.error(Method::GET, "/api/configs", None::<()>);
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, status, "{}", name);
+        assert_eq!("internal", error);
+        let path = format!("/api/configs/{}", config.id);
+        let (status, _) = api.error(Method::GET, &path, None::<()>);
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, status, "{}", name);
+
+        // The store keeps working once the module is back
+        fs::copy("../modules/adder/module.json", &module).unwrap();
+        let configs: Vec<Config> = api.get("/api/configs");
+        assert_eq!(1, configs.len(), "{}", name);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[test]
+fn broken_endpoints_are_reported_and_fixed() {
+    for (name, mut api) in stores() {
+        let dir = env::temp_dir().join(format!("web_api_frontend_module_{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let module = dir.join("module.json");
+        let mut json: Value =
+            serde_json::from_str(&fs::read_to_string("../modules/adder/module.json").unwrap())
+                .unwrap();
+        fs::write(&module, json.to_string()).unwrap();
+        let form = ConfigForm {
+            name: "renamed".to_string(),
+            path: dir.to_str().unwrap().to_string(),
+        };
+        let config: Config = api.json(Method::POST, "/api/configs", Some(form));
+        let mut endpoint = new_endpoint("/add/{x}", vec![add_one(&config, "y", "x")]);
+        endpoint = api.json(Method::POST, "/api/endpoints", Some(endpoint));
+        let broken: Vec<Value> = api.get("/api/endpoints/broken");
+        assert!(broken.is_empty(), "{}", name);
+
+        // Rename `add` to `sum` and its input `b` to `y`
+        json["methods"][0]["name"] = Value::from("sum");
+        json["methods"][0]["renamed_from"] = Value::from(vec!["add"]);
+        json["methods"][0]["input"][1]["name"] = Value::from("y");
+        json["methods"][0]["input"][1]["renamed_from"] = Value::from(vec!["b"]);
+        fs::write(&module, json.to_string()).unwrap();
+        let broken: Vec<Value> = api.get("/api/endpoints/broken");
+        assert_eq!(1, broken.len(), "{}", name);
+        assert_eq!(endpoint.id.to_string(), broken[0]["id"]);
+        assert_eq!(true, broken[0]["fixable"]);
+        let problems = &broken[0]["instructions"][0]["problems"];
+        assert_eq!("missing_method", problems[0]["problem"], "{}", name);
+        assert_eq!("sum", problems[0]["renamed_to"]);
+
+        let path = format!("/api/endpoints/{}/fix", endpoint.id);
+        let result: Value = api.json(Method::POST, &path, None::<()>);
+        assert_eq!(2, result["fixed"], "{}", name);
+        assert_eq!(Value::Null, result["remaining"]);
+        assert_eq!(endpoint.version + 1, result["endpoint"]["version"]);
+        let fixed: Endpoint = api.get(&format!("/api/endpoints/{}", endpoint.id));
+        match &fixed.instructions[0] {
+            Instruction::CallMethod {
+                method, arguments, ..
+            } => {
+                assert_eq!("sum", method);
+                assert_eq!(vec!["a", "y"], arguments.iter().map(|a| &a.name).collect::<Vec<_>>());
+            }
+            instruction => panic!("Expected a method call, got {:?}", instruction),
+        }
+        let broken: Vec<Value> = api.get("/api/endpoints/broken");
+        assert!(broken.is_empty(), "{}", name);
+
+        // A type change can not be fixed automatically
+        let input = json["methods"][0]["input"][0]["type"].as_object_mut().unwrap();
+        input.insert("type".to_string(), Value::from("string"));
+        input.remove("between");
+        fs::write(&module, json.to_string()).unwrap();
+        let broken: Vec<Value> = api.get("/api/endpoints/broken");
+        assert_eq!(false, broken[0]["fixable"], "{}", name);
+        let result: Value = api.json(Method::POST, &path, None::<()>);
+        assert_eq!(0, result["fixed"], "{}", name);
+        assert_eq!(fixed.version, result["endpoint"]["version"]);
+        let problems = &result["remaining"]["instructions"][0]["problems"];
+        assert_eq!("type_mismatch", problems[0]["problem"], "{}", name);
+        assert_eq!("a", problems[0]["name"]);
+
+        let path = format!("/api/endpoints/{}/fix", Uuid::new_v4());
+        let (status, _) = api.error(Method::POST, &path, None::<()>);
+        assert_eq!(StatusCode::NOT_FOUND, status, "{}", name);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
+
+#[test]
+fn projects_are_exported_and_imported() {
+    let mut exported = None;
+    for (name, mut api) in stores() {
+        let config = api.add_adder();
+        let mut endpoint = new_endpoint("/add/{x}", vec![add_one(&config, "y", "x")]);
+        endpoint = api.json(Method::POST, "/api/endpoints", Some(endpoint));
+        let other = new_endpoint("/other", vec![json_return("1")]);
+        let other: Endpoint = api.json(
+            Method::POST,
+            "/api/endpoints",
+            Some(Endpoint {
+                name: "other".to_string(),
+                description: "Another endpoint".to_string(),
+                ..other
+            }),
+        );
+
+        let (status, headers, yaml) = api.raw(
+            Method::GET,
+            "/api/project?format=yaml",
+            "text/plain",
+            Vec::new(),
+        );
+        assert_eq!(StatusCode::OK, status, "{}", name);
+        assert_eq!("application/x-yaml", headers["Content-Type"]);
+        assert_eq!(
+            "attachment; filename=\"project.yaml\"",
+            headers["Content-Disposition"]
+        );
+        let path = format!("/api/project?endpoints={}", endpoint.id);
+        let mut project: Value = api.get(&path);
+        assert_eq!("adder", project["modules"][0]["name"], "{}", name);
+        assert_eq!(1, project["endpoints"].as_array().unwrap().len());
+        assert_eq!("/add/{x}", project["endpoints"][0]["url"]);
+        let call = &project["endpoints"][0]["instructions"][0]["CallMethod"];
+        assert_eq!("adder", call["module"]);
+        let all: Value = api.get("/api/project");
+        assert_eq!(2, all["endpoints"].as_array().unwrap().len(), "{}", name);
+        let (status, _) = api.error(Method::GET, "/api/project?format=xml", None::<()>);
+        assert_eq!(StatusCode::BAD_REQUEST, status, "{}", name);
+        let (status, _) = api.error(Method::GET, "/api/project?endpoints=add", None::<()>);
+        assert_eq!(StatusCode::BAD_REQUEST, status, "{}", name);
+
+        // Only writers import
+        project["endpoints"][0]["description"] = Value::from("Add one");
+        let json = serde_json::to_vec(&project).unwrap();
+        api.login("carol");
+        let (status, _, body) = api.raw(
+            Method::POST,
+            "/api/project",
+            "application/json",
+            json.clone(),
+        );
+        assert_eq!(StatusCode::FORBIDDEN, status, "{}", name);
+        let body: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!("forbidden", body["error"]);
+        let stored: Endpoint = api.get(&format!("/api/endpoints/{}", endpoint.id));
+        assert_eq!(endpoint.version, stored.version, "{}", name);
+
+        api.login("alice");
+        let (status, _, body) = api.raw(
+            Method::POST,
+            "/api/project",
+            "application/json",
+            json.clone(),
+        );
+        assert_eq!(StatusCode::OK, status, "{}", name);
+        let result: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(0, result["created_configs"].as_array().unwrap().len());
+        assert_eq!("updated", result["endpoints"][0]["status"], "{}", name);
+        assert_eq!(endpoint.version + 1, result["endpoints"][0]["version"]);
+        assert_eq!(0, result["broken"].as_array().unwrap().len());
+        let stored: Endpoint = api.get(&format!("/api/endpoints/{}", endpoint.id));
+        assert_eq!("Add one", stored.description, "{}", name);
+        let (_, _, body) = api.raw(Method::POST, "/api/project", "application/json", json);
+        let result: Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!("unchanged", result["endpoints"][0]["status"], "{}", name);
+
+        let (status, _, _) = api.raw(
+            Method::POST,
+            "/api/project",
+            "application/json",
+            yaml.clone(),
+        );
+        assert_eq!(StatusCode::BAD_REQUEST, status, "{}", name);
+
+        exported = Some((yaml, other));
+    }
+
+    // A new instance creates the configs and endpoints of the project
+    let (yaml, other) = exported.unwrap();
+    let mut api = Api::new();
+    let (status, _, body) = api.raw(Method::POST, "/api/project", "application/x-yaml", yaml);
+    assert_eq!(StatusCode::OK, status);
+    let result: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        vec!["adder"],
+        *result["created_configs"].as_array().unwrap()
+    );
+    assert_eq!("created", result["endpoints"][0]["status"]);
+    assert_eq!("created", result["endpoints"][1]["status"]);
+    let imported: Endpoint = api.get(&format!("/api/endpoints/{}", other.id));
+    assert_eq!(other.instructions, imported.instructions);
+}
+
+#[test]
+fn invalid_requests_are_bad_requests() {
+    let mut api = Api::new();
+    let form = ConfigForm {
+        name: "missing".to_string(),
+        path: "../modules/missing".to_string(),
+    };
+    let (status, error) = api.error(Method::POST, "/api/configs", Some(form));
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    assert_eq!("bad_request", error);
+
+    let mut form = HashMap::new();
+    form.insert("name", 1);
+    let (status, error) = api.error(Method::POST, "/api/endpoints", Some(form));
+    assert_eq!(StatusCode::BAD_REQUEST, status);
+    assert_eq!("bad_request", error);
+
+    let mut request = api.server.client(Method::POST, "/api/endpoints");
+    request.header("Cookie", api.session.clone().unwrap().as_str());
+    let request = request
+        .content_type("application/json")
+        .body("{\"name\":")
+        .unwrap();
+    let response = api.server.execute(request.send()).unwrap();
+    assert_eq!(StatusCode::BAD_REQUEST, response.status());
+    let body = api.server.execute(response.body()).unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!("bad_request", body["error"]);
+}
+
+#[test]
+fn configs_in_use_cannot_be_deleted() {
+    let mut api = Api::new();
+    let adder = api.add_adder();
+    let endpoint = api.create_endpoint(vec![add(&adder, "out", "1", "2"), json_return("out")]);
+
+    let config = format!("/api/configs/{}", adder.id);
+    let (status, error) = api.error(Method::DELETE, &config, None::<()>);
+    assert_eq!(StatusCode::CONFLICT, status);
+    assert_eq!("conflict", error);
+
+    let path = format!("/api/endpoints/{}", endpoint.id);
+    let (status, _) = api.send(Method::DELETE, &path, None::<()>);
+    assert_eq!(StatusCode::NO_CONTENT, status);
+    let (status, _) = api.send(Method::DELETE, &config, None::<()>);
+    assert_eq!(StatusCode::NO_CONTENT, status);
+}
+
+#[test]
+fn preview_does_not_save() {
+    let mut api = Api::new();
+    let adder = api.add_adder();
+    let mut endpoint = new_endpoint("/add", vec![add(&adder, "out", "1", "2"), json_return("out")]);
+    let preview: Value = api.json(Method::POST, "/api/preview", Some(&endpoint));
+    assert!(preview["diagnostics"].as_array().unwrap().is_empty());
+    let source = preview["files"]["src/lib.rs"].as_str().unwrap();
+    assert!(source.contains("let out = adder::add(1, 2);"));
+
+    endpoint.instructions[0] = add(&adder, "out", "one", "2");
+    let preview: Value = api.json(Method::POST, "/api/preview", Some(&endpoint));
+    assert!(preview["files"].as_object().unwrap().is_empty());
+    let diagnostics = preview["diagnostics"].as_array().unwrap();
+    assert_eq!(1, diagnostics.len());
+    assert_eq!(0, diagnostics[0]["index"].as_u64().unwrap());
+
+    let endpoints: Value = api.get("/api/endpoints");
+    assert!(endpoints["endpoints"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn run_with_the_interpreter() {
+    let mut api = Api::new();
+    let adder = api.add_adder();
+    let instructions = vec![add_one(&adder, "out", "x"), json_return("out")];
+    let mut endpoint = new_endpoint("/add/{x}", instructions);
+    let run = RunEndpoint {
+        endpoint: endpoint.clone(),
+        inputs: vec![("x".to_string(), Value::from(41))].into_iter().collect(),
+    };
+    let outcome: Value = api.json(Method::POST, "/api/run", Some(&run));
+    assert_eq!(42, outcome["Return"].as_i64().unwrap());
+
+    endpoint.instructions[0] = add(&adder, "out", "1", "2");
+    let run = RunEndpoint {
+        endpoint,
+        inputs: HashMap::new(),
+    };
+    let outcome: Value = api.json(Method::POST, "/api/run", Some(&run));
+    assert_eq!(3, outcome["Return"].as_i64().unwrap());
+}
+
+#[test]
+fn trace_module_calls() {
+    let mut api = Api::new();
+    let adder = api.add_adder();
+    let instructions = vec![add_one(&adder, "out", "x"), json_return("out")];
+    let endpoint = new_endpoint("/add/{x}", instructions);
+    let run = RunEndpoint {
+        endpoint,
+        inputs: vec![("x".to_string(), Value::from(41))].into_iter().collect(),
+    };
+    let trace: Value = api.json(Method::POST, "/api/trace", Some(&run));
+    assert_eq!(42, trace["outcome"]["Return"].as_i64().unwrap());
+    assert!(trace["error"].is_null());
+    let calls = trace["calls"].as_array().unwrap();
+    assert_eq!(1, calls.len());
+    assert_eq!(0, calls[0]["instruction"].as_u64().unwrap());
+    assert_eq!("add", calls[0]["method"]);
+    assert_eq!(41, calls[0]["inputs"]["b"].as_i64().unwrap());
+    assert_eq!(42, calls[0]["output"].as_i64().unwrap());
+}
+
+#[test]
+fn requests_need_a_session() {
+    let mut api = Api::new();
+    let (status, _) = api.send(Method::POST, "/api/logout", None::<()>);
+    assert_eq!(StatusCode::OK, status);
+    let (status, error) = api.error(Method::GET, "/api/endpoints", None::<()>);
+    assert_eq!(StatusCode::UNAUTHORIZED, status);
+    assert_eq!("unauthorized", error);
+    let (status, _) = api.error(Method::GET, "/api/events", None::<()>);
+    assert_eq!(StatusCode::UNAUTHORIZED, status);
+
+    let login = Login {
+        name: "alice",
+        password: "wrong",
+    };
+    let (status, _) = api.error(Method::POST, "/api/login", Some(login));
+    assert_eq!(StatusCode::UNAUTHORIZED, status);
+    assert!(api.session.is_none());
+
+    let alice = api.login("alice");
+    let me: Value = api.get("/api/me");
+    assert_eq!(alice.id.to_string(), me["id"]);
+    assert!(me.get("password_hash").is_none());
+}
+
+#[test]
+fn sessions_expire_and_logout_clears_them() {
+    let mut api = Api::new();
+    let login = Login {
+        name: "bob",
+        password: "secret",
+    };
+    let body = serde_json::to_vec(&login).unwrap();
+    let (status, headers, _) = api.raw(Method::POST, "/api/login", "application/json", body);
+    assert_eq!(StatusCode::OK, status);
+    let cookie = headers["Set-Cookie"].to_str().unwrap();
+    let attributes = cookie.split("; ").collect::<Vec<_>>();
+    assert!(attributes[0].starts_with("session="), "{}", cookie);
+    for attribute in &["Path=/", "Max-Age=604800", "HttpOnly", "SameSite=Strict"] {
+        assert!(attributes.contains(attribute), "{}", cookie);
+    }
+
+    let (status, headers, _) = api.raw(Method::POST, "/api/logout", "text/plain", Vec::new());
+    assert_eq!(StatusCode::OK, status);
+    let cookie = headers["Set-Cookie"].to_str().unwrap();
+    let attributes = cookie.split("; ").collect::<Vec<_>>();
+    assert_eq!("session=", attributes[0]);
+    for attribute in &["Path=/", "Max-Age=0", "HttpOnly", "SameSite=Strict"] {
+        assert!(attributes.contains(attribute), "{}", cookie);
+    }
+    assert!(attributes.iter().any(|a| a.starts_with("Expires=")), "{}", cookie);
+
+    let (status, _) = api.send(Method::POST, "/api/logout", None::<()>);
+    assert_eq!(StatusCode::OK, status);
+    assert!(api.session.is_none());
+    let (status, _) = api.error(Method::GET, "/api/me", None::<()>);
+    assert_eq!(StatusCode::UNAUTHORIZED, status);
+}
+
+#[test]
+fn readers_cannot_write() {
+    let mut api = Api::new();
+    api.add_adder();
+    api.login("carol");
+    let _: Value = api.get("/api/endpoints");
+    let form = ConfigForm {
+        name: "adder2".to_string(),
+        path: "../modules/adder".to_string(),
+    };
+    let (status, error) = api.error(Method::POST, "/api/configs", Some(form));
+    assert_eq!(StatusCode::FORBIDDEN, status);
+    assert_eq!("forbidden", error);
+
+    let endpoint = new_endpoint("/add", Vec::new());
+    let (status, _) = api.error(Method::POST, "/api/endpoints", Some(endpoint));
+    assert_eq!(StatusCode::FORBIDDEN, status);
+}
+
+#[test]
+fn only_the_owner_changes_an_endpoint() {
+    let mut api = Api::new();
+    let adder = api.add_adder();
+    let endpoint = api.create_endpoint(vec![add(&adder, "out", "1", "2"), json_return("out")]);
+    let alice: User = api.get("/api/me");
+    assert_eq!(Some(alice.id), endpoint.owner);
+
+    let path = format!("/api/endpoints/{}", endpoint.id);
+    let bob = api.login("bob");
+    let (status, _) = api.error(Method::PUT, &path, Some(&endpoint));
+    assert_eq!(StatusCode::FORBIDDEN, status);
+    let (status, _) = api.error(Method::DELETE, &path, None::<()>);
+    assert_eq!(StatusCode::FORBIDDEN, status);
+    // The owner cannot be changed by saving the endpoint either
+    let mut taken = endpoint.clone();
+    taken.owner = Some(bob.id);
+    let (status, _) = api.error(Method::PUT, &path, Some(&taken));
+    assert_eq!(StatusCode::FORBIDDEN, status);
+
+    api.login("admin");
+    let owner = vec![("owner", bob.id)].into_iter().collect::<HashMap<_, _>>();
+    let _: Endpoint = api.json(Method::PUT, &format!("{}/owner", path), Some(owner));
+
+    api.login("bob");
+    let updated: Endpoint = api.json(Method::PUT, &path, Some(&endpoint));
+    assert_eq!(Some(bob.id), updated.owner);
+    let (status, _) = api.send(Method::DELETE, &path, None::<()>);
+    assert_eq!(StatusCode::NO_CONTENT, status);
+}
+
+#[test]
+fn admins_manage_users() {
+    let mut api = Api::new();
+    let (status, _) = api.error(Method::GET, "/api/users", None::<()>);
+    assert_eq!(StatusCode::FORBIDDEN, status);
+
+    api.login("admin");
+    let mut form = vec![("name", "dave"), ("role", "Read"), ("password", "secret")]
+        .into_iter()
+        .collect::<HashMap<_, _>>();
+    let dave: User = api.json(Method::POST, "/api/users", Some(&form));
+    assert_eq!(Role::Read, dave.role);
+    let (status, _) = api.error(Method::POST, "/api/users", Some(&form));
+    assert_eq!(StatusCode::CONFLICT, status);
+
+    form.insert("role", "Write");
+    form.remove("password");
+    let path = format!("/api/users/{}", dave.id);
+    let dave: User = api.json(Method::PUT, &path, Some(&form));
+    assert_eq!(Role::Write, dave.role);
+    let users: Vec<User> = api.get("/api/users");
+    assert_eq!(5, users.len());
+
+    // The password is kept when it is not given
+    assert_eq!(dave.id, api.login("dave").id);
+}
@@ -0,0 +1,200 @@
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::{digest, pbkdf2};
+use std::fmt;
+use std::str::{self, FromStr};
+use uuid::Uuid;
+
+/// What a user may do. Every role may also do everything the roles before it may.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Role {
+    /// Read endpoints, configs and services, and run endpoints with the interpreter
+    Read,
+    /// Create endpoints and change the ones they own, configs and services
+    Write,
+    /// Change every endpoint and manage users
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Read => "Read",
+            Role::Write => "Write",
+            Role::Admin => "Admin",
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Role, String> {
+        match s {
+            "Read" => Ok(Role::Read),
+            "Write" => Ok(Role::Write),
+            "Admin" => Ok(Role::Admin),
+            _ => Err(format!("Unknown role {:?}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct User {
+    pub id: Uuid,
+    pub name: String,
+    pub role: Role,
+    /// See `hash_password`. Never sent to clients.
+    #[serde(skip_serializing, default)]
+    pub password_hash: String,
+}
+
+impl User {
+    pub fn new(name: String, password: &str, role: Role) -> User {
+        User {
+            id: Uuid::new_v4(),
+            name,
+            role,
+            password_hash: hash_password(password),
+        }
+    }
+
+    pub fn check_password(&self, password: &str) -> bool {
+        verify_password(&self.password_hash, password)
+    }
+
+    /// Whether the user may change an endpoint with the given owner.
+    /// Endpoints without an owner, saved before there were users, may only be changed by admins.
+    pub fn can_change(&self, owner: Option<Uuid>) -> bool {
+        match self.role {
+            Role::Admin => true,
+            Role::Write => owner == Some(self.id),
+            Role::Read => false,
+        }
+    }
+}
+
+/// The fields of a user that can be set through the API
+#[derive(Debug, Deserialize)]
+pub struct UserForm {
+    pub name: String,
+    pub role: Role,
+    /// Keeps the current password of an existing user if `None`
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+const ITERATIONS: u32 = 100_000;
+const SALT_LEN: usize = 16;
+
+/// Hash a password with PBKDF2-SHA256 and a random salt,
+/// as `pbkdf2-sha256$<iterations>$<salt>$<hash>` with the salt and hash in hex
+pub fn hash_password(password: &str) -> String {
+    let mut salt = [0; SALT_LEN];
+    SystemRandom::new()
+        .fill(&mut salt)
+        .expect("Could not generate a salt");
+    let mut hash = [0; digest::SHA256_OUTPUT_LEN];
+    pbkdf2::derive(&digest::SHA256, ITERATIONS, &salt, password.as_bytes(), &mut hash);
+    format!("pbkdf2-sha256${}${}${}", ITERATIONS, to_hex(&salt), to_hex(&hash))
+}
+
+/// Check a password against a hash created by `hash_password`
+pub fn verify_password(hash: &str, password: &str) -> bool {
+    let parts = hash.split('$').collect::<Vec<_>>();
+    if parts.len() != 4 || parts[0] != "pbkdf2-sha256" {
+        return false;
+    }
+    match (parts[1].parse(), from_hex(parts[2]), from_hex(parts[3])) {
+        (Ok(iterations), Some(salt), Some(hash)) => pbkdf2::verify(
+            &digest::SHA256,
+            iterations,
+            &salt,
+            password.as_bytes(),
+            &hash,
+        ).is_ok(),
+        _ => false,
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 == 1 {
+        return None;
+    }
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passwords_are_salted() {
+        let hash = hash_password("secret");
+        assert!(hash.starts_with("pbkdf2-sha256$100000$"), "{}", hash);
+        assert_ne!(hash, hash_password("secret"));
+        assert!(verify_password(&hash, "secret"));
+        assert!(!verify_password(&hash, "Secret"));
+    }
+
+    #[test]
+    fn malformed_hashes_never_match() {
+        let hash = hash_password("secret");
+        let parts = hash.split('$').collect::<Vec<_>>();
+        let odd_salt = format!("{}${}${}0${}", parts[0], parts[1], parts[2], parts[3]);
+        let candidates = [
+            String::new(),
+            hash.replace("pbkdf2-sha256", "md5"),
+            hash.replacen("$100000$", "$many$", 1),
+            odd_salt,
+            format!("{}$", hash),
+        ];
+        for candidate in &candidates {
+            assert!(!verify_password(candidate, "secret"), "{}", candidate);
+        }
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        let bytes = [0, 1, 0x7f, 0xff];
+        assert_eq!("00017fff", to_hex(&bytes));
+        assert_eq!(Some(bytes.to_vec()), from_hex("00017fff"));
+        assert_eq!(Some(bytes.to_vec()), from_hex("00017FFF"));
+        assert_eq!(None, from_hex("000"));
+        assert_eq!(None, from_hex("0g"));
+    }
+
+    #[test]
+    fn roles_decide_who_changes_an_endpoint() {
+        let user = |role| User {
+            id: Uuid::new_v4(),
+            name: "alice".to_string(),
+            role,
+            password_hash: String::new(),
+        };
+        let (reader, writer, admin) = (user(Role::Read), user(Role::Write), user(Role::Admin));
+        assert!(!reader.can_change(Some(reader.id)));
+        assert!(writer.can_change(Some(writer.id)));
+        assert!(!writer.can_change(Some(admin.id)));
+        assert!(!writer.can_change(None));
+        assert!(admin.can_change(Some(writer.id)));
+        assert!(admin.can_change(None));
+        assert!(Role::Read < Role::Write && Role::Write < Role::Admin);
+        for role in &[Role::Read, Role::Write, Role::Admin] {
+            assert_eq!(Ok(*role), role.to_string().parse());
+        }
+        assert!("admin".parse::<Role>().is_err());
+    }
+}
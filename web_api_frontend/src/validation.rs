@@ -0,0 +1,193 @@
+use endpoint::{Endpoint, Instruction};
+use project::{check_call, fix_call};
+use rust_functional::Config as BaseConfig;
+use std::collections::HashSet;
+use std::error::Error as StdError;
+use std::fmt;
+use std::rc::Rc;
+use uuid::Uuid;
+
+pub use project::Problem;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BrokenInstruction {
+    pub id: Uuid,
+    pub index: usize,
+    pub problems: Vec<Problem>,
+}
+
+/// An endpoint with at least one instruction that does not match the current module configs
+#[derive(Debug, Clone, Serialize)]
+pub struct BrokenEndpoint {
+    pub id: Uuid,
+    pub name: String,
+    pub version: i32,
+    pub instructions: Vec<BrokenInstruction>,
+    /// Whether every problem can be resolved by `fix`
+    pub fixable: bool,
+}
+
+impl fmt::Display for BrokenEndpoint {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Endpoint {:?} is broken:", self.name)?;
+        for instruction in &self.instructions {
+            for problem in &instruction.problems {
+                write!(fmt, " instruction {}: {};", instruction.index + 1, problem)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl StdError for BrokenEndpoint {
+    fn description(&self) -> &str {
+        "Endpoint does not match its module configs"
+    }
+}
+
+/// Check the instructions of an endpoint against the given configs.
+/// Returns `None` if the endpoint can be generated.
+pub fn check(endpoint: &Endpoint, configs: &[(Uuid, Rc<BaseConfig>)]) -> Option<BrokenEndpoint> {
+    let instructions = endpoint
+        .instructions
+        .iter()
+        .enumerate()
+        .filter_map(|(index, instruction)| {
+            let problems = check_instruction(instruction, configs);
+            if problems.is_empty() {
+                None
+            } else {
+                Some(BrokenInstruction {
+                    id: instruction.get_id(),
+                    index,
+                    problems,
+                })
+            }
+        })
+        .collect::<Vec<_>>();
+    if instructions.is_empty() {
+        return None;
+    }
+    let fixable = instructions
+        .iter()
+        .all(|i| i.problems.iter().all(Problem::is_fixable));
+    Some(BrokenEndpoint {
+        id: endpoint.id,
+        name: endpoint.name.clone(),
+        version: endpoint.version,
+        instructions,
+        fixable,
+    })
+}
+
+fn check_instruction(
+    instruction: &Instruction,
+    configs: &[(Uuid, Rc<BaseConfig>)],
+) -> Vec<Problem> {
+    match instruction {
+        Instruction::CallMethod {
+            config,
+            method,
+            arguments,
+            ..
+        } => match configs.iter().find(|c| &c.0 == config) {
+            Some(c) => check_call(&c.1, method, arguments),
+            None => vec![Problem::MissingConfig { config: *config }],
+        },
+        Instruction::JsonReturn { .. } => Vec::new(),
+    }
+}
+
+/// The id of the first instruction that has the same id as an instruction before it.
+/// Instructions are matched by their id, e.g. by `revision::diff`, so ids need to be unique.
+pub fn duplicate_instruction(endpoint: &Endpoint) -> Option<Uuid> {
+    let mut ids = HashSet::new();
+    endpoint
+        .instructions
+        .iter()
+        .map(|i| i.get_id())
+        .find(|id| !ids.insert(*id))
+}
+
+/// Apply the renames declared in the module configs to the instructions of an endpoint.
+/// Returns the number of method and argument names that were changed.
+pub fn fix(endpoint: &mut Endpoint, configs: &[(Uuid, Rc<BaseConfig>)]) -> usize {
+    let mut fixed = 0;
+    for instruction in &mut endpoint.instructions {
+        if let Instruction::CallMethod {
+            config,
+            method,
+            arguments,
+            ..
+        } = instruction
+        {
+            if let Some(c) = configs.iter().find(|c| &c.0 == config) {
+                fixed += fix_call(&c.1, method, arguments);
+            }
+        }
+    }
+    fixed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use endpoint::ArgType;
+
+    fn endpoint(instructions: Vec<Instruction>) -> Endpoint {
+        Endpoint {
+            id: Uuid::new_v4(),
+            name: "add".to_string(),
+            description: "Add two numbers".to_string(),
+            url: "/add".to_string(),
+            instructions,
+            version: 1,
+            updated_at: None,
+            owner: None,
+        }
+    }
+
+    fn call(config: Uuid) -> Instruction {
+        Instruction::CallMethod {
+            id: Uuid::new_v4(),
+            config,
+            method: "add".to_string(),
+            out_variable_name: "out".to_string(),
+            arguments: Vec::new(),
+        }
+    }
+
+    fn json_return(id: Uuid) -> Instruction {
+        Instruction::JsonReturn {
+            id,
+            arg_type: ArgType::Parameter,
+            arg_type_value: "out".to_string(),
+        }
+    }
+
+    #[test]
+    fn missing_configs_can_not_be_fixed() {
+        let config = Uuid::new_v4();
+        let mut endpoint = endpoint(vec![json_return(Uuid::new_v4()), call(config)]);
+        let broken = check(&endpoint, &[]).unwrap();
+        assert_eq!(endpoint.id, broken.id);
+        assert!(!broken.fixable);
+        assert_eq!(1, broken.instructions.len());
+        assert_eq!(1, broken.instructions[0].index);
+        assert_eq!(
+            vec![Problem::MissingConfig { config }],
+            broken.instructions[0].problems
+        );
+        assert_eq!(0, fix(&mut endpoint, &[]));
+    }
+
+    #[test]
+    fn instruction_ids_must_be_unique() {
+        let id = Uuid::new_v4();
+        let unique = endpoint(vec![json_return(id), json_return(Uuid::new_v4())]);
+        assert_eq!(None, duplicate_instruction(&unique));
+        let duplicated = endpoint(vec![json_return(id), json_return(id)]);
+        assert_eq!(Some(id), duplicate_instruction(&duplicated));
+        assert!(check(&duplicated, &[]).is_none());
+    }
+}
@@ -0,0 +1,233 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rust_functional::Config;
+use std::fs::{self, File};
+use std::io::{self, Cursor, Read, Write};
+use std::path::Path;
+use tar;
+use zip::write::{FileOptions, ZipWriter};
+
+/// The directory inside the generated crate that module crates are copied to
+pub const MODULE_DIR: &str = "modules";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// Parse a file extension, e.g. `tar.gz` or `zip`
+    pub fn from_extension(extension: &str) -> Option<ArchiveFormat> {
+        match extension {
+            "tar.gz" | "tgz" => Some(ArchiveFormat::TarGz),
+            "zip" => Some(ArchiveFormat::Zip),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ArchiveFormat::TarGz => "tar.gz",
+            ArchiveFormat::Zip => "zip",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ArchiveFormat::TarGz => "application/gzip",
+            ArchiveFormat::Zip => "application/zip",
+        }
+    }
+}
+
+/// Read the files of a module crate, with paths relative to the generated crate.
+/// `target` directories and `Cargo.lock` are skipped.
+pub fn module_files(module: &Config) -> io::Result<Vec<(String, Vec<u8>)>> {
+    let mut files = Vec::new();
+    let prefix = format!("{}/{}", MODULE_DIR, module.name);
+    read_dir(&module.url, &prefix, &mut files)?;
+    Ok(files)
+}
+
+fn read_dir(dir: &Path, prefix: &str, files: &mut Vec<(String, Vec<u8>)>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name == "target" || name == "Cargo.lock" || name.starts_with('.') {
+            continue;
+        }
+        let path = format!("{}/{}", prefix, name);
+        if entry.file_type()?.is_dir() {
+            read_dir(&entry.path(), &path, files)?;
+        } else {
+            let mut content = Vec::new();
+            File::open(entry.path())?.read_to_end(&mut content)?;
+            files.push((path, content));
+        }
+    }
+    Ok(())
+}
+
+/// Write the given files to an archive, inside a directory named `root`
+pub fn write(
+    format: ArchiveFormat,
+    root: &str,
+    files: &[(String, Vec<u8>)],
+) -> io::Result<Vec<u8>> {
+    match format {
+        ArchiveFormat::TarGz => {
+            let mut archive = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+            for (path, content) in files {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(content.len() as u64);
+                header.set_mode(0o644);
+                header.set_cksum();
+                archive.append_data(&mut header, format!("{}/{}", root, path), &content[..])?;
+            }
+            archive.into_inner()?.finish()
+        }
+        ArchiveFormat::Zip => {
+            let mut archive = ZipWriter::new(Cursor::new(Vec::new()));
+            for (path, content) in files {
+                archive.start_file(format!("{}/{}", root, path), FileOptions::default())?;
+                archive.write_all(content)?;
+            }
+            Ok(archive.finish()?.into_inner())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backend::Cgi;
+    use builder::{Builder, EndPoint};
+    use flate2::read::GzDecoder;
+    use instruction::Instruction;
+    use rust_functional::{Instruction as BaseInstruction, InstructionParameter};
+    use std::env;
+    use std::process;
+    use std::rc::Rc;
+    use zip::ZipArchive;
+
+    /// Read the paths and contents of the files in an archive
+    fn read(format: ArchiveFormat, archive: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let mut files = Vec::new();
+        match format {
+            ArchiveFormat::TarGz => {
+                let mut archive = tar::Archive::new(GzDecoder::new(archive));
+                for entry in archive.entries().unwrap() {
+                    let mut entry = entry.unwrap();
+                    let path = entry.path().unwrap().to_string_lossy().into_owned();
+                    let mut content = Vec::new();
+                    entry.read_to_end(&mut content).unwrap();
+                    files.push((path, content));
+                }
+            }
+            ArchiveFormat::Zip => {
+                let mut archive = ZipArchive::new(Cursor::new(archive)).unwrap();
+                for index in 0..archive.len() {
+                    let mut file = archive.by_index(index).unwrap();
+                    let mut content = Vec::new();
+                    file.read_to_end(&mut content).unwrap();
+                    files.push((file.name().to_string(), content));
+                }
+            }
+        }
+        files
+    }
+
+    #[test]
+    fn files_are_written_inside_the_root() {
+        let files = vec![
+            ("Cargo.toml".to_string(), b"[package]\n".to_vec()),
+            ("src/lib.rs".to_string(), b"pub fn app() {}\n".to_vec()),
+        ];
+        for format in &[ArchiveFormat::TarGz, ArchiveFormat::Zip] {
+            let archive = write(*format, "api", &files).unwrap();
+            let expected = files
+                .iter()
+                .map(|(path, content)| (format!("api/{}", path), content.clone()))
+                .collect::<Vec<_>>();
+            assert_eq!(expected, read(*format, &archive), "{:?}", format);
+            assert_eq!(Some(*format), ArchiveFormat::from_extension(format.extension()));
+        }
+    }
+
+    #[test]
+    fn module_files_skip_build_output() {
+        let dir = env::temp_dir().join(format!("web_api_generator_archive_{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for path in &["src", "target/debug", ".git"] {
+            fs::create_dir_all(dir.join(path)).unwrap();
+        }
+        for path in &["module.json", "src/lib.rs", "target/debug/lib", "Cargo.lock", ".git/HEAD"] {
+            File::create(dir.join(path)).unwrap().write_all(path.as_bytes()).unwrap();
+        }
+        let module = Config {
+            url: dir.clone(),
+            name: "vendored".to_string(),
+            ..Config::default()
+        };
+        let mut files = module_files(&module).unwrap();
+        files.sort();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            vec![
+                ("modules/vendored/module.json".to_string(), b"module.json".to_vec()),
+                ("modules/vendored/src/lib.rs".to_string(), b"src/lib.rs".to_vec()),
+            ],
+            files
+        );
+    }
+
+    /// Unpacks a generated CGI crate, whose only other dependency is `serde_json`,
+    /// and runs `cargo check` on it
+    #[test]
+    fn archives_build_on_their_own() {
+        let adder = Rc::new(Config::from_path("../modules/adder"));
+        let mut endpoint = EndPoint::new("one", "/one");
+        endpoint.add_base_instruction(BaseInstruction::CallModule {
+            config: adder.clone(),
+            method: "add".to_string(),
+            parameters: vec![
+                ("a".to_string(), InstructionParameter::Number(1)),
+                ("b".to_string(), InstructionParameter::Number(2)),
+            ],
+            out_variable_name: "sum".to_string(),
+        });
+        endpoint.add_instruction(Instruction::Json(InstructionParameter::Variable(
+            "sum".to_string(),
+        )));
+        let mut builder = Builder::default();
+        builder.set_backend(Box::new(Cgi));
+        builder.add_module(adder);
+        builder.add_endpoint(endpoint);
+
+        let archive = builder.build_archive(ArchiveFormat::Zip, "api").unwrap();
+        let files = read(ArchiveFormat::Zip, &archive);
+        assert!(!files.iter().any(|f| f.0.contains("/target/")));
+        let dir = env::temp_dir().join(format!("web_api_generator_unpacked_{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (path, content) in &files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            File::create(path).unwrap().write_all(content).unwrap();
+        }
+        let manifest = fs::read_to_string(dir.join("api/Cargo.toml")).unwrap();
+        assert!(manifest.contains("adder = { path = \"modules/adder\" }\n"));
+
+        let output = process::Command::new(env::var("CARGO").unwrap_or_else(|_| "cargo".into()))
+            .arg("check")
+            .current_dir(dir.join("api"))
+            .output()
+            .unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
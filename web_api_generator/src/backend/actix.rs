@@ -0,0 +1,127 @@
+use super::Backend;
+use builder::Scope;
+
+/// actix-web 0.7, with synchronous handlers
+#[derive(Debug, Default)]
+pub struct Actix;
+
+impl Actix {
+    fn route(indent: &str, url: &str, handler: &str) -> String {
+        format!(
+            "{}.route({:?}, actix_web::http::Method::GET, {})\n",
+            indent, url, handler
+        )
+    }
+}
+
+impl Backend for Actix {
+    fn name(&self) -> &'static str {
+        "actix"
+    }
+
+    fn dependencies(&self) -> Vec<(&'static str, &'static str)> {
+        vec![("actix-web", "\"*\"")]
+    }
+
+    fn cargo_footer(&self) -> &'static str {
+        r#"
+[replace]
+"cookie:0.10.1" = { path = "../libs/cookie-rs" }
+"#
+    }
+
+    fn main_rs(&self) -> &'static str {
+        r#"extern crate actix_web;
+extern crate app;
+
+fn main() {
+    actix_web::server::new(app::create_app)
+    .bind("127.0.0.1:8080").unwrap()
+    .run();
+}
+"#
+    }
+
+    fn register_routes(&self, scopes: &[Scope], openapi_path: &str) -> String {
+        let mut result = "\npub fn create_app() -> actix_web::App {\n    actix_web::App::new()\n".to_string();
+        for scope in scopes {
+            if scope.prefix.is_empty() {
+                for endpoint in &scope.endpoints {
+                    result += &Actix::route("        ", &endpoint.url, &endpoint.handler_name());
+                }
+                continue;
+            }
+            result += &format!(
+                "        .scope({:?}, |scope| {{\n            scope\n",
+                scope.prefix
+            );
+            for endpoint in &scope.endpoints {
+                result += &Actix::route(
+                    "                ",
+                    &endpoint.url,
+                    &endpoint.handler_name(),
+                );
+            }
+            result += "        })\n";
+        }
+        result += &Actix::route("        ", openapi_path, "openapi");
+        result += "}\n";
+        result
+    }
+
+    fn handler_start(&self, name: &str) -> String {
+        format!(
+            "\nfn {}(req: actix_web::HttpRequest) -> impl actix_web::Responder {{\n",
+            name
+        )
+    }
+
+    fn json(&self, value: &str) -> String {
+        format!("    actix_web::Json({})\n", value)
+    }
+
+    fn traced_json(&self, value: &str) -> Option<String> {
+        Some(format!(
+            "    actix_web::HttpResponse::Ok()\n        .header(\"X-Trace\", trace_calls.into_json())\n        .json({})\n",
+            value
+        ))
+    }
+
+    fn openapi_handler(&self) -> &'static str {
+        r#"
+fn openapi(_req: actix_web::HttpRequest) -> actix_web::HttpResponse {
+    actix_web::HttpResponse::Ok()
+        .content_type("application/json")
+        .body(include_str!("../openapi.json"))
+}
+"#
+    }
+
+    fn test_header(&self) -> &'static str {
+        r#"extern crate actix_web;
+extern crate app;
+extern crate serde_json;
+
+use actix_web::{http, test, HttpMessage};
+
+fn get(path: &str) -> (http::StatusCode, serde_json::Value) {
+    let mut srv = test::TestServer::with_factory(app::create_app);
+    let request = srv.client(http::Method::GET, path).finish().unwrap();
+    let response = srv.execute(request.send()).unwrap();
+    let status = response.status();
+    let body = srv.execute(response.body()).unwrap();
+    (
+        status,
+        serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null),
+    )
+}
+"#
+    }
+
+    fn test_start(&self, name: &str, url: &str) -> String {
+        format!(
+            "\n#[test]\nfn {}() {{\n    let (status, json) = get({:?});\n    assert!(status.is_success(), \"Unexpected status {{}}\", status);\n",
+            name, url
+        )
+    }
+}
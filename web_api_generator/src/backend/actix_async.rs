@@ -0,0 +1,132 @@
+use super::Backend;
+use builder::Scope;
+
+/// actix-web 4, with `async fn` handlers
+#[derive(Debug, Default)]
+pub struct ActixAsync;
+
+impl ActixAsync {
+    fn route(indent: &str, url: &str, handler: &str) -> String {
+        format!(
+            "{}.route({:?}, actix_web::web::get().to({}))\n",
+            indent, url, handler
+        )
+    }
+}
+
+impl Backend for ActixAsync {
+    fn name(&self) -> &'static str {
+        "actix_async"
+    }
+
+    fn is_async(&self) -> bool {
+        true
+    }
+
+    fn package_header(&self) -> &'static str {
+        "edition = \"2018\"\n"
+    }
+
+    fn dependencies(&self) -> Vec<(&'static str, &'static str)> {
+        vec![("actix-web", "\"4\"")]
+    }
+
+    fn main_rs(&self) -> &'static str {
+        r#"extern crate actix_web;
+extern crate app;
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    actix_web::HttpServer::new(|| actix_web::App::new().configure(app::configure))
+        .bind("127.0.0.1:8080")?
+        .run()
+        .await
+}
+"#
+    }
+
+    fn register_routes(&self, scopes: &[Scope], openapi_path: &str) -> String {
+        let mut result =
+            "\npub fn configure(cfg: &mut actix_web::web::ServiceConfig) {\n    cfg\n".to_string();
+        for scope in scopes {
+            if scope.prefix.is_empty() {
+                for endpoint in &scope.endpoints {
+                    result +=
+                        &ActixAsync::route("        ", &endpoint.url, &endpoint.handler_name());
+                }
+                continue;
+            }
+            result += &format!(
+                "        .service(\n            actix_web::web::scope({:?})\n",
+                scope.prefix
+            );
+            for endpoint in &scope.endpoints {
+                result += &ActixAsync::route(
+                    "                ",
+                    &endpoint.url,
+                    &endpoint.handler_name(),
+                );
+            }
+            result += "        )\n";
+        }
+        result += &ActixAsync::route("        ", openapi_path, "openapi");
+        result += "    ;\n}\n";
+        result
+    }
+
+    fn handler_start(&self, name: &str) -> String {
+        format!(
+            "\nasync fn {}(req: actix_web::HttpRequest) -> impl actix_web::Responder {{\n",
+            name
+        )
+    }
+
+    fn json(&self, value: &str) -> String {
+        format!("    actix_web::web::Json({})\n", value)
+    }
+
+    fn traced_json(&self, value: &str) -> Option<String> {
+        Some(format!(
+            "    actix_web::HttpResponse::Ok()\n        .insert_header((\"X-Trace\", trace_calls.into_json()))\n        .json({})\n",
+            value
+        ))
+    }
+
+    fn openapi_handler(&self) -> &'static str {
+        r#"
+async fn openapi() -> actix_web::HttpResponse {
+    actix_web::HttpResponse::Ok()
+        .content_type("application/json")
+        .body(include_str!("../openapi.json"))
+}
+"#
+    }
+
+    fn test_header(&self) -> &'static str {
+        r#"extern crate actix_web;
+extern crate app;
+extern crate serde_json;
+
+use actix_web::{http, test, App};
+
+async fn get(path: &str) -> (http::StatusCode, serde_json::Value) {
+    let srv = test::init_service(App::new().configure(app::configure)).await;
+    let request = test::TestRequest::get().uri(path).to_request();
+    let response = test::call_service(&srv, request).await;
+    let status = response.status();
+    let body = test::read_body(response).await;
+    (
+        status,
+        serde_json::from_slice(&body).unwrap_or(serde_json::Value::Null),
+    )
+}
+"#
+    }
+
+    fn test_start(&self, name: &str, url: &str) -> String {
+        format!(
+            "\n#[actix_web::test]\nasync fn {}() {{\n    let (status, json) = get({:?}).await;\n    assert!(status.is_success(), \"Unexpected status {{}}\", status);\n",
+            name, url
+        )
+    }
+}
@@ -0,0 +1,179 @@
+use super::Backend;
+use builder::Scope;
+
+/// A command line program that reads a request like `{"path": "/api/users"}` from stdin,
+/// and writes a response like `{"status": 200, "body": ...}` to stdout
+#[derive(Debug, Default)]
+pub struct Cgi;
+
+impl Cgi {
+    fn route(url: &str, handler: &str) -> String {
+        format!(
+            "    if let Some(req) = match_route({:?}, path) {{\n        return (200, {}(&req));\n    }}\n",
+            url, handler
+        )
+    }
+}
+
+impl Backend for Cgi {
+    fn name(&self) -> &'static str {
+        "cgi"
+    }
+
+    fn dependencies(&self) -> Vec<(&'static str, &'static str)> {
+        vec![("serde_json", "\"*\"")]
+    }
+
+    fn lib_header(&self) -> &'static str {
+        "extern crate serde_json;\n"
+    }
+
+    fn main_rs(&self) -> &'static str {
+        r#"extern crate app;
+extern crate serde_json;
+
+use std::io::{self, Read};
+
+fn main() {
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input).unwrap();
+    let request: serde_json::Value = serde_json::from_str(&input).unwrap();
+    let (status, body) = app::handle(request["path"].as_str().unwrap_or("/"));
+    println!("{{\"status\":{},\"body\":{}}}", status, body);
+}
+"#
+    }
+
+    fn register_routes(&self, scopes: &[Scope], openapi_path: &str) -> String {
+        let mut result = r#"
+/// Handle a request to the given path, returning the status code and the JSON body
+pub fn handle(path: &str) -> (u16, String) {
+    let path = path.split('?').next().unwrap_or("");
+"#.to_string();
+        for scope in scopes {
+            for endpoint in &scope.endpoints {
+                result += &Cgi::route(
+                    &format!("{}{}", scope.prefix, endpoint.url),
+                    &endpoint.handler_name(),
+                );
+            }
+        }
+        result += &format!(
+            "    if path == {:?} {{\n        return (200, openapi());\n    }}\n",
+            openapi_path
+        );
+        result += r#"    (404, "null".to_string())
+}
+
+/// Match a path against a route, returning the values of the `{name}` segments of the route
+fn match_route(
+    route: &str,
+    path: &str,
+) -> Option<::std::collections::HashMap<String, String>> {
+    let route = route.trim_matches('/').split('/').collect::<Vec<_>>();
+    let path = path.trim_matches('/').split('/').collect::<Vec<_>>();
+    if route.len() != path.len() {
+        return None;
+    }
+    let mut result = ::std::collections::HashMap::new();
+    for (route, path) in route.iter().zip(path.iter()) {
+        if route.starts_with('{') && route.ends_with('}') {
+            result.insert(route[1..route.len() - 1].to_string(), path.to_string());
+        } else if route != path {
+            return None;
+        }
+    }
+    Some(result)
+}
+"#;
+        result
+    }
+
+    fn has_http_request(&self) -> bool {
+        false
+    }
+
+    fn handler_start(&self, name: &str) -> String {
+        format!(
+            "\nfn {}(req: &::std::collections::HashMap<String, String>) -> String {{\n",
+            name
+        )
+    }
+
+    fn json(&self, value: &str) -> String {
+        format!("    ::serde_json::to_string(&{}).unwrap()\n", value)
+    }
+
+    fn openapi_handler(&self) -> &'static str {
+        r#"
+fn openapi() -> String {
+    include_str!("../openapi.json").to_string()
+}
+"#
+    }
+
+    fn test_header(&self) -> &'static str {
+        r#"extern crate app;
+extern crate serde_json;
+
+fn get(path: &str) -> (u16, serde_json::Value) {
+    let (status, body) = app::handle(path);
+    (
+        status,
+        serde_json::from_str(&body).unwrap_or(serde_json::Value::Null),
+    )
+}
+"#
+    }
+
+    fn test_start(&self, name: &str, url: &str) -> String {
+        format!(
+            "\n#[test]\nfn {}() {{\n    let (status, json) = get({:?});\n    assert_eq!(200, status);\n",
+            name, url
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use builder::{Builder, BuilderError, EndPoint};
+    use test_util::{add_endpoint, adder};
+
+    #[test]
+    fn routes_include_the_scope_prefix() {
+        let adder = adder();
+        let mut builder = Builder::default();
+        builder.set_backend(Box::new(Cgi));
+        builder.add_module(adder.clone());
+        let mut scope = Scope::new("/api");
+        scope.add_endpoint(add_endpoint(&adder, "/add/{x}"));
+        builder.add_scope(scope);
+        let files = builder.build().unwrap();
+
+        let lib = &files["src/lib.rs"];
+        assert!(lib.starts_with("extern crate serde_json;\n"));
+        assert!(lib.contains(
+            "match_route(\"/api/add/{x}\", path) {\n        return (200, add(&req));"
+        ));
+        assert!(lib.contains("if path == \"/openapi.json\" {"));
+        assert!(lib.contains(
+            "fn add(req: &::std::collections::HashMap<String, String>) -> String {"
+        ));
+        assert!(lib.contains("    ::serde_json::to_string(&sum).unwrap()\n"));
+        assert!(files["src/main.rs"].contains("app::handle("));
+    }
+
+    #[test]
+    fn handlers_cannot_shadow_the_router() {
+        for name in &["handle", "match_route"] {
+            let mut builder = Builder::default();
+            builder.set_backend(Box::new(Cgi));
+            builder.add_endpoint(EndPoint::new(*name, "/"));
+            match builder.validate() {
+                Err(BuilderError::DuplicateHandler(ref handler)) if handler == name => {}
+                result => panic!("Expected DuplicateHandler, got {:?}", result),
+            }
+        }
+    }
+}
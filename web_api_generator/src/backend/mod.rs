@@ -0,0 +1,104 @@
+mod actix;
+mod actix_async;
+mod cgi;
+
+pub use self::actix::Actix;
+pub use self::actix_async::ActixAsync;
+pub use self::cgi::Cgi;
+
+use builder::Scope;
+use std::fmt;
+
+/// A runtime the generated crate is written for.
+///
+/// The backend owns everything that differs between runtimes: the dependencies, `src/main.rs`,
+/// route registration, handler signatures and how the value of a `Json` instruction is rendered.
+pub trait Backend: fmt::Debug {
+    /// The name this backend is selected by, see `backend_by_name`
+    fn name(&self) -> &'static str;
+
+    /// Whether handlers are `async fn`, allowing modules to declare async methods
+    fn is_async(&self) -> bool {
+        false
+    }
+
+    /// Extra lines in the `[package]` section of `Cargo.toml`
+    fn package_header(&self) -> &'static str {
+        ""
+    }
+
+    /// The dependencies of the generated crate, as (name, version) pairs
+    fn dependencies(&self) -> Vec<(&'static str, &'static str)>;
+
+    /// Trailing sections of `Cargo.toml`, after `[dependencies]`
+    fn cargo_footer(&self) -> &'static str {
+        ""
+    }
+
+    /// The `extern crate` lines at the start of `src/lib.rs`
+    fn lib_header(&self) -> &'static str {
+        "extern crate actix_web;\n"
+    }
+
+    /// The content of `src/main.rs`, which calls into the generated `app` library
+    fn main_rs(&self) -> &'static str;
+
+    /// The public function of `src/lib.rs` that registers all routes
+    fn register_routes(&self, scopes: &[Scope], openapi_path: &str) -> String;
+
+    /// The signature of a handler, up to and including the opening brace
+    fn handler_start(&self, name: &str) -> String;
+
+    /// Whether the `req` of a handler is an `actix_web::HttpRequest`, which module methods like
+    /// the ones of `actix_web_helper` can take
+    fn has_http_request(&self) -> bool {
+        true
+    }
+
+    /// Render the given expression as the JSON response of a handler
+    fn json(&self, value: &str) -> String;
+
+    /// Render the given expression as the JSON response of a handler, and send the calls in the
+    /// `trace_calls` variable of the handler along, e.g. in a header, see `trace::CREATE`.
+    /// `None` if the backend cannot send them, which `Builder::validate` rejects.
+    fn traced_json(&self, _value: &str) -> Option<String> {
+        None
+    }
+
+    /// The `openapi` handler that serves `openapi.json`
+    fn openapi_handler(&self) -> &'static str;
+
+    /// The start of `tests/endpoints.rs`, defining `get(path) -> (status, serde_json::Value)`
+    fn test_header(&self) -> &'static str;
+
+    /// The start of a test function that calls `url`, binding `status` and `json`
+    fn test_start(&self, name: &str, url: &str) -> String;
+}
+
+/// Get a backend by the name it is stored with in a project
+pub fn backend_by_name(name: &str) -> Option<Box<dyn Backend>> {
+    match name {
+        "actix" => Some(Box::new(Actix)),
+        "actix_async" => Some(Box::new(ActixAsync)),
+        "cgi" => Some(Box::new(Cgi)),
+        _ => None,
+    }
+}
+
+/// The names of all available backends
+pub fn backend_names() -> &'static [&'static str] {
+    &["actix", "actix_async", "cgi"]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backends_are_found_by_their_name() {
+        for name in backend_names() {
+            assert_eq!(*name, backend_by_name(name).unwrap().name());
+        }
+        assert!(backend_by_name("rocket").is_none());
+    }
+}
@@ -1,12 +1,43 @@
+use archive::{self, ArchiveFormat};
+use backend::{Actix, Backend};
 use instruction::Instruction;
-use rust_functional::{Config, Instruction as BaseInstruction};
-use std::collections::HashMap;
+use openapi;
+use rust_functional::{
+    Config, Instruction as BaseInstruction, InterpretError, Interpreter, Outcome, Run,
+};
+use serde_json::Value;
+use source_map::{ArgumentSpan, SourceMap, SourceMapEntry};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::io;
 use std::rc::Rc;
+use test_generator;
+use trace;
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct Builder {
     modules: Vec<Rc<Config>>,
-    endpoints: Vec<EndPoint>,
+    scopes: Vec<Scope>,
+    openapi_path: Option<String>,
+    generate_tests: bool,
+    trace: bool,
+    vendor_modules: bool,
+    backend: Box<dyn Backend>,
+}
+
+impl Default for Builder {
+    fn default() -> Builder {
+        Builder {
+            modules: Vec::new(),
+            scopes: Vec::new(),
+            openapi_path: None,
+            generate_tests: false,
+            trace: false,
+            vendor_modules: false,
+            backend: Box::new(Actix),
+        }
+    }
 }
 
 impl Builder {
@@ -22,63 +53,359 @@ impl Builder {
     }
 
     pub fn add_endpoint(&mut self, endpoint: EndPoint) {
-        self.endpoints.push(endpoint);
+        self.scope_mut("").endpoints.push(endpoint);
+    }
+
+    /// Add a group of endpoints that share the given url prefix.
+    /// Scopes with the same prefix are merged.
+    pub fn add_scope(&mut self, scope: Scope) {
+        let Scope { prefix, endpoints } = scope;
+        self.scope_mut(&prefix).endpoints.extend(endpoints);
+    }
+
+    fn scope_mut(&mut self, prefix: &str) -> &mut Scope {
+        if let Some(index) = self.scopes.iter().position(|s| s.prefix == prefix) {
+            return &mut self.scopes[index];
+        }
+        self.scopes.push(Scope::new(prefix));
+        self.scopes.last_mut().unwrap()
+    }
+
+    /// All endpoints with their full url, including the prefix of their scope
+    fn routes(&self) -> Vec<(String, &EndPoint)> {
+        self.scopes
+            .iter()
+            .flat_map(|s| {
+                s.endpoints
+                    .iter()
+                    .map(move |e| (format!("{}{}", s.prefix, e.url), e))
+            })
+            .collect()
+    }
+
+    /// Check that no two endpoints share a route or a handler name, and that the backend supports
+    /// the async methods and the tracing that are used
+    pub fn validate(&self) -> Result<(), BuilderError> {
+        if self.trace && self.backend.traced_json("()").is_none() {
+            return Err(BuilderError::TraceUnsupported(self.backend.name()));
+        }
+        if self.generate_tests {
+            for module in &self.modules {
+                let mut methods = module.methods.iter();
+                if methods.clone().any(|m| m.mock.is_some()) {
+                    if let Some(method) = methods.find(|m| m.mock.is_none()) {
+                        return Err(BuilderError::PartialMock(
+                            module.name.clone(),
+                            method.name.clone(),
+                        ));
+                    }
+                }
+            }
+        }
+        let mut routes = HashSet::new();
+        routes.insert(self.openapi_path().to_string());
+        let mut handlers = HashSet::new();
+        for reserved in &["create_app", "configure", "handle", "match_route", "openapi"] {
+            handlers.insert(reserved.to_string());
+        }
+        for (url, endpoint) in self.routes() {
+            if !routes.insert(url.clone()) {
+                return Err(BuilderError::DuplicateRoute(url));
+            }
+            let handler = endpoint.handler_name();
+            if !handlers.insert(handler.clone()) {
+                return Err(BuilderError::DuplicateHandler(handler));
+            }
+            if !self.backend.is_async() && endpoint.instructions.iter().any(|i| i.is_async()) {
+                return Err(BuilderError::AsyncMethod(handler));
+            }
+            if !self.backend.has_http_request()
+                && endpoint.instructions.iter().any(|i| i.takes_http_request())
+            {
+                return Err(BuilderError::HttpRequestUnsupported(
+                    handler,
+                    self.backend.name(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Set the url the generated server serves `openapi.json` on. Defaults to `/openapi.json`
+    pub fn set_openapi_path(&mut self, path: impl Into<String>) {
+        self.openapi_path = Some(path.into());
+    }
+
+    pub fn openapi_path(&self) -> &str {
+        self.openapi_path.as_deref().unwrap_or("/openapi.json")
+    }
+
+    /// Also generate a `tests/` directory that calls every endpoint on a test server.
+    /// The tests are run with `cargo test --features mock`, replacing the methods of the modules
+    /// that declare a `mock` value in their `module.json`. A module mocks all methods or none.
+    pub fn set_generate_tests(&mut self, generate_tests: bool) {
+        self.generate_tests = generate_tests;
+    }
+
+    /// Add a `trace` feature to the generated crate. When it is enabled, every module call records
+    /// its inputs, output and duration, and the backend sends the calls of a request back with
+    /// the response, see `Backend::traced_json`. Not every backend supports it.
+    pub fn set_trace(&mut self, trace: bool) {
+        self.trace = trace;
+    }
+
+    /// Reference the modules from `modules/<name>` inside the generated crate, instead of their
+    /// original location. Used by `build_archive` so the archive builds on its own.
+    pub fn set_vendor_modules(&mut self, vendor_modules: bool) {
+        self.vendor_modules = vendor_modules;
+    }
+
+    /// Set the runtime the generated crate is written for. Defaults to `Actix`
+    pub fn set_backend(&mut self, backend: Box<dyn Backend>) {
+        self.backend = backend;
     }
 
-    pub fn build(self) -> HashMap<String, String> {
+    /// Build the crate, after checking it with `validate`
+    pub fn build(self) -> Result<HashMap<String, String>, BuilderError> {
+        Ok(self.build_with_source_map()?.0)
+    }
+
+    /// Build the crate, and record which lines of the generated code belong to which instruction
+    pub fn build_with_source_map(
+        self,
+    ) -> Result<(HashMap<String, String>, SourceMap), BuilderError> {
+        self.validate()?;
+        let routes = self.routes();
+        let backend = &*self.backend;
         let mut result = HashMap::new();
+        let mut source_map = SourceMap::default();
+
+        result.insert(
+            "openapi.json".to_string(),
+            ::serde_json::to_string_pretty(&openapi::generate(&self.modules, &routes)).unwrap(),
+        );
+
+        let mut dependencies = backend.dependencies();
+        let mock_serde_json = self.generate_tests && !dependencies.iter().any(|d| d.0 == "serde_json");
+        if mock_serde_json {
+            dependencies.push(("serde_json", "\"*\""));
+        }
+        let mut trace_dependencies = Vec::new();
+        if self.trace {
+            for name in &["serde", "serde_json"] {
+                if !dependencies.iter().any(|d| d.0 == *name) {
+                    dependencies.push((name, "{ version = \"*\", optional = true }"));
+                    trace_dependencies.push(format!("{:?}", name));
+                }
+            }
+        }
 
         result.insert("Cargo.toml".to_string(), {
             let mut str = r#"[package]
 name = "test"
 version = "0.1.0"
 authors = [""]
+"#.to_string();
+            str += backend.package_header();
+            str += r#"
+[lib]
+name = "app"
+path = "src/lib.rs"
 
 [dependencies]
-actix-web = "*"
-"#.to_string();
+"#;
+            for (name, version) in dependencies {
+                str += &format!("{} = {}\n", name, version);
+            }
             for module in &self.modules {
-                str += &format!(
-                    "{} = {{ path = \"{}\" }}\n",
-                    module.name,
+                let path = if self.vendor_modules {
+                    format!("{}/{}", archive::MODULE_DIR, module.name)
+                } else {
                     module.url.to_str().unwrap().replace("\\", "/")
-                );
+                };
+                str += &format!("{} = {{ path = \"{}\" }}\n", module.name, path);
             }
-            str += r#"
-[replace]
-"cookie:0.10.1" = { path = "../libs/cookie-rs" }
+            let mut features = Vec::new();
+            if self.generate_tests {
+                features.push("mock = []".to_string());
+            }
+            if self.trace {
+                features.push(format!("trace = [{}]", trace_dependencies.join(", ")));
+            }
+            if !features.is_empty() {
+                str += "\n[features]\n";
+                for feature in features {
+                    str += &feature;
+                    str += "\n";
+                }
+            }
+            if self.generate_tests {
+                str += r#"
+[[test]]
+name = "endpoints"
+required-features = ["mock"]
 "#;
+            }
+            str += backend.cargo_footer();
             str
         });
 
-        result.insert("src/main.rs".to_string(), {
-            let mut str = "extern crate actix_web;\n".to_string();
-            for module in &self.modules {
-                str += &format!("extern crate {};\n", module.name);
+        result.insert("src/main.rs".to_string(), backend.main_rs().to_string());
+
+        result.insert("src/lib.rs".to_string(), {
+            let mut str = backend.lib_header().to_string();
+            if self.generate_tests {
+                if mock_serde_json {
+                    str += "#[cfg(feature = \"mock\")]\nextern crate serde_json;\n";
+                }
+                for module in &self.modules {
+                    str += &test_generator::module_import(module);
+                }
+            } else {
+                for module in &self.modules {
+                    str += &format!("extern crate {};\n", module.name);
+                }
             }
-            str += r#"
-fn main() {
-    actix_web::server::new(||
-        actix_web::App::new()
-"#;
-            for endpoint in &self.endpoints {
-                str += &endpoint.register_with_app();
+            if self.trace {
+                str += trace::MODULE;
             }
-            str += r#"
-    )
-    .bind("127.0.0.1:8080").unwrap()
-    .run();
-}
-"#;
+            str += &backend.register_routes(&self.scopes, self.openapi_path());
 
-            for endpoint in &self.endpoints {
-                str += &endpoint.create_function();
+            for (_, endpoint) in &routes {
+                let line = str.matches('\n').count() + 1;
+                str += &endpoint.create_function(
+                    backend,
+                    self.trace,
+                    "src/lib.rs",
+                    line,
+                    &mut source_map,
+                );
             }
 
+            str += backend.openapi_handler();
+
             str
         });
 
-        result
+        if self.generate_tests {
+            result.insert(
+                "tests/endpoints.rs".to_string(),
+                test_generator::generate(backend, &routes),
+            );
+        }
+
+        Ok((result, source_map))
+    }
+
+    /// Build the crate and write it to an archive in the given format, together with
+    /// a copy of every module crate it depends on.
+    pub fn build_archive(
+        mut self,
+        format: ArchiveFormat,
+        root: &str,
+    ) -> Result<Vec<u8>, BuilderError> {
+        self.set_vendor_modules(true);
+        let mut files = Vec::new();
+        for module in &self.modules {
+            files.extend(archive::module_files(module)?);
+        }
+        files.extend(
+            self.build()?
+                .into_iter()
+                .map(|(path, content)| (path, content.into_bytes())),
+        );
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(archive::write(format, root, &files)?)
+    }
+}
+
+#[derive(Debug)]
+pub enum BuilderError {
+    DuplicateRoute(String),
+    DuplicateHandler(String),
+    /// The endpoint calls an async method, but the backend does not support async handlers
+    AsyncMethod(String),
+    /// The endpoint calls a method that takes an `actix_web::HttpRequest`, but the handlers of
+    /// the backend with this name do not have one
+    HttpRequestUnsupported(String, &'static str),
+    /// Tracing is enabled, but the backend with this name cannot send the calls
+    TraceUnsupported(&'static str),
+    /// Tests are generated, but the module mocks some of its methods and not the method with
+    /// this name. The mocked values cannot be passed to the real methods.
+    PartialMock(String, String),
+    /// A module could not be read while building an archive
+    Io(io::Error),
+}
+
+impl From<io::Error> for BuilderError {
+    fn from(e: io::Error) -> BuilderError {
+        BuilderError::Io(e)
+    }
+}
+
+impl fmt::Display for BuilderError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuilderError::DuplicateRoute(url) => write!(fmt, "Duplicate route {:?}", url),
+            BuilderError::DuplicateHandler(name) => {
+                write!(fmt, "Duplicate handler name {:?}", name)
+            }
+            BuilderError::AsyncMethod(name) => write!(
+                fmt,
+                "Endpoint {:?} calls an async method, which requires an async backend",
+                name
+            ),
+            BuilderError::HttpRequestUnsupported(name, backend) => write!(
+                fmt,
+                "Endpoint {:?} calls a method that takes an actix_web::HttpRequest, \
+                 which the {} backend does not have",
+                name, backend
+            ),
+            BuilderError::TraceUnsupported(backend) => {
+                write!(fmt, "The {} backend does not support tracing", backend)
+            }
+            BuilderError::PartialMock(module, method) => write!(
+                fmt,
+                "Module {:?} does not mock {:?}, either all of its methods need a mock or none",
+                module, method
+            ),
+            BuilderError::Io(e) => write!(fmt, "{}", e),
+        }
+    }
+}
+
+impl Error for BuilderError {
+    fn description(&self) -> &str {
+        match self {
+            BuilderError::DuplicateRoute(_) => "Duplicate route",
+            BuilderError::DuplicateHandler(_) => "Duplicate handler name",
+            BuilderError::AsyncMethod(_) => "Async method in a synchronous backend",
+            BuilderError::HttpRequestUnsupported(_, _) => {
+                "Method takes an HttpRequest, which the backend does not have"
+            }
+            BuilderError::TraceUnsupported(_) => "Tracing is not supported by the backend",
+            BuilderError::PartialMock(_, _) => "Module mocks only some of its methods",
+            BuilderError::Io(_) => "Could not read a module",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Scope {
+    pub prefix: String,
+    pub endpoints: Vec<EndPoint>,
+}
+
+impl Scope {
+    pub fn new(prefix: impl Into<String>) -> Scope {
+        Scope {
+            prefix: prefix.into(),
+            endpoints: Vec::new(),
+        }
+    }
+
+    pub fn add_endpoint(&mut self, endpoint: EndPoint) {
+        self.endpoints.push(endpoint);
     }
 }
 
@@ -107,23 +434,331 @@ impl EndPoint {
         self.instructions.push(instruction);
     }
 
-    fn register_with_app(&self) -> String {
-        format!(
-            "            .route({:?}, actix_web::http::Method::GET, {})",
-            self.url, self.name
-        )
+    /// Run the instructions with the interpreter instead of generating and building a crate.
+    /// `inputs` are the values of the `{name}` segments of the url.
+    pub fn interpret(
+        &self,
+        interpreter: &Interpreter,
+        inputs: HashMap<String, Value>,
+    ) -> Result<Outcome, InterpretError> {
+        self.interpret_with(&mut interpreter.start(inputs))
     }
 
-    fn create_function(&self) -> String {
-        let mut result = String::new();
-        result += &format!(
-            "\nfn {}(req: actix_web::HttpRequest) -> impl actix_web::Responder {{\n",
-            self.name
-        );
+    /// Run the instructions in a run that has been started already,
+    /// e.g. with `Interpreter::start_traced`
+    pub fn interpret_with(&self, run: &mut Run) -> Result<Outcome, InterpretError> {
         for instruction in &self.instructions {
-            result += &instruction.build();
+            if let Some(outcome) = instruction.interpret(run)? {
+                return Ok(outcome);
+            }
+        }
+        Ok(Outcome::Finished)
+    }
+
+    /// The name of the generated handler function, derived from the free-text endpoint name
+    pub fn handler_name(&self) -> String {
+        let mut name = self
+            .name
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>()
+            .join("_")
+            .to_ascii_lowercase();
+        if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+            name.insert(0, '_');
+        }
+        if KEYWORDS.contains(&name.as_str()) {
+            name.push('_');
+        }
+        name
+    }
+
+    /// Generate the handler function, which starts at `line` of `file`.
+    /// With `trace`, the module calls are recorded when the `trace` feature is enabled.
+    fn create_function(
+        &self,
+        backend: &dyn Backend,
+        trace: bool,
+        file: &str,
+        line: usize,
+        source_map: &mut SourceMap,
+    ) -> String {
+        let mut result = backend.handler_start(&self.handler_name());
+        if trace {
+            result += trace::CREATE;
+        }
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            let mut code = instruction.build(backend);
+            let mut traced = String::new();
+            if trace {
+                match instruction {
+                    Instruction::BaseInstruction(bi @ BaseInstruction::CallModule { .. }) => {
+                        result += trace::START;
+                        traced = trace::record(index, bi);
+                    }
+                    Instruction::Json(param) => {
+                        let value = param.to_string(false);
+                        if let Some(json) = backend.traced_json(&value) {
+                            // Only one of the returns is compiled, depending on the feature
+                            result += "    #[cfg(not(feature = \"trace\"))]\n";
+                            code = format!("    return {};\n", code.trim());
+                            traced = format!(
+                                "    #[cfg(feature = \"trace\")]\n    return {};\n",
+                                json.trim()
+                            );
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            let line_start = line + result.matches('\n').count();
+            let first_line = code.lines().next().unwrap_or("");
+
+            // Arguments are located by searching for their code after the opening parenthesis
+            let mut position = first_line.find('(').unwrap_or(0);
+            let mut arguments = Vec::new();
+            for (name, value) in instruction.arguments() {
+                if let Some(offset) = first_line[position..].find(value.as_str()) {
+                    let start = position + offset;
+                    position = start + value.len();
+                    arguments.push(ArgumentSpan {
+                        name,
+                        line: line_start,
+                        column_start: start + 1,
+                        column_end: position + 1,
+                    });
+                }
+            }
+
+            source_map.add(SourceMapEntry {
+                file: file.to_string(),
+                line_start,
+                line_end: line_start + code.trim_end_matches('\n').matches('\n').count(),
+                column_start: first_line.len() - first_line.trim_start().len() + 1,
+                column_end: first_line.trim_end().len() + 1,
+                endpoint: self.name.clone(),
+                instruction: index,
+                arguments,
+            });
+            result += &code;
+            result += &traced;
         }
         result += "}\n";
         result
     }
 }
+
+const KEYWORDS: &[&str] = &[
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+    "self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "abstract", "async", "await", "become", "box", "do", "dyn", "final", "macro",
+    "override", "priv", "try", "typeof", "unsized", "virtual", "yield", "main", "_",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backend::{backend_by_name, Cgi};
+    use rust_functional::{InstructionParameter, Method};
+    use test_util::{add_endpoint, adder};
+
+    fn builder(backend: Box<dyn Backend>) -> Builder {
+        let adder = adder();
+        let mut builder = Builder::default();
+        builder.set_backend(backend);
+        builder.add_module(adder.clone());
+        builder.add_endpoint(add_endpoint(&adder, "/add/{x}"));
+        builder
+    }
+
+    #[test]
+    fn scopes_share_their_prefix() {
+        let adder = adder();
+        let mut builder = Builder::default();
+        builder.add_module(adder.clone());
+        for endpoint in [add_endpoint(&adder, "/add/{x}"), EndPoint::new("Add one", "/one")] {
+            let mut scope = Scope::new("/api");
+            scope.add_endpoint(endpoint);
+            builder.add_scope(scope);
+        }
+        builder.add_endpoint(EndPoint::new("health", "/health"));
+        let files = builder.build().unwrap();
+
+        let lib = &files["src/lib.rs"];
+        assert_eq!(1, lib.matches(".scope(").count());
+        let scope = lib.find(".scope(\"/api\", |scope| {").unwrap();
+        let end = scope + lib[scope..].find("        })\n").unwrap();
+        let add = lib.find(".route(\"/add/{x}\", actix_web::http::Method::GET, add)").unwrap();
+        let one = lib.find(".route(\"/one\", actix_web::http::Method::GET, add_one)").unwrap();
+        assert!(scope < add && add < one && one < end);
+        assert!(lib[end..].contains(".route(\"/health\", actix_web::http::Method::GET, health)"));
+        let openapi: Value = ::serde_json::from_str(&files["openapi.json"]).unwrap();
+        let paths = openapi["paths"].as_object().unwrap();
+        let mut paths = paths.keys().collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(vec!["/api/add/{x}", "/api/one", "/health"], paths);
+    }
+
+    #[test]
+    fn routes_and_handler_names_are_unique() {
+        let validate = |endpoints: Vec<(&str, EndPoint)>| {
+            let mut builder = Builder::default();
+            for (prefix, endpoint) in endpoints {
+                let mut scope = Scope::new(prefix);
+                scope.add_endpoint(endpoint);
+                builder.add_scope(scope);
+            }
+            builder.validate()
+        };
+        match validate(vec![
+            ("", EndPoint::new("one", "/api/one")),
+            ("/api", EndPoint::new("two", "/one")),
+        ]) {
+            Err(BuilderError::DuplicateRoute(ref url)) if url == "/api/one" => {}
+            result => panic!("Expected DuplicateRoute, got {:?}", result),
+        }
+        match validate(vec![("", EndPoint::new("spec", "/openapi.json"))]) {
+            Err(BuilderError::DuplicateRoute(ref url)) if url == "/openapi.json" => {}
+            result => panic!("Expected DuplicateRoute, got {:?}", result),
+        }
+        match validate(vec![
+            ("", EndPoint::new("Add one", "/a")),
+            ("/api", EndPoint::new("add-one", "/a")),
+        ]) {
+            Err(BuilderError::DuplicateHandler(ref name)) if name == "add_one" => {}
+            result => panic!("Expected DuplicateHandler, got {:?}", result),
+        }
+        // The functions that the backends generate next to the handlers are taken
+        match validate(vec![("", EndPoint::new("OpenAPI", "/spec"))]) {
+            Err(BuilderError::DuplicateHandler(ref name)) if name == "openapi" => {}
+            result => panic!("Expected DuplicateHandler, got {:?}", result),
+        }
+        assert!(validate(vec![
+            ("/v1", EndPoint::new("add", "/add")),
+            ("/v2", EndPoint::new("add 2", "/add")),
+        ]).is_ok());
+    }
+
+    #[test]
+    fn handler_names_are_identifiers() {
+        let handler_name = |name: &str| EndPoint::new(name, "/").handler_name();
+        assert_eq!("get_user_by_id", handler_name("Get user by ID"));
+        assert_eq!("add_one", handler_name("  add -- one! "));
+        assert_eq!("caf_au_lait", handler_name("café au lait"));
+        assert_eq!("_2fa", handler_name("2FA"));
+        assert_eq!("__", handler_name("?"));
+        assert_eq!("match_", handler_name("Match"));
+    }
+
+    #[test]
+    fn async_methods_need_an_async_backend() {
+        let mut adder = Config::from_path("../modules/adder");
+        adder.methods[0].is_async = true;
+        let adder = Rc::new(adder);
+        let mut builder = Builder::default();
+        builder.add_module(adder.clone());
+        builder.add_endpoint(add_endpoint(&adder, "/add/{x}"));
+        match builder.validate() {
+            Err(BuilderError::AsyncMethod(ref name)) if name == "add" => {}
+            result => panic!("Expected AsyncMethod, got {:?}", result),
+        }
+
+        builder.set_backend(backend_by_name("actix_async").unwrap());
+        let files = builder.build().unwrap();
+        let lib = &files["src/lib.rs"];
+        assert!(lib.contains("\nasync fn add(req: actix_web::HttpRequest)"));
+        assert!(lib.contains("let sum = adder::add(1, &x).await;"));
+        assert!(lib.contains(".route(\"/add/{x}\", actix_web::web::get().to(add))"));
+        assert!(files["Cargo.toml"].contains("edition = \"2018\""));
+        assert!(files["src/main.rs"].contains("#[actix_web::main]"));
+    }
+
+    #[test]
+    fn every_handler_has_its_own_trace() {
+        for name in &["actix", "actix_async"] {
+            let mut builder = builder(backend_by_name(name).unwrap());
+            builder.set_trace(true);
+            let files = builder.build().unwrap();
+            let lib = &files["src/lib.rs"];
+            assert!(lib.contains("let mut trace_calls = trace::Trace::default();"));
+            assert!(lib.contains("trace_calls.record("));
+            assert!(lib.contains("trace_calls.into_json()"));
+            assert!(!lib.contains("thread_local!"));
+        }
+    }
+
+    #[test]
+    fn backends_that_cannot_send_the_trace_are_rejected() {
+        let mut builder = builder(Box::new(Cgi));
+        assert!(builder.validate().is_ok());
+        builder.set_trace(true);
+        match builder.build() {
+            Err(BuilderError::TraceUnsupported("cgi")) => {}
+            result => panic!("Expected TraceUnsupported, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn only_actix_handlers_pass_the_request() {
+        let helper = Rc::new(Config::from_path("../modules/actix_web_helper"));
+        let mut endpoint = EndPoint::new("name", "/name/{name}");
+        endpoint.add_base_instruction(BaseInstruction::CallModule {
+            config: helper.clone(),
+            method: "get_property".to_string(),
+            parameters: vec![
+                ("req".to_string(), InstructionParameter::Variable("req".to_string())),
+                ("field".to_string(), InstructionParameter::String("name".to_string())),
+            ],
+            out_variable_name: "name".to_string(),
+        });
+        let mut builder = builder(Box::new(Cgi));
+        builder.add_module(helper);
+        builder.add_endpoint(endpoint);
+        match builder.validate() {
+            Err(BuilderError::HttpRequestUnsupported(ref name, "cgi")) if name == "name" => {}
+            result => panic!("Expected HttpRequestUnsupported, got {:?}", result),
+        }
+        builder.set_backend(Box::new(Actix));
+        assert!(builder.validate().is_ok());
+    }
+
+    #[test]
+    fn modules_mock_all_methods_or_none() {
+        let mut builder = builder(Box::new(Actix));
+        builder.set_generate_tests(true);
+        let lib = &builder.build().unwrap()["src/lib.rs"];
+        assert!(lib.contains("extern crate adder;\n"));
+        assert!(!lib.contains("mod adder"));
+
+        let mut adder = Config::from_path("../modules/adder");
+        adder.methods[0].mock = Some(3.into());
+        adder.methods.push(Method {
+            name: "subtract".to_string(),
+            ..Method::default()
+        });
+        let adder = Rc::new(adder);
+        let mut builder = Builder::default();
+        builder.set_generate_tests(true);
+        builder.add_module(adder.clone());
+        builder.add_endpoint(add_endpoint(&adder, "/add/{x}"));
+        match builder.build() {
+            Err(BuilderError::PartialMock(ref module, ref method))
+                if module == "adder" && method == "subtract" => {}
+            result => panic!("Expected PartialMock, got {:?}", result),
+        }
+
+        let mut adder = Config::from_path("../modules/adder");
+        adder.methods[0].mock = Some(3.into());
+        let adder = Rc::new(adder);
+        let mut builder = Builder::default();
+        builder.set_generate_tests(true);
+        builder.add_module(adder.clone());
+        builder.add_endpoint(add_endpoint(&adder, "/add/{x}"));
+        let lib = &builder.build().unwrap()["src/lib.rs"];
+        assert!(lib.contains(
+            "#[cfg(feature = \"mock\")]\nmod adder {\n    pub use adder_real::*;\n\n    \
+             pub fn add<T0, T1>(_a: T0, _b: T1) -> i32 {\n        3\n    }\n}\n"
+        ));
+    }
+}
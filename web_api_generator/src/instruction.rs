@@ -1,4 +1,8 @@
-use rust_functional::{Instruction as BaseInstruction, InstructionParameter};
+use backend::Backend;
+use rust_functional::{
+    Instruction as BaseInstruction, InstructionParameter, InterpretError, Outcome, ParameterType,
+    Run,
+};
 
 #[derive(Debug)]
 pub enum Instruction {
@@ -7,12 +11,54 @@ pub enum Instruction {
 }
 
 impl Instruction {
-    pub fn build(&self) -> String {
+    pub fn build(&self, backend: &dyn Backend) -> String {
         match self {
             Instruction::BaseInstruction(bi) => bi.build(),
-            Instruction::Json(param) => {
-                format!("    actix_web::Json({})\n", param.to_string(false))
+            Instruction::Json(param) => backend.json(&param.to_string(false)),
+        }
+    }
+
+    /// The generated code of each argument of this instruction, by name, in the order they are generated
+    pub fn arguments(&self) -> Vec<(String, String)> {
+        match self {
+            Instruction::BaseInstruction(bi) => bi.arguments(),
+            Instruction::Json(param) => vec![("value".to_string(), param.to_string(false))],
+        }
+    }
+
+    /// Run this instruction with the interpreter. A `Json` instruction returns its value.
+    pub fn interpret(&self, run: &mut Run) -> Result<Option<Outcome>, InterpretError> {
+        match self {
+            Instruction::BaseInstruction(bi) => run.step(bi),
+            Instruction::Json(param) => Ok(Some(Outcome::Return(run.value(param)?))),
+        }
+    }
+
+    pub fn is_async(&self) -> bool {
+        match self {
+            Instruction::BaseInstruction(bi) => bi.is_async(),
+            Instruction::Json(_) => false,
+        }
+    }
+
+    /// Whether this instruction calls a method that takes an `actix_web::HttpRequest`,
+    /// see `Backend::has_http_request`
+    pub fn takes_http_request(&self) -> bool {
+        match self {
+            Instruction::BaseInstruction(BaseInstruction::CallModule { config, method, .. }) => {
+                config
+                    .methods
+                    .iter()
+                    .filter(|m| &m.name == method)
+                    .flat_map(|m| &m.input)
+                    .any(|input| match &input.value_type {
+                        ParameterType::Object(path) => {
+                            path.trim_start_matches("::") == "actix_web::HttpRequest"
+                        }
+                        _ => false,
+                    })
             }
+            _ => false,
         }
     }
 }
@@ -1,8 +1,27 @@
+extern crate flate2;
 extern crate rust_functional;
+#[macro_use]
+extern crate serde_json;
+extern crate tar;
+extern crate zip;
 
+mod archive;
+mod backend;
 mod builder;
 mod instruction;
+mod openapi;
+mod source_map;
+mod test_generator;
+#[cfg(test)]
+mod test_util;
+mod trace;
 
-pub use builder::{Builder, EndPoint};
+pub use archive::ArchiveFormat;
+pub use backend::{backend_by_name, backend_names, Actix, ActixAsync, Backend, Cgi};
+pub use builder::{Builder, BuilderError, EndPoint, Scope};
 pub use instruction::Instruction;
-pub use rust_functional::{Config, Instruction as BaseInstruction, InstructionParameter};
+pub use rust_functional::{
+    Config, Instruction as BaseInstruction, InstructionParameter, InterpretError, Interpreter,
+    Outcome, Run, TraceEntry,
+};
+pub use source_map::{ArgumentSpan, SourceMap, SourceMapEntry};
@@ -1,7 +1,22 @@
+#![cfg_attr(debug_assertions, allow(dead_code))]
+
+extern crate flate2;
 extern crate rust_functional;
+#[macro_use]
+extern crate serde_json;
+extern crate tar;
+extern crate zip;
 
+mod archive;
+mod backend;
 mod builder;
 mod instruction;
+mod openapi;
+mod source_map;
+mod test_generator;
+#[cfg(test)]
+mod test_util;
+mod trace;
 
 use builder::{Builder, EndPoint};
 use instruction::Instruction;
@@ -14,6 +29,7 @@ use std::rc::Rc;
 fn main() {
     let postgres = Rc::new(Config::from_path("modules/postgres"));
     let mut builder = Builder::default();
+    builder.set_generate_tests(true);
     builder.add_module(postgres.clone());
     builder.add_endpoint({
         let mut endpoint = EndPoint::new("user_list", "/api/users/list");
@@ -44,9 +60,10 @@ fn main() {
         endpoint
     });
 
-    let files = builder.build();
+    let files = builder.build().unwrap();
 
     create_dir_all("output/src").unwrap();
+    create_dir_all("output/tests").unwrap();
     for (name, content) in files {
         File::create(format!("output/{}", name))
             .unwrap()
@@ -0,0 +1,236 @@
+use builder::EndPoint;
+use instruction::Instruction;
+use rust_functional::{
+    Config, Instruction as BaseInstruction, InstructionParameter, NumericConstraint,
+    ParameterType, StringConstraint,
+};
+use serde_json::{Map, Value};
+use std::rc::Rc;
+
+pub fn generate(modules: &[Rc<Config>], routes: &[(String, &EndPoint)]) -> Value {
+    let mut paths = Map::new();
+    for (url, endpoint) in routes {
+        let mut operation = Map::new();
+        operation.insert("operationId".to_string(), json!(endpoint.name));
+        operation.insert(
+            "parameters".to_string(),
+            Value::Array(path_parameters(url, endpoint)),
+        );
+        operation.insert(
+            "responses".to_string(),
+            json!({
+                "200": {
+                    "description": "Successful response",
+                    "content": {
+                        "application/json": {
+                            "schema": response_schema(endpoint)
+                        }
+                    }
+                }
+            }),
+        );
+        let mut path = Map::new();
+        path.insert("get".to_string(), Value::Object(operation));
+        paths.insert(url.clone(), Value::Object(path));
+    }
+
+    json!({
+        "openapi": "3.0.0",
+        "info": {
+            "title": "test",
+            "description": modules
+                .iter()
+                .map(|m| m.description.as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+            "version": "0.1.0"
+        },
+        "paths": paths
+    })
+}
+
+/// Get the names of the `{name}` segments in the given url
+pub fn url_parameters(url: &str) -> Vec<&str> {
+    url.split('/')
+        .filter(|s| s.starts_with('{') && s.ends_with('}'))
+        .map(|s| &s[1..s.len() - 1])
+        .collect()
+}
+
+pub fn path_parameters(url: &str, endpoint: &EndPoint) -> Vec<Value> {
+    url_parameters(url)
+        .into_iter()
+        .map(|name| {
+            json!({
+                "name": name,
+                "in": "path",
+                "required": true,
+                "schema": find_variable_type(endpoint, name)
+                    .map(schema_for_type)
+                    .unwrap_or_else(|| json!({ "type": "string" }))
+            })
+        })
+        .collect()
+}
+
+/// Find the type of a variable by looking at the module inputs it is passed to
+fn find_variable_type<'a>(endpoint: &'a EndPoint, name: &str) -> Option<&'a ParameterType> {
+    for instruction in &endpoint.instructions {
+        if let Instruction::BaseInstruction(BaseInstruction::CallModule {
+            config,
+            method,
+            parameters,
+            ..
+        }) = instruction
+        {
+            let method = match config.methods.iter().find(|m| &m.name == method) {
+                Some(m) => m,
+                None => continue,
+            };
+            for (parameter_name, value) in parameters {
+                if let InstructionParameter::Variable(variable) = value {
+                    if variable != name {
+                        continue;
+                    }
+                    if let Some(input) = method.input.iter().find(|i| &i.name == parameter_name) {
+                        return Some(&input.value_type);
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+pub fn response_schema(endpoint: &EndPoint) -> Value {
+    let param = endpoint.instructions.iter().rev().find_map(|i| match i {
+        Instruction::Json(param) => Some(param),
+        _ => None,
+    });
+    match param {
+        Some(InstructionParameter::String(_)) => json!({ "type": "string" }),
+        Some(InstructionParameter::Number(_)) => json!({ "type": "integer" }),
+        Some(InstructionParameter::Float(_)) => json!({ "type": "number" }),
+        Some(InstructionParameter::Variable(name)) => find_output_type(endpoint, name)
+            .map(schema_for_type)
+            .unwrap_or_else(|| json!({})),
+        None => json!({}),
+    }
+}
+
+/// Find the type of a variable by looking at the module call that assigned it
+fn find_output_type<'a>(endpoint: &'a EndPoint, name: &str) -> Option<&'a ParameterType> {
+    endpoint.instructions.iter().rev().find_map(|i| match i {
+        Instruction::BaseInstruction(BaseInstruction::CallModule {
+            config,
+            method,
+            out_variable_name,
+            ..
+        }) if out_variable_name == name => config
+            .methods
+            .iter()
+            .find(|m| &m.name == method)
+            .and_then(|m| m.output.first())
+            .map(|o| &o.value_type),
+        _ => None,
+    })
+}
+
+pub fn schema_for_type(value_type: &ParameterType) -> Value {
+    match value_type {
+        ParameterType::Numeric(NumericConstraint::NoConstraint) => json!({ "type": "integer" }),
+        ParameterType::Numeric(NumericConstraint::IntegerRange { from, to }) => json!({
+            "type": "integer",
+            "minimum": from,
+            "maximum": to
+        }),
+        ParameterType::Numeric(NumericConstraint::DecimalRange { from, to }) => json!({
+            "type": "number",
+            "minimum": from,
+            "maximum": to
+        }),
+        ParameterType::Numeric(NumericConstraint::IntegerList(list)) => json!({
+            "type": "integer",
+            "enum": list
+        }),
+        ParameterType::String(StringConstraint::NoConstraint) => json!({ "type": "string" }),
+        ParameterType::String(StringConstraint::Regex(regex)) => json!({
+            "type": "string",
+            "pattern": regex.as_str()
+        }),
+        ParameterType::String(StringConstraint::StringList(list)) => json!({
+            "type": "string",
+            "enum": list
+        }),
+        ParameterType::Object(path) if path.starts_with("::Vec<") => json!({
+            "type": "array",
+            "items": {},
+            "x-rust-type": path
+        }),
+        ParameterType::Object(path) => json!({
+            "type": "object",
+            "x-rust-type": path
+        }),
+        ParameterType::Trait(_) | ParameterType::Unknown => json!({}),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_util::{add_endpoint, adder};
+
+    #[test]
+    fn types_come_from_the_module_calls() {
+        let adder = adder();
+        let endpoint = add_endpoint(&adder, "/add/{x}/{name}");
+        let routes = vec![("/api/add/{x}/{name}".to_string(), &endpoint)];
+        let document = generate(&[adder], &routes);
+
+        assert_eq!("3.0.0", document["openapi"]);
+        assert_eq!("Module with several adding methods", document["info"]["description"]);
+        let operation = &document["paths"]["/api/add/{x}/{name}"]["get"];
+        assert_eq!("add", operation["operationId"]);
+        assert_eq!(
+            json!([
+                {
+                    "name": "x",
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "integer", "minimum": 1, "maximum": 100 }
+                },
+                {
+                    "name": "name",
+                    "in": "path",
+                    "required": true,
+                    "schema": { "type": "string" }
+                }
+            ]),
+            operation["parameters"]
+        );
+        assert_eq!(
+            json!({ "type": "integer" }),
+            operation["responses"]["200"]["content"]["application/json"]["schema"]
+        );
+    }
+
+    #[test]
+    fn literal_responses() {
+        let response = |value| {
+            let mut endpoint = EndPoint::new("literal", "/literal");
+            endpoint.add_instruction(Instruction::Json(value));
+            response_schema(&endpoint)
+        };
+        assert_eq!(
+            json!({ "type": "string" }),
+            response(InstructionParameter::String("hello".to_string()))
+        );
+        assert_eq!(json!({ "type": "integer" }), response(InstructionParameter::Number(1)));
+        assert_eq!(json!({ "type": "number" }), response(InstructionParameter::Float(1.5)));
+        // The type of a variable that no module call assigns is unknown
+        assert_eq!(
+            json!({}),
+            response(InstructionParameter::Variable("missing".to_string()))
+        );
+    }
+}
@@ -0,0 +1,92 @@
+/// Maps lines of the generated code back to the instruction they were generated from
+#[derive(Debug, Default, Clone)]
+pub struct SourceMap {
+    entries: Vec<SourceMapEntry>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SourceMapEntry {
+    /// The generated file, relative to the crate root
+    pub file: String,
+    /// The first and last generated line of the instruction, starting at 1
+    pub line_start: usize,
+    pub line_end: usize,
+    /// The columns of the first line that contain the instruction, starting at 1. `column_end` is exclusive.
+    pub column_start: usize,
+    pub column_end: usize,
+    /// The name of the `EndPoint` the instruction belongs to
+    pub endpoint: String,
+    /// The index of the instruction in `EndPoint::instructions`
+    pub instruction: usize,
+    pub arguments: Vec<ArgumentSpan>,
+}
+
+/// The location of a single argument of an instruction
+#[derive(Debug, Clone)]
+pub struct ArgumentSpan {
+    pub name: String,
+    pub line: usize,
+    pub column_start: usize,
+    pub column_end: usize,
+}
+
+impl SourceMapEntry {
+    /// Find the argument at the given position, if any
+    pub fn argument_at(&self, line: usize, column: usize) -> Option<&ArgumentSpan> {
+        self.arguments.iter().find(|a| {
+            a.line == line && a.column_start <= column && column < a.column_end
+        })
+    }
+}
+
+impl SourceMap {
+    pub fn add(&mut self, entry: SourceMapEntry) {
+        self.entries.push(entry);
+    }
+
+    pub fn entries(&self) -> &[SourceMapEntry] {
+        &self.entries
+    }
+
+    /// Find the instruction that generated the given line
+    pub fn lookup(&self, file: &str, line: usize) -> Option<&SourceMapEntry> {
+        self.entries
+            .iter()
+            .find(|e| e.file == file && e.line_start <= line && line <= e.line_end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use builder::Builder;
+    use test_util::{add_endpoint, adder};
+
+    #[test]
+    fn lines_map_back_to_instructions() {
+        let adder = adder();
+        let mut builder = Builder::default();
+        builder.add_module(adder.clone());
+        builder.add_endpoint(add_endpoint(&adder, "/add/{x}"));
+        let (files, source_map) = builder.build_with_source_map().unwrap();
+        let lines = files["src/lib.rs"].lines().collect::<Vec<_>>();
+        let line_of = |text: &str| 1 + lines.iter().position(|l| l.contains(text)).unwrap();
+
+        let line = line_of("adder::add(");
+        let code = lines[line - 1];
+        let column = |text: &str| 1 + code.find(text).unwrap();
+        let entry = source_map.lookup("src/lib.rs", line).unwrap();
+        assert_eq!("add", entry.endpoint);
+        assert_eq!(0, entry.instruction);
+        assert_eq!(column("let sum"), entry.column_start);
+        assert_eq!("a", entry.argument_at(line, column("1, ")).unwrap().name);
+        assert_eq!("b", entry.argument_at(line, column("&x")).unwrap().name);
+        assert!(entry.argument_at(line, column("adder")).is_none());
+        assert!(entry.argument_at(line + 1, column("1, ")).is_none());
+
+        let entry = source_map.lookup("src/lib.rs", line_of("Json(sum)")).unwrap();
+        assert_eq!(1, entry.instruction);
+        assert!(source_map.lookup("src/lib.rs", 1).is_none());
+        assert!(source_map.lookup("src/main.rs", line).is_none());
+        assert_eq!(2, source_map.entries().len());
+    }
+}
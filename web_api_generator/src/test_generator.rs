@@ -0,0 +1,135 @@
+use backend::Backend;
+use builder::EndPoint;
+use openapi;
+use rust_functional::{Config, Method, ParameterType};
+use serde_json::Value;
+
+/// Import a module into the generated crate.
+/// When its methods have mocks, the module is replaced by a shim with the `mock` feature enabled
+pub fn module_import(module: &Config) -> String {
+    let mocked = module
+        .methods
+        .iter()
+        .filter(|m| m.mock.is_some())
+        .collect::<Vec<_>>();
+    if mocked.is_empty() {
+        return format!("extern crate {};\n", module.name);
+    }
+    let mut result = format!(
+        r#"#[cfg(not(feature = "mock"))]
+extern crate {name};
+#[cfg(feature = "mock")]
+extern crate {name} as {name}_real;
+#[cfg(feature = "mock")]
+mod {name} {{
+    pub use {name}_real::*;
+"#,
+        name = module.name
+    );
+    for method in mocked {
+        result += &mock_function(method);
+    }
+    result += "}\n";
+    result
+}
+
+fn mock_function(method: &Method) -> String {
+    let generics = (0..method.input.len())
+        .map(|i| format!("T{}", i))
+        .collect::<Vec<_>>();
+    let arguments = method
+        .input
+        .iter()
+        .enumerate()
+        .map(|(i, input)| format!("_{}: T{}", input.name, i))
+        .collect::<Vec<_>>();
+    let (return_type, value) = mock_value(
+        method.output.first().map(|o| &o.value_type),
+        method.mock.as_ref().unwrap(),
+    );
+    format!(
+        "\n    pub fn {}{}({}){} {{\n        {}\n    }}\n",
+        method.name,
+        if generics.is_empty() {
+            String::new()
+        } else {
+            format!("<{}>", generics.join(", "))
+        },
+        arguments.join(", "),
+        return_type,
+        value
+    )
+}
+
+fn mock_value(value_type: Option<&ParameterType>, mock: &Value) -> (String, String) {
+    match (value_type, mock) {
+        (None, _) => (String::new(), String::new()),
+        (Some(ParameterType::Numeric(_)), Value::Number(n)) if n.is_i64() => {
+            (" -> i32".to_string(), n.to_string())
+        }
+        (Some(ParameterType::Numeric(_)), Value::Number(n)) => {
+            (" -> f32".to_string(), format!("{}f32", n))
+        }
+        (Some(ParameterType::String(_)), Value::String(s)) => {
+            (" -> String".to_string(), format!("String::from({:?})", s))
+        }
+        (Some(_), value) => (
+            " -> ::serde_json::Value".to_string(),
+            format!(
+                "::serde_json::from_str({:?}).unwrap()",
+                ::serde_json::to_string(value).unwrap()
+            ),
+        ),
+    }
+}
+
+pub fn generate(backend: &dyn Backend, routes: &[(String, &EndPoint)]) -> String {
+    let mut result = backend.test_header().to_string();
+    for (url, endpoint) in routes {
+        result += &backend.test_start(&endpoint.handler_name(), &example_url(url, endpoint));
+        result += &shape_assertion(&openapi::response_schema(endpoint));
+        result += "}\n";
+    }
+    result
+}
+
+/// Fill the `{name}` segments of the endpoint url with a value matching their schema
+fn example_url(url: &str, endpoint: &EndPoint) -> String {
+    let mut result = url.to_string();
+    for parameter in openapi::path_parameters(url, endpoint) {
+        let schema = &parameter["schema"];
+        let value = if let Some(first) = schema["enum"].get(0) {
+            match first {
+                Value::String(s) => s.clone(),
+                x => x.to_string(),
+            }
+        } else if schema["type"] == "integer" || schema["type"] == "number" {
+            schema["minimum"]
+                .as_f64()
+                .map(|m| m.to_string())
+                .unwrap_or_else(|| "1".to_string())
+        } else {
+            "test".to_string()
+        };
+        result = result.replace(
+            &format!("{{{}}}", parameter["name"].as_str().unwrap()),
+            &value,
+        );
+    }
+    result
+}
+
+fn shape_assertion(schema: &Value) -> String {
+    let check = match schema["type"].as_str() {
+        Some("integer") => "is_i64",
+        Some("number") => "is_number",
+        Some("string") => "is_string",
+        Some("array") => "is_array",
+        Some("object") => "is_object",
+        _ => return String::new(),
+    };
+    format!(
+        "    assert!(json.{}(), \"Unexpected response {{}}\", json);\n",
+        check
+    )
+}
@@ -0,0 +1,29 @@
+//! Endpoints shared by the tests of the generator
+
+use builder::EndPoint;
+use instruction::Instruction;
+use rust_functional::{Config, Instruction as BaseInstruction, InstructionParameter};
+use std::rc::Rc;
+
+/// The `adder` module in `modules/adder`
+pub fn adder() -> Rc<Config> {
+    Rc::new(Config::from_path("../modules/adder"))
+}
+
+/// An endpoint named `add` on `url`, which returns `1 + x` for a path parameter `x`
+pub fn add_endpoint(adder: &Rc<Config>, url: &str) -> EndPoint {
+    let mut endpoint = EndPoint::new("add", url);
+    endpoint.add_base_instruction(BaseInstruction::CallModule {
+        config: adder.clone(),
+        method: "add".to_string(),
+        parameters: vec![
+            ("a".to_string(), InstructionParameter::Number(1)),
+            ("b".to_string(), InstructionParameter::Variable("x".to_string())),
+        ],
+        out_variable_name: "sum".to_string(),
+    });
+    endpoint.add_instruction(Instruction::Json(InstructionParameter::Variable(
+        "sum".to_string(),
+    )));
+    endpoint
+}
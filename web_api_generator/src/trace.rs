@@ -0,0 +1,156 @@
+use rust_functional::{Instruction as BaseInstruction, InstructionParameter};
+
+/// The `trace` module of the generated `src/lib.rs`, only compiled with the `trace` feature.
+/// Every handler records its module calls in its own `Trace`, which the backend sends back with
+/// the response. Values that do not implement `Serialize` are recorded as `null`.
+pub const MODULE: &str = r#"
+#[cfg(feature = "trace")]
+#[macro_use]
+pub mod trace {
+    extern crate serde;
+    extern crate serde_json;
+
+    pub use self::serde_json::Value;
+    use self::serde_json::Map;
+    use std::time::Instant;
+
+    pub struct Wrap<'a, T: 'a>(pub &'a T);
+
+    pub trait Serializable {
+        fn trace_value(&self) -> Value;
+    }
+
+    impl<'a, T: serde::Serialize> Serializable for Wrap<'a, T> {
+        fn trace_value(&self) -> Value {
+            serde_json::to_value(self.0).unwrap_or(Value::Null)
+        }
+    }
+
+    /// Picked by `trace_value!` when `Serializable` is not implemented, as it needs an extra `&`
+    pub trait Unserializable {
+        fn trace_value(&self) -> Value;
+    }
+
+    impl<'a, 'b, T> Unserializable for &'b Wrap<'a, T> {
+        fn trace_value(&self) -> Value {
+            Value::Null
+        }
+    }
+
+    macro_rules! trace_value {
+        ($value:expr) => {{
+            #[allow(unused_imports)]
+            use $crate::trace::{Serializable, Unserializable};
+            (&$crate::trace::Wrap(&$value)).trace_value()
+        }};
+    }
+
+    /// The module calls made while handling a single request
+    #[derive(Default)]
+    pub struct Trace {
+        calls: Vec<Value>,
+    }
+
+    impl Trace {
+        pub fn record(
+            &mut self,
+            instruction: usize,
+            module: &str,
+            method: &str,
+            started: Instant,
+            inputs: Vec<(&str, Value)>,
+            output: Value,
+        ) {
+            let elapsed = started.elapsed();
+            let inputs = inputs
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value))
+                .collect::<Map<_, _>>();
+            let mut call = Map::new();
+            call.insert("instruction".to_string(), Value::from(instruction));
+            call.insert("module".to_string(), Value::from(module));
+            call.insert("method".to_string(), Value::from(method));
+            call.insert("inputs".to_string(), Value::Object(inputs));
+            call.insert("output".to_string(), output);
+            call.insert(
+                "duration_us".to_string(),
+                Value::from(elapsed.as_secs() * 1_000_000 + u64::from(elapsed.subsec_micros())),
+            );
+            self.calls.push(Value::Object(call));
+        }
+
+        /// The recorded calls as a JSON array.
+        /// Non-ASCII characters are escaped, so it can be sent as a header.
+        pub fn into_json(self) -> String {
+            let json = Value::Array(self.calls).to_string();
+            let mut result = String::with_capacity(json.len());
+            for c in json.chars() {
+                if c.is_ascii() {
+                    result.push(c);
+                } else {
+                    for unit in c.encode_utf16(&mut [0; 2]).iter() {
+                        result += &format!("\\u{:04x}", unit);
+                    }
+                }
+            }
+            result
+        }
+    }
+}
+"#;
+
+/// The lines at the start of every handler, which create the `Trace` of the request.
+/// Backends send it with the response in `Backend::traced_json`.
+pub const CREATE: &str = "    #[cfg(feature = \"trace\")]
+    #[allow(unused_mut, unused_variables)]
+    let mut trace_calls = trace::Trace::default();
+";
+
+/// The line before an instruction that calls a module, which starts timing the call
+pub const START: &str =
+    "    #[cfg(feature = \"trace\")]\n    let trace_started = ::std::time::Instant::now();\n";
+
+/// The lines after an instruction that calls a module, which record the call
+pub fn record(index: usize, instruction: &BaseInstruction) -> String {
+    let (config, method, parameters, out_variable_name) = match instruction {
+        BaseInstruction::CallModule {
+            config,
+            method,
+            parameters,
+            out_variable_name,
+        } => (config, method, parameters, out_variable_name),
+        _ => return String::new(),
+    };
+    let inputs = parameters
+        .iter()
+        .map(|(name, parameter)| format!("({:?}, {})", name, value(parameter)))
+        .collect::<Vec<_>>();
+    format!(
+        "    #[cfg(feature = \"trace\")]
+    trace_calls.record(
+        {},
+        {:?},
+        {:?},
+        trace_started,
+        vec![{}],
+        trace_value!({}),
+    );
+",
+        index,
+        config.name,
+        method,
+        inputs.join(", "),
+        out_variable_name
+    )
+}
+
+/// The JSON value of a parameter. Literals are converted directly, so their type does not
+/// need to be inferred.
+fn value(parameter: &InstructionParameter) -> String {
+    match parameter {
+        InstructionParameter::Variable(name) => format!("trace_value!({})", name),
+        InstructionParameter::String(value) => format!("trace::Value::from({:?})", value),
+        InstructionParameter::Number(value) => format!("trace::Value::from({})", value),
+        InstructionParameter::Float(value) => format!("trace::Value::from({}f64)", value),
+    }
+}